@@ -1,11 +1,137 @@
+use std::cmp;
 use super::*;
 
+mod camera;
+mod huc1;
+mod huc3;
+mod infrared;
+mod mbc1;
+mod mbc2;
+mod mbc3;
+mod mbc5;
+mod mbc6;
+mod mbc7;
+mod mmm01;
+mod rtc;
+mod tama5;
+
+pub use self::camera::{PocketCamera, CAMERA_WIDTH, CAMERA_HEIGHT};
+pub use self::huc1::HuC1;
+pub use self::huc3::{HuC3, HUC3_RTC_SIZE_BYTES};
+pub use self::infrared::*;
+pub use self::mbc1::Mbc1;
+pub use self::mbc2::{Mbc2, MBC2_RAM_SIZE};
+pub use self::mbc3::Mbc3;
+pub use self::mbc5::Mbc5;
+pub use self::mbc6::{Mbc6, MBC6_FLASH_SIZE_BYTES, MBC6_RAM_SIZE_BYTES};
+pub use self::mbc7::{Mbc7, MBC7_EEPROM_SIZE_BYTES, ACCELEROMETER_CENTRE, ACCELEROMETER_GRAVITY};
+pub use self::mmm01::Mmm01;
+pub use self::rtc::*;
+pub use self::tama5::{Tama5, TAMA5_RAM_SIZE_BYTES, TAMA5_RTC_SIZE_BYTES};
+
+/// Callback run when a cartridge's rumble motor starts or stops.
+pub type RumbleCallback = Box<dyn FnMut(bool)>;
+
+/// Size of a single switchable bank of cartridge ROM in bytes.
+pub const ROM_BANK_SIZE_BYTES: usize = 0x4000;
+/// Size of a single switchable bank of cartridge RAM in bytes.
+pub const RAM_BANK_SIZE_BYTES: usize = 0x2000;
+
 /// Generic interface for all gameboy cartridges.
 pub trait Cartridge {
     fn read_byte(&self, address: Address) -> u8;
     fn write_byte(&mut self, address: Address, value: u8);
-    fn read_word(&self, address: Address) -> u16;
-    fn write_word(&self, address: Address, value: u16);
+
+    fn read_word(&self, address: Address) -> u16 {
+        make_u16(self.read_byte(address.wrapping_add(1)), self.read_byte(address))
+    }
+
+    fn write_word(&self, _address: Address, _value: u16) {
+        // Words are never written to cartridges by the CPU
+    }
+
+    /// Advance any hardware on the cartridge by the given number of cycles at
+    /// normal speed. Cartridge clocks keep real time, so they are not sped
+    /// up by CGB double speed mode.
+    fn tick(&mut self, _cycles: Cycles) {
+        // Most cartridges have nothing to clock
+    }
+
+    /// Indicates whether the cartridge's rumble motor is currently running.
+    /// Games pulse the motor faster than once a frame to vary its strength,
+    /// so hosts driving a motor should use `connect_rumble` instead.
+    fn rumble(&self) -> bool {
+        false
+    }
+
+    /// Register a callback run whenever the cartridge's rumble motor starts
+    /// or stops, with true when it starts. Only cartridges with a rumble
+    /// motor use this.
+    fn connect_rumble(&mut self, _callback: RumbleCallback) {
+        // Most cartridges have no rumble motor
+    }
+
+    /// Tilt the cartridge by the given acceleration in g along each axis,
+    /// positive X to the right and positive Y towards the bottom of the
+    /// screen. Only cartridges with an accelerometer use this.
+    fn set_accelerometer(&mut self, _x: f32, _y: f32) {
+        // Most cartridges have no accelerometer
+    }
+
+    /// Connect the cartridge's infrared LED and receiver, which start out
+    /// connected to nothing. Only cartridges with an infrared port use this.
+    fn connect_infrared(&mut self, _infrared: Box<dyn Infrared>) {
+        // Most cartridges have no infrared port
+    }
+
+    /// Tone last played on the cartridge's speaker, if any, numbered as the
+    /// game selected it. There's no APU yet, so the host should poll this and
+    /// play the tone itself. Only cartridges with a speaker use this.
+    fn speaker_tone(&self) -> Option<u8> {
+        None
+    }
+
+    /// Supply the image seen by the cartridge's camera sensor, as
+    /// `CAMERA_WIDTH` x `CAMERA_HEIGHT` grayscale pixels in rows from the
+    /// top left, 0 black and 255 white. Only camera cartridges use this.
+    fn set_camera_image(&mut self, _pixels: &[u8]) {
+        // Most cartridges have no camera
+    }
+
+    /// Indicates whether the cartridge has a battery, or other memory which
+    /// keeps its contents when powered off. The host should persist
+    /// `save_ram` for these cartridges between sessions.
+    fn battery(&self) -> bool {
+        false
+    }
+
+    /// Contents of the cartridge's external RAM, including any flash, EEPROM
+    /// or other state the cartridge keeps while powered off. This is the
+    /// contents of a `.sav` file.
+    fn save_ram(&self) -> Vec<u8> {
+        Vec::new()
+    }
+
+    /// Restore the contents of external RAM from `save_ram`. Data past the
+    /// end of the RAM is ignored, and RAM past the end of the data is left
+    /// unchanged.
+    fn load_save_ram(&mut self, _data: &[u8]) {
+        // Most cartridges have no RAM
+    }
+
+    /// Clock state to be appended to the `.sav` file after `save_ram`,
+    /// recording the given UNIX time. This is an `RtcFooter` for the MBC3,
+    /// and empty if the cartridge has no clock.
+    fn save_rtc(&self, _timestamp: u64) -> Vec<u8> {
+        Vec::new()
+    }
+
+    /// Restore the clock from a footer returned by `save_rtc`, advancing it by
+    /// the time elapsed between the footer being saved and the given UNIX
+    /// time. Footers which are too short are ignored.
+    fn load_rtc(&mut self, _footer: &[u8], _timestamp: u64) {
+        // Most cartridges have no clock
+    }
 }
 
 /// A cartridge which only contains ROM data and supports no other features.
@@ -15,7 +141,7 @@ pub struct RomOnly {
 
 impl RomOnly {
     pub fn new(data: Vec<u8>) -> Self {
-        Self { data: data }
+        Self { data }
     }
 }
 
@@ -23,6 +149,8 @@ impl Cartridge for RomOnly {
     fn read_byte(&self, address: Address) -> u8 {
         match address {
             0x0000..=0x7FFF => self.data[address as usize],
+            // Nothing drives the bus without cartridge RAM
+            0xA000..=0xBFFF => 0xFF,
             _ => panic!("Unsupported read from address {:04X}", address)
         }
     }
@@ -31,11 +159,80 @@ impl Cartridge for RomOnly {
         // Do nothing because we have no writable memory
     }
 
-    fn read_word(&self, address: Address) -> u16 {
-        make_u16(self.read_byte(address), self.read_byte(address + 1))
-    }
-
     fn write_word(&self, _address: Address, _value: u16) {
         // Do nothing because we have no writable memory
     }
 }
+
+/// A cartridge with up to 32 KiB of ROM and 8 KiB of RAM at 0xA000 - 0xBFFF,
+/// without a memory bank controller.
+pub struct RomRam {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    has_battery: bool
+}
+
+impl RomRam {
+    /// Create a cartridge from ROM data with the given amount of RAM, backed
+    /// by a battery if `has_battery` is set.
+    pub fn new(rom: Vec<u8>, ram_size_bytes: usize, has_battery: bool) -> Self {
+        Self { rom, ram: vec![0; ram_size_bytes], has_battery }
+    }
+}
+
+impl Cartridge for RomRam {
+    fn read_byte(&self, address: Address) -> u8 {
+        match address {
+            0x0000..=0x7FFF => self.rom[address as usize % self.rom.len()],
+            0xA000..=0xBFFF => {
+                if self.ram.is_empty() {
+                    return 0xFF;
+                }
+                self.ram[ram_bank_offset(&self.ram, 0, address)]
+            },
+            _ => panic!("Unsupported read from address {:04X}", address)
+        }
+    }
+
+    fn write_byte(&mut self, address: Address, value: u8) {
+        if let 0xA000..=0xBFFF = address {
+            if !self.ram.is_empty() {
+                let offset = ram_bank_offset(&self.ram, 0, address);
+                self.ram[offset] = value;
+            }
+        }
+    }
+
+    fn battery(&self) -> bool {
+        self.has_battery
+    }
+
+    fn save_ram(&self) -> Vec<u8> {
+        self.ram.clone()
+    }
+
+    fn load_save_ram(&mut self, data: &[u8]) {
+        load_ram(&mut self.ram, data);
+    }
+}
+
+/// Read from a 16 KiB bank of ROM mapped at 0x0000 - 0x3FFF or
+/// 0x4000 - 0x7FFF. Banks past the end of the ROM wrap around, as the upper
+/// bank number bits are not connected on smaller cartridges.
+fn rom_bank_byte(rom: &[u8], bank: usize, address: Address) -> u8 {
+    let offset = bank * ROM_BANK_SIZE_BYTES + (address as usize & (ROM_BANK_SIZE_BYTES - 1));
+    rom[offset % rom.len()]
+}
+
+/// Offset into cartridge RAM of an address in 0xA000 - 0xBFFF for the given
+/// 8 KiB bank. Banks past the end of the RAM wrap around.
+fn ram_bank_offset(ram: &[u8], bank: usize, address: Address) -> usize {
+    let offset = bank * RAM_BANK_SIZE_BYTES + (address as usize & (RAM_BANK_SIZE_BYTES - 1));
+    offset % ram.len()
+}
+
+/// Copy saved data into cartridge RAM, ignoring data past the end of the RAM.
+fn load_ram(ram: &mut [u8], data: &[u8]) {
+    let length = cmp::min(ram.len(), data.len());
+    ram[..length].copy_from_slice(&data[..length]);
+}
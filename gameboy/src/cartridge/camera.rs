@@ -0,0 +1,247 @@
+use super::*;
+
+/// Width of the image captured by the camera sensor in pixels.
+pub const CAMERA_WIDTH: usize = 128;
+/// Height of the image captured by the camera sensor in pixels.
+pub const CAMERA_HEIGHT: usize = 112;
+
+/// Number of camera registers, mirrored across 0xA000 - 0xBFFF.
+const REGISTER_COUNT: usize = 0x36;
+/// Offset into RAM bank 0 where captured images are written.
+const IMAGE_OFFSET: usize = 0x0100;
+/// Bit of the RAM bank register which maps the camera registers.
+const REGISTER_BANK: u8 = 0x10;
+
+const CAPTURE_START: u8 = 0x01;
+const GAIN_NO_OFFSET: u8 = 0x80;
+const GAIN_MASK: u8 = 0x1F;
+/// Sensor gain added by each step of the gain in register 1, in decibels.
+const GAIN_STEP_DB: f32 = 1.5;
+const OFFSET_POSITIVE: u8 = 0x20;
+const OFFSET_MASK: u8 = 0x1F;
+/// Pixel levels added or removed by each step of the output offset.
+const OFFSET_STEP: i32 = 4;
+const EDGE_ENABLE: u8 = 0x80;
+const INVERT: u8 = 0x08;
+
+/// Edge enhancement ratios selected by bits 4-6 of register 4, in quarters.
+const EDGE_RATIOS: [i32; 8] = [2, 3, 4, 5, 8, 12, 16, 20];
+
+/// A Game Boy Camera (Pocket Camera) cartridge, with 1 MiB of ROM, 128 KiB of
+/// RAM and an M64282FP image sensor.
+///
+/// Registers are written through the ROM area:
+///
+///   - 0x0000 - 0x1FFF: RAM write enable, enabled by writing 0x0A. RAM can
+///     always be read
+///   - 0x2000 - 0x3FFF: ROM bank number mapped at 0x4000 - 0x7FFF (6 bits)
+///   - 0x4000 - 0x5FFF: RAM bank number (4 bits), or the sensor registers
+///     when bit 4 is set
+///
+/// Sensor registers, of which only the first can be read:
+///
+///   - 0x00: Bit 0 starts a capture and reads set until it completes
+///   - 0x01: Bit 7 disables the extra 2048 cycles (512 M-cycles) of
+///     exposure, bits 0-4 gain in steps of 1.5 dB
+///   - 0x02 - 0x03: Exposure time in steps of 64 cycles (16 M-cycles), most
+///     significant byte first
+///   - 0x04: Bit 7 enables edge enhancement with the ratio in bits 4-6,
+///     bit 3 inverts the image
+///   - 0x05: Output offset voltage, bits 0-4 moving every pixel 4 levels
+///     brighter per step if bit 5 is set or darker if it is clear
+///   - 0x06 - 0x35: 4x4 dithering matrix of 3 thresholds per pixel, an
+///     exposed pixel below the first is black and above the last is white
+///
+/// Captured images are written to RAM bank 0 from offset 0x100 as 16x14
+/// tiles. The sensor image is supplied by the host with `set_camera_image`.
+pub struct PocketCamera {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    ram_write_enabled: bool,
+    rom_bank: u8,
+    ram_bank: u8,
+    registers: [u8; REGISTER_COUNT],
+    /// Grayscale sensor input, 0 black and 255 white
+    image: Vec<u8>,
+    /// Cycles until the capture in progress completes
+    capture_cycles: u32
+}
+
+impl PocketCamera {
+    /// Create a camera cartridge from ROM data with the given amount of RAM.
+    /// The sensor sees a black image until one is supplied.
+    pub fn new(rom: Vec<u8>, ram_size_bytes: usize) -> Self {
+        Self {
+            rom,
+            ram: vec![0; ram_size_bytes],
+            ram_write_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            registers: [0; REGISTER_COUNT],
+            image: vec![0; CAMERA_WIDTH * CAMERA_HEIGHT],
+            capture_cycles: 0
+        }
+    }
+
+    /// Indicates whether a capture is in progress.
+    pub fn capturing(&self) -> bool {
+        self.registers[0] & CAPTURE_START != 0
+    }
+
+    /// Cycles taken by a capture with the current registers. The sensor is
+    /// documented in M-cycles, 32448 plus 512 unless disabled plus 16 per
+    /// step of exposure, which are multiplied by 4 here.
+    fn capture_length(&self) -> u32 {
+        let exposure = self.exposure();
+        let extra = if self.registers[1] & GAIN_NO_OFFSET != 0 { 0 } else { 2048 };
+        129_792 + extra + exposure * 64
+    }
+
+    fn exposure(&self) -> u32 {
+        (self.registers[2] as u32) << 8 | self.registers[3] as u32
+    }
+
+    /// Sensor pixel scaled by the exposure time and gain, then moved by the
+    /// output offset, clamping coordinates to the edge of the image.
+    fn exposed_pixel(&self, x: i32, y: i32) -> i32 {
+        let x = x.clamp(0, CAMERA_WIDTH as i32 - 1) as usize;
+        let y = y.clamp(0, CAMERA_HEIGHT as i32 - 1) as usize;
+        let exposed = self.image[y * CAMERA_WIDTH + x] as u32 * self.exposure() / 0x1000;
+        let gain_db = (self.registers[1] & GAIN_MASK) as f32 * GAIN_STEP_DB;
+        let value = (exposed as f32 * 10f32.powf(gain_db / 20.0)) as i32 + self.offset();
+        value.clamp(0, 0xFF)
+    }
+
+    /// Levels added to every pixel by the output offset in register 5.
+    fn offset(&self) -> i32 {
+        let offset = (self.registers[5] & OFFSET_MASK) as i32 * OFFSET_STEP;
+        if self.registers[5] & OFFSET_POSITIVE != 0 { offset } else { -offset }
+    }
+
+    /// Pixel after exposure, edge enhancement and inversion.
+    fn processed_pixel(&self, x: i32, y: i32) -> i32 {
+        let mut value = self.exposed_pixel(x, y);
+        if self.registers[4] & EDGE_ENABLE != 0 {
+            let ratio = EDGE_RATIOS[((self.registers[4] >> 4) & 0x07) as usize];
+            let neighbours = self.exposed_pixel(x - 1, y) + self.exposed_pixel(x + 1, y) +
+                self.exposed_pixel(x, y - 1) + self.exposed_pixel(x, y + 1);
+            value += (4 * value - neighbours) * ratio / 16;
+        }
+        let value = value.clamp(0, 0xFF);
+        if self.registers[4] & INVERT != 0 { 0xFF - value } else { value }
+    }
+
+    /// Process the sensor image and write it to RAM as 2-bit tiles.
+    fn capture(&mut self) {
+        if self.ram.len() < IMAGE_OFFSET + CAMERA_WIDTH * CAMERA_HEIGHT / 4 {
+            return;
+        }
+
+        for y in 0..CAMERA_HEIGHT {
+            for x in 0..CAMERA_WIDTH {
+                let value = self.processed_pixel(x as i32, y as i32);
+                let matrix = 6 + ((y & 0x03) * 4 + (x & 0x03)) * 3;
+                let thresholds = &self.registers[matrix..matrix + 3];
+                let colour = thresholds.iter().filter(|&&threshold| value < threshold as i32).count() as u8;
+
+                let tile = (y / 8) * (CAMERA_WIDTH / 8) + x / 8;
+                let offset = IMAGE_OFFSET + tile * 16 + (y & 0x07) * 2;
+                let bit = 0x80 >> (x & 0x07);
+                for (plane, byte) in self.ram[offset..offset + 2].iter_mut().enumerate() {
+                    if colour & (1 << plane) != 0 {
+                        *byte |= bit;
+                    } else {
+                        *byte &= !bit;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Cartridge for PocketCamera {
+    fn read_byte(&self, address: Address) -> u8 {
+        match address {
+            0x0000..=0x3FFF => rom_bank_byte(&self.rom, 0, address),
+            0x4000..=0x7FFF => rom_bank_byte(&self.rom, self.rom_bank as usize, address),
+            0xA000..=0xBFFF => {
+                if self.ram_bank & REGISTER_BANK != 0 {
+                    // Only the capture register can be read
+                    return match address & 0x7F {
+                        0x00 => self.registers[0] & 0x07,
+                        _ => 0x00
+                    };
+                }
+                if self.ram.is_empty() || self.capturing() {
+                    return 0x00;
+                }
+                self.ram[ram_bank_offset(&self.ram, self.ram_bank as usize, address)]
+            },
+            _ => panic!("Unsupported read from address {:04X}", address)
+        }
+    }
+
+    fn write_byte(&mut self, address: Address, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.ram_write_enabled = value & 0x0F == 0x0A,
+            0x2000..=0x3FFF => self.rom_bank = value & 0x3F,
+            0x4000..=0x5FFF => self.ram_bank = value & 0x1F,
+            0x6000..=0x7FFF => {
+                // No registers here
+            },
+            0xA000..=0xBFFF => {
+                if self.ram_bank & REGISTER_BANK != 0 {
+                    let register = (address & 0x7F) as usize;
+                    if register >= REGISTER_COUNT {
+                        return;
+                    }
+                    if register == 0 {
+                        let start = !self.capturing() && value & CAPTURE_START != 0;
+                        self.registers[0] = value & 0x07;
+                        if start {
+                            self.capture_cycles = self.capture_length();
+                        } else if value & CAPTURE_START == 0 {
+                            // Clearing the start bit cancels the capture
+                            self.capture_cycles = 0;
+                        }
+                    } else {
+                        self.registers[register] = value;
+                    }
+                } else if self.ram_write_enabled && !self.ram.is_empty() {
+                    let offset = ram_bank_offset(&self.ram, self.ram_bank as usize, address);
+                    self.ram[offset] = value;
+                }
+            },
+            _ => panic!("Unsupported write to address {:04X}", address)
+        }
+    }
+
+    fn tick(&mut self, cycles: Cycles) {
+        if !self.capturing() {
+            return;
+        }
+
+        self.capture_cycles = self.capture_cycles.saturating_sub(cycles as u32);
+        if self.capture_cycles == 0 {
+            self.capture();
+            self.registers[0] &= !CAPTURE_START;
+        }
+    }
+
+    fn set_camera_image(&mut self, pixels: &[u8]) {
+        let length = cmp::min(pixels.len(), self.image.len());
+        self.image[..length].copy_from_slice(&pixels[..length]);
+    }
+
+    fn battery(&self) -> bool {
+        true
+    }
+
+    fn save_ram(&self) -> Vec<u8> {
+        self.ram.clone()
+    }
+
+    fn load_save_ram(&mut self, data: &[u8]) {
+        load_ram(&mut self.ram, data);
+    }
+}
@@ -0,0 +1,98 @@
+use super::*;
+
+/// A cartridge using Hudson's HuC1 memory bank controller, with up to 1 MiB
+/// of ROM, 32 KiB of RAM and an infrared LED and receiver.
+///
+/// Registers are written through the ROM area:
+///
+///   - 0x0000 - 0x1FFF: Writing 0x0E maps the infrared register at
+///     0xA000 - 0xBFFF, anything else maps RAM
+///   - 0x2000 - 0x3FFF: ROM bank number mapped at 0x4000 - 0x7FFF (6 bits)
+///   - 0x4000 - 0x5FFF: RAM bank number (2 bits)
+///
+/// The infrared register reads 0xC1 while light is received and 0xC0
+/// otherwise, writing bit 0 switches the LED.
+pub struct HuC1 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    infrared: Box<dyn Infrared>,
+    infrared_mode: bool,
+    /// ROM bank mapped at 0x4000 - 0x7FFF, never 0
+    rom_bank: u8,
+    ram_bank: u8
+}
+
+impl HuC1 {
+    /// Create a HuC1 cartridge from ROM data with the given amount of RAM.
+    /// The infrared port is left unconnected.
+    pub fn new(rom: Vec<u8>, ram_size_bytes: usize) -> Self {
+        Self {
+            rom,
+            ram: vec![0; ram_size_bytes],
+            infrared: Box::new(NoInfrared),
+            infrared_mode: false,
+            rom_bank: 1,
+            ram_bank: 0
+        }
+    }
+}
+
+impl Cartridge for HuC1 {
+    fn read_byte(&self, address: Address) -> u8 {
+        match address {
+            0x0000..=0x3FFF => rom_bank_byte(&self.rom, 0, address),
+            0x4000..=0x7FFF => rom_bank_byte(&self.rom, self.rom_bank as usize, address),
+            0xA000..=0xBFFF => {
+                if self.infrared_mode {
+                    0xC0 | self.infrared.receiving() as u8
+                } else if self.ram.is_empty() {
+                    0xFF
+                } else {
+                    self.ram[ram_bank_offset(&self.ram, self.ram_bank as usize, address)]
+                }
+            },
+            _ => panic!("Unsupported read from address {:04X}", address)
+        }
+    }
+
+    fn write_byte(&mut self, address: Address, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.infrared_mode = value & 0x0F == 0x0E,
+            0x2000..=0x3FFF => {
+                self.rom_bank = value & 0x3F;
+                if self.rom_bank == 0 {
+                    self.rom_bank = 1;
+                }
+            },
+            0x4000..=0x5FFF => self.ram_bank = value & 0x03,
+            0x6000..=0x7FFF => {
+                // No registers here
+            },
+            0xA000..=0xBFFF => {
+                if self.infrared_mode {
+                    self.infrared.set_led(value & 0x01 != 0);
+                } else if !self.ram.is_empty() {
+                    let offset = ram_bank_offset(&self.ram, self.ram_bank as usize, address);
+                    self.ram[offset] = value;
+                }
+            },
+            _ => panic!("Unsupported write to address {:04X}", address)
+        }
+    }
+
+    fn connect_infrared(&mut self, infrared: Box<dyn Infrared>) {
+        self.infrared = infrared;
+    }
+
+    fn battery(&self) -> bool {
+        true
+    }
+
+    fn save_ram(&self) -> Vec<u8> {
+        self.ram.clone()
+    }
+
+    fn load_save_ram(&mut self, data: &[u8]) {
+        load_ram(&mut self.ram, data);
+    }
+}
@@ -0,0 +1,255 @@
+use super::*;
+
+/// Number of 4-bit values in the HuC3's RTC memory.
+const RTC_MEMORY_SIZE: usize = 0x100;
+/// Number of minutes in a day.
+const MINUTES_PER_DAY: u16 = 1440;
+/// RTC memory address holding the tone to play on the speaker.
+const TONE_ADDRESS: usize = 0x27;
+
+/// Size of the clock state saved after the HuC3's RAM in bytes, made up of
+/// the minutes past midnight and the day counter followed by a UNIX
+/// timestamp.
+pub const HUC3_RTC_SIZE_BYTES: usize = 12;
+
+/// Register mapped at 0xA000 - 0xBFFF, selected through 0x0000 - 0x1FFF.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum HuC3Mode {
+    /// RAM, read only
+    RamReadOnly,
+    /// RAM, read and write
+    Ram,
+    /// RTC command to run next
+    RtcCommand,
+    /// RTC command response
+    RtcResponse,
+    /// RTC semaphore, writing bit 0 clear runs the command
+    RtcSemaphore,
+    /// Infrared LED and receiver
+    Infrared,
+    /// Unmapped, reads return 0xFF
+    None
+}
+
+/// A cartridge using Hudson's HuC3 memory bank controller, with up to 2 MiB
+/// of ROM, 32 KiB of RAM, a real time clock, a speaker and an infrared LED
+/// and receiver.
+///
+/// Registers are written through the ROM area:
+///
+///   - 0x0000 - 0x1FFF: Selects what is mapped at 0xA000 - 0xBFFF using the
+///     low nibble, 0x0 read only RAM, 0xA RAM, 0xB RTC command, 0xC RTC
+///     response, 0xD RTC semaphore and 0xE infrared
+///   - 0x2000 - 0x3FFF: ROM bank number mapped at 0x4000 - 0x7FFF (7 bits)
+///   - 0x4000 - 0x5FFF: RAM bank number (2 bits)
+///
+/// The clock is accessed through 256 4-bit values of RTC memory. A command
+/// is written with its opcode in the upper nibble and argument in the lower
+/// nibble, then run by writing the semaphore. Responses echo the opcode in
+/// the upper nibble:
+///
+///   - 0x1: Read the value at the access address and increment it
+///   - 0x3: Write the argument to the access address and increment it
+///   - 0x4: Set the lower nibble of the access address
+///   - 0x5: Set the upper nibble of the access address
+///   - 0x6: Extended, 0x0 copies the time into 0x00 - 0x06 (12 bits of
+///     minutes past midnight and 16 bits of days), 0x1 sets the time from
+///     there, 0x2 reads 1 and 0xE plays the tone selected at 0x27
+///
+/// The clock is saved by `save_rtc` in its own footer rather than the
+/// VBA-M/BGB one, which has no room for the 16-bit day counter.
+pub struct HuC3 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    infrared: Box<dyn Infrared>,
+    mode: HuC3Mode,
+    rom_bank: u8,
+    ram_bank: u8,
+    rtc_memory: [u8; RTC_MEMORY_SIZE],
+    access_address: u8,
+    command: u8,
+    response: u8,
+    /// Minutes past midnight
+    minutes: u16,
+    days: u16,
+    /// Cycles elapsed towards the next minute
+    cycles: u32,
+    /// Tone last played on the speaker
+    tone: Option<u8>
+}
+
+impl HuC3 {
+    /// Create a HuC3 cartridge from ROM data with the given amount of RAM.
+    /// The infrared port is left unconnected.
+    pub fn new(rom: Vec<u8>, ram_size_bytes: usize) -> Self {
+        Self {
+            rom,
+            ram: vec![0; ram_size_bytes],
+            infrared: Box::new(NoInfrared),
+            mode: HuC3Mode::RamReadOnly,
+            rom_bank: 1,
+            ram_bank: 0,
+            rtc_memory: [0; RTC_MEMORY_SIZE],
+            access_address: 0,
+            command: 0,
+            response: 0,
+            minutes: 0,
+            days: 0,
+            cycles: 0,
+            tone: None
+        }
+    }
+
+    /// Advance the clock by the given number of seconds.
+    fn advance_seconds(&mut self, seconds: u64) {
+        let seconds = (self.cycles / CYCLES_PER_SECOND) as u64 + seconds;
+        let minutes = self.minutes as u64 + seconds / 60;
+        self.cycles = (seconds % 60) as u32 * CYCLES_PER_SECOND;
+        self.minutes = (minutes % MINUTES_PER_DAY as u64) as u16;
+        self.days = self.days.wrapping_add((minutes / MINUTES_PER_DAY as u64) as u16);
+    }
+
+    /// Run the command last written, once the semaphore is written.
+    fn run_command(&mut self) {
+        let argument = self.command & 0x0F;
+        self.response = 0;
+        match self.command >> 4 {
+            0x1 => {
+                self.response = self.rtc_memory[self.access_address as usize];
+                self.access_address = self.access_address.wrapping_add(1);
+            },
+            0x3 => {
+                self.rtc_memory[self.access_address as usize] = argument;
+                self.access_address = self.access_address.wrapping_add(1);
+            },
+            0x4 => self.access_address = (self.access_address & 0xF0) | argument,
+            0x5 => self.access_address = (self.access_address & 0x0F) | argument << 4,
+            0x6 => match argument {
+                0x0 => {
+                    let time = self.minutes as u32 | (self.days as u32) << 12;
+                    for (index, nibble) in self.rtc_memory[..7].iter_mut().enumerate() {
+                        *nibble = (time >> (index * 4)) as u8 & 0x0F;
+                    }
+                },
+                0x1 => {
+                    let time = self.rtc_memory[..7].iter().enumerate()
+                        .fold(0u32, |time, (index, nibble)| time | (*nibble as u32) << (index * 4));
+                    self.minutes = (time & 0x0FFF) as u16 % MINUTES_PER_DAY;
+                    self.days = (time >> 12) as u16;
+                    self.cycles = 0;
+                },
+                0x2 => self.response = 0x1,
+                0xE => self.tone = Some(self.rtc_memory[TONE_ADDRESS]),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+}
+
+impl Cartridge for HuC3 {
+    fn read_byte(&self, address: Address) -> u8 {
+        match address {
+            0x0000..=0x3FFF => rom_bank_byte(&self.rom, 0, address),
+            0x4000..=0x7FFF => rom_bank_byte(&self.rom, self.rom_bank as usize, address),
+            0xA000..=0xBFFF => match self.mode {
+                HuC3Mode::RamReadOnly | HuC3Mode::Ram if !self.ram.is_empty() => {
+                    self.ram[ram_bank_offset(&self.ram, self.ram_bank as usize, address)]
+                },
+                HuC3Mode::RtcResponse => (self.command & 0xF0) | self.response,
+                // Commands complete instantly
+                HuC3Mode::RtcSemaphore => 0xFF,
+                HuC3Mode::Infrared => 0xC0 | self.infrared.receiving() as u8,
+                _ => 0xFF
+            },
+            _ => panic!("Unsupported read from address {:04X}", address)
+        }
+    }
+
+    fn write_byte(&mut self, address: Address, value: u8) {
+        match address {
+            0x0000..=0x1FFF => {
+                self.mode = match value & 0x0F {
+                    0x0 => HuC3Mode::RamReadOnly,
+                    0xA => HuC3Mode::Ram,
+                    0xB => HuC3Mode::RtcCommand,
+                    0xC => HuC3Mode::RtcResponse,
+                    0xD => HuC3Mode::RtcSemaphore,
+                    0xE => HuC3Mode::Infrared,
+                    _ => HuC3Mode::None
+                };
+            },
+            0x2000..=0x3FFF => self.rom_bank = value & 0x7F,
+            0x4000..=0x5FFF => self.ram_bank = value & 0x03,
+            0x6000..=0x7FFF => {
+                // No registers here
+            },
+            0xA000..=0xBFFF => match self.mode {
+                HuC3Mode::Ram if !self.ram.is_empty() => {
+                    let offset = ram_bank_offset(&self.ram, self.ram_bank as usize, address);
+                    self.ram[offset] = value;
+                },
+                HuC3Mode::RtcCommand => self.command = value & 0x7F,
+                HuC3Mode::RtcSemaphore if value & 0x01 == 0 => self.run_command(),
+                HuC3Mode::Infrared => self.infrared.set_led(value & 0x01 != 0),
+                _ => {}
+            },
+            _ => panic!("Unsupported write to address {:04X}", address)
+        }
+    }
+
+    fn tick(&mut self, cycles: Cycles) {
+        self.cycles += cycles as u32;
+        while self.cycles >= CYCLES_PER_SECOND * 60 {
+            self.cycles -= CYCLES_PER_SECOND * 60;
+            self.minutes += 1;
+            if self.minutes == MINUTES_PER_DAY {
+                self.minutes = 0;
+                self.days = self.days.wrapping_add(1);
+            }
+        }
+    }
+
+    fn connect_infrared(&mut self, infrared: Box<dyn Infrared>) {
+        self.infrared = infrared;
+    }
+
+    fn speaker_tone(&self) -> Option<u8> {
+        self.tone
+    }
+
+    fn battery(&self) -> bool {
+        true
+    }
+
+    fn save_ram(&self) -> Vec<u8> {
+        self.ram.clone()
+    }
+
+    fn load_save_ram(&mut self, data: &[u8]) {
+        load_ram(&mut self.ram, data);
+    }
+
+    /// The minutes past midnight and the day counter as little endian 16-bit
+    /// values, followed by the UNIX time as a little endian 64-bit value.
+    /// Seconds towards the next minute are not saved.
+    fn save_rtc(&self, timestamp: u64) -> Vec<u8> {
+        let mut footer = Vec::with_capacity(HUC3_RTC_SIZE_BYTES);
+        footer.extend_from_slice(&self.minutes.to_le_bytes());
+        footer.extend_from_slice(&self.days.to_le_bytes());
+        footer.extend_from_slice(&timestamp.to_le_bytes());
+        footer
+    }
+
+    fn load_rtc(&mut self, footer: &[u8], timestamp: u64) {
+        if footer.len() < HUC3_RTC_SIZE_BYTES {
+            return;
+        }
+        self.minutes = make_u16(footer[1], footer[0]) % MINUTES_PER_DAY;
+        self.days = make_u16(footer[3], footer[2]);
+        self.cycles = 0;
+        let mut saved = [0; 8];
+        saved.copy_from_slice(&footer[4..HUC3_RTC_SIZE_BYTES]);
+        self.advance_seconds(timestamp.saturating_sub(u64::from_le_bytes(saved)));
+    }
+}
@@ -0,0 +1,60 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Connection between a cartridge's infrared LED and receiver and the
+/// outside world.
+pub trait Infrared {
+    /// Switch the LED on or off.
+    fn set_led(&mut self, on: bool);
+    /// Indicates whether the receiver currently sees light.
+    fn receiving(&self) -> bool;
+}
+
+/// Infrared connection to nothing, no light is ever received.
+pub struct NoInfrared;
+
+impl Infrared for NoInfrared {
+    fn set_led(&mut self, _on: bool) {
+        // Nobody is watching
+    }
+
+    fn receiving(&self) -> bool {
+        false
+    }
+}
+
+/// One end of an infrared link between two emulated machines. Each end
+/// receives light while the LED at the other end is on.
+pub struct InfraredPort {
+    leds: Rc<RefCell<[bool; 2]>>,
+    side: usize
+}
+
+impl InfraredPort {
+    /// Create both ends of a link, each to be connected to a cartridge.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use gameboy::cartridge::*;
+    /// let (mut left, right) = InfraredPort::pair();
+    /// assert!(!right.receiving());
+    /// left.set_led(true);
+    /// assert!(right.receiving());
+    /// assert!(!left.receiving());
+    /// ```
+    pub fn pair() -> (Self, Self) {
+        let leds = Rc::new(RefCell::new([false; 2]));
+        (Self { leds: leds.clone(), side: 0 }, Self { leds, side: 1 })
+    }
+}
+
+impl Infrared for InfraredPort {
+    fn set_led(&mut self, on: bool) {
+        self.leds.borrow_mut()[self.side] = on;
+    }
+
+    fn receiving(&self) -> bool {
+        self.leds.borrow()[1 - self.side]
+    }
+}
@@ -0,0 +1,145 @@
+use super::*;
+use super::super::rom::{NINTENDO_LOGO_BYTES, NINTENDO_LOGO_SIZE_BYTES};
+
+/// Size of an MBC1M multicart in bytes, made up of 4 games of 256 KiB.
+const MULTICART_SIZE_BYTES: usize = 0x100000;
+
+/// A cartridge using the MBC1 memory bank controller, with up to 2 MiB of ROM
+/// and 32 KiB of RAM.
+///
+/// Registers are written through the ROM area:
+///
+///   - 0x0000 - 0x1FFF: RAM enable, enabled by writing 0x0A to the low nibble
+///   - 0x2000 - 0x3FFF: Lower 5 bits of the ROM bank number (BANK1)
+///   - 0x4000 - 0x5FFF: Upper 2 bits of the ROM bank number or the RAM bank
+///     number (BANK2)
+///   - 0x6000 - 0x7FFF: Banking mode. In mode 1 BANK2 also switches the RAM
+///     bank and the ROM bank mapped at 0x0000 - 0x3FFF
+///
+/// MBC1M multicarts wire BANK2 to bits 4-5 of the ROM bank number instead of
+/// bits 5-6, leaving bit 4 of BANK1 unconnected.
+pub struct Mbc1 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    ram_enabled: bool,
+    /// Lower ROM bank number bits, never 0
+    bank1: u8,
+    /// Upper ROM bank number bits or RAM bank number
+    bank2: u8,
+    /// Banking mode 1, switching the RAM bank and ROM bank 0 area with BANK2
+    mode: bool,
+    multicart: bool,
+    has_battery: bool
+}
+
+impl Mbc1 {
+    /// Create an MBC1 cartridge from ROM data with the given amount of RAM,
+    /// backed by a battery if `has_battery` is set, detecting whether it is
+    /// an MBC1M multicart.
+    pub fn new(rom: Vec<u8>, ram_size_bytes: usize, has_battery: bool) -> Self {
+        let multicart = is_multicart(&rom);
+        Self {
+            rom,
+            ram: vec![0; ram_size_bytes],
+            ram_enabled: false,
+            bank1: 1,
+            bank2: 0,
+            mode: false,
+            multicart,
+            has_battery
+        }
+    }
+
+    /// Indicates whether the ROM was detected as an MBC1M multicart.
+    pub fn multicart(&self) -> bool {
+        self.multicart
+    }
+
+    /// Number of bits BANK2 is shifted by to form the ROM bank number.
+    fn bank2_shift(&self) -> u8 {
+        if self.multicart { 4 } else { 5 }
+    }
+
+    /// ROM bank mapped at 0x0000 - 0x3FFF.
+    fn low_rom_bank(&self) -> usize {
+        if self.mode { (self.bank2 << self.bank2_shift()) as usize } else { 0 }
+    }
+
+    /// ROM bank mapped at 0x4000 - 0x7FFF.
+    fn high_rom_bank(&self) -> usize {
+        let bank1 = if self.multicart { self.bank1 & 0x0F } else { self.bank1 };
+        ((self.bank2 << self.bank2_shift()) | bank1) as usize
+    }
+
+    /// RAM bank mapped at 0xA000 - 0xBFFF.
+    fn ram_bank(&self) -> usize {
+        if self.mode { self.bank2 as usize } else { 0 }
+    }
+}
+
+impl Cartridge for Mbc1 {
+    fn read_byte(&self, address: Address) -> u8 {
+        match address {
+            0x0000..=0x3FFF => rom_bank_byte(&self.rom, self.low_rom_bank(), address),
+            0x4000..=0x7FFF => rom_bank_byte(&self.rom, self.high_rom_bank(), address),
+            0xA000..=0xBFFF => {
+                if !self.ram_enabled || self.ram.is_empty() {
+                    return 0xFF;
+                }
+                self.ram[ram_bank_offset(&self.ram, self.ram_bank(), address)]
+            },
+            _ => panic!("Unsupported read from address {:04X}", address)
+        }
+    }
+
+    fn write_byte(&mut self, address: Address, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.ram_enabled = value & 0x0F == 0x0A,
+            0x2000..=0x3FFF => {
+                // Selecting bank 0 selects bank 1 instead. Only the 5 bits
+                // written are checked, so banks 0x20, 0x40 and 0x60 can
+                // never be mapped at 0x4000 - 0x7FFF
+                self.bank1 = value & 0x1F;
+                if self.bank1 == 0 {
+                    self.bank1 = 1;
+                }
+            },
+            0x4000..=0x5FFF => self.bank2 = value & 0x03,
+            0x6000..=0x7FFF => self.mode = value & 0x01 != 0,
+            0xA000..=0xBFFF => {
+                if self.ram_enabled && !self.ram.is_empty() {
+                    let offset = ram_bank_offset(&self.ram, self.ram_bank(), address);
+                    self.ram[offset] = value;
+                }
+            },
+            _ => panic!("Unsupported write to address {:04X}", address)
+        }
+    }
+
+    fn battery(&self) -> bool {
+        self.has_battery
+    }
+
+    fn save_ram(&self) -> Vec<u8> {
+        self.ram.clone()
+    }
+
+    fn load_save_ram(&mut self, data: &[u8]) {
+        load_ram(&mut self.ram, data);
+    }
+}
+
+/// Detect an MBC1M multicart. These are 1 MiB and have the menu and each
+/// game starting at a multiple of 16 banks, so the Nintendo logo is repeated
+/// at the start of more than one of these banks.
+fn is_multicart(rom: &[u8]) -> bool {
+    if rom.len() != MULTICART_SIZE_BYTES {
+        return false;
+    }
+
+    let logos = (0..4)
+        .map(|game| game * 0x10 * ROM_BANK_SIZE_BYTES + 0x0104)
+        .filter(|&offset| rom[offset..offset + NINTENDO_LOGO_SIZE_BYTES] == NINTENDO_LOGO_BYTES[..])
+        .count();
+    logos > 1
+}
@@ -0,0 +1,88 @@
+use super::*;
+
+/// Number of 4-bit values in the MBC2's built-in RAM.
+pub const MBC2_RAM_SIZE: usize = 0x200;
+
+/// A cartridge using the MBC2 memory bank controller, with up to 256 KiB of
+/// ROM and 512 4-bit values of RAM built into the controller.
+///
+/// Registers are written through 0x0000 - 0x3FFF, with bit 8 of the address
+/// selecting between them:
+///
+///   - Bit 8 clear: RAM enable, enabled by writing 0x0A to the low nibble
+///   - Bit 8 set: ROM bank number mapped at 0x4000 - 0x7FFF (4 bits)
+///
+/// The RAM is echoed across 0xA000 - 0xBFFF and the upper nibble of each
+/// value reads back as 1s.
+pub struct Mbc2 {
+    rom: Vec<u8>,
+    ram: [u8; MBC2_RAM_SIZE],
+    ram_enabled: bool,
+    /// ROM bank mapped at 0x4000 - 0x7FFF, never 0
+    rom_bank: u8,
+    has_battery: bool
+}
+
+impl Mbc2 {
+    /// Create an MBC2 cartridge from ROM data, with its RAM backed by a
+    /// battery if `has_battery` is set.
+    pub fn new(rom: Vec<u8>, has_battery: bool) -> Self {
+        Self { rom, ram: [0; MBC2_RAM_SIZE], ram_enabled: false, rom_bank: 1, has_battery }
+    }
+}
+
+impl Cartridge for Mbc2 {
+    fn read_byte(&self, address: Address) -> u8 {
+        match address {
+            0x0000..=0x3FFF => rom_bank_byte(&self.rom, 0, address),
+            0x4000..=0x7FFF => rom_bank_byte(&self.rom, self.rom_bank as usize, address),
+            0xA000..=0xBFFF => {
+                if !self.ram_enabled {
+                    return 0xFF;
+                }
+                0xF0 | self.ram[address as usize & (MBC2_RAM_SIZE - 1)]
+            },
+            _ => panic!("Unsupported read from address {:04X}", address)
+        }
+    }
+
+    fn write_byte(&mut self, address: Address, value: u8) {
+        match address {
+            0x0000..=0x3FFF => {
+                if address & 0x0100 == 0 {
+                    self.ram_enabled = value & 0x0F == 0x0A;
+                } else {
+                    self.rom_bank = value & 0x0F;
+                    if self.rom_bank == 0 {
+                        self.rom_bank = 1;
+                    }
+                }
+            },
+            0x4000..=0x7FFF => {
+                // No registers here
+            },
+            0xA000..=0xBFFF => {
+                if self.ram_enabled {
+                    self.ram[address as usize & (MBC2_RAM_SIZE - 1)] = value & 0x0F;
+                }
+            },
+            _ => panic!("Unsupported write to address {:04X}", address)
+        }
+    }
+
+    fn battery(&self) -> bool {
+        self.has_battery
+    }
+
+    fn save_ram(&self) -> Vec<u8> {
+        self.ram.to_vec()
+    }
+
+    fn load_save_ram(&mut self, data: &[u8]) {
+        load_ram(&mut self.ram, data);
+        // Only the lower nibble of each byte exists
+        for value in self.ram.iter_mut() {
+            *value &= 0x0F;
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use super::*;
+
+/// A cartridge using the MBC3 memory bank controller, with up to 2 MiB of ROM,
+/// 32 KiB of RAM and optionally a real time clock.
+///
+/// Registers are written through the ROM area:
+///
+///   - 0x0000 - 0x1FFF: RAM and RTC enable, enabled by writing 0x0A to the
+///     low nibble
+///   - 0x2000 - 0x3FFF: ROM bank number mapped at 0x4000 - 0x7FFF (7 bits)
+///   - 0x4000 - 0x5FFF: RAM bank number (0x00 - 0x03) or RTC register
+///     (0x08 - 0x0C) mapped at 0xA000 - 0xBFFF
+///   - 0x6000 - 0x7FFF: Writing 0x00 then 0x01 latches the RTC registers
+pub struct Mbc3 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    rtc: Option<Rtc>,
+    ram_enabled: bool,
+    /// ROM bank mapped at 0x4000 - 0x7FFF, never 0
+    rom_bank: u8,
+    /// RAM bank or RTC register mapped at 0xA000 - 0xBFFF
+    ram_select: u8,
+    /// Last write to the latch register was 0x00
+    latch_armed: bool,
+    has_battery: bool
+}
+
+impl Mbc3 {
+    /// Create an MBC3 cartridge from ROM data with the given amount of RAM,
+    /// a real time clock if `has_rtc` is set and a battery if `has_battery`
+    /// is set.
+    pub fn new(rom: Vec<u8>, ram_size_bytes: usize, has_rtc: bool, has_battery: bool) -> Self {
+        Self {
+            rom,
+            ram: vec![0; ram_size_bytes],
+            rtc: if has_rtc { Some(Rtc::new()) } else { None },
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_select: 0,
+            latch_armed: false,
+            has_battery
+        }
+    }
+}
+
+impl Cartridge for Mbc3 {
+    fn read_byte(&self, address: Address) -> u8 {
+        match address {
+            0x0000..=0x3FFF => rom_bank_byte(&self.rom, 0, address),
+            0x4000..=0x7FFF => rom_bank_byte(&self.rom, self.rom_bank as usize, address),
+            0xA000..=0xBFFF => {
+                if !self.ram_enabled {
+                    return 0xFF;
+                }
+                match (self.ram_select, &self.rtc) {
+                    (0x00..=0x03, _) if !self.ram.is_empty() => {
+                        self.ram[ram_bank_offset(&self.ram, self.ram_select as usize, address)]
+                    },
+                    (RTC_SECONDS..=RTC_DAYS_HIGH, Some(rtc)) => rtc.read(self.ram_select),
+                    _ => 0xFF
+                }
+            },
+            _ => panic!("Unsupported read from address {:04X}", address)
+        }
+    }
+
+    fn write_byte(&mut self, address: Address, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.ram_enabled = value & 0x0F == 0x0A,
+            0x2000..=0x3FFF => {
+                self.rom_bank = value & 0x7F;
+                if self.rom_bank == 0 {
+                    self.rom_bank = 1;
+                }
+            },
+            0x4000..=0x5FFF => self.ram_select = value,
+            0x6000..=0x7FFF => {
+                if let Some(rtc) = &mut self.rtc {
+                    if self.latch_armed && value == 0x01 {
+                        rtc.latch();
+                    }
+                }
+                self.latch_armed = value == 0x00;
+            },
+            0xA000..=0xBFFF => {
+                if !self.ram_enabled {
+                    return;
+                }
+                match (self.ram_select, &mut self.rtc) {
+                    (0x00..=0x03, _) if !self.ram.is_empty() => {
+                        let offset = ram_bank_offset(&self.ram, self.ram_select as usize, address);
+                        self.ram[offset] = value;
+                    },
+                    (RTC_SECONDS..=RTC_DAYS_HIGH, Some(rtc)) => rtc.write(self.ram_select, value),
+                    _ => {}
+                }
+            },
+            _ => panic!("Unsupported write to address {:04X}", address)
+        }
+    }
+
+    fn tick(&mut self, cycles: Cycles) {
+        if let Some(rtc) = &mut self.rtc {
+            rtc.tick(cycles);
+        }
+    }
+
+    fn battery(&self) -> bool {
+        self.has_battery
+    }
+
+    fn save_ram(&self) -> Vec<u8> {
+        self.ram.clone()
+    }
+
+    fn load_save_ram(&mut self, data: &[u8]) {
+        load_ram(&mut self.ram, data);
+    }
+
+    fn save_rtc(&self, timestamp: u64) -> Vec<u8> {
+        match &self.rtc {
+            Some(rtc) => rtc.footer(timestamp).to_bytes(),
+            None => Vec::new()
+        }
+    }
+
+    fn load_rtc(&mut self, footer: &[u8], timestamp: u64) {
+        if let (Some(rtc), Some(footer)) = (&mut self.rtc, RtcFooter::from_bytes(footer)) {
+            rtc.load_footer(&footer, timestamp);
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use super::*;
+
+/// Bit of the RAM bank register wired to the motor on rumble cartridges.
+const RUMBLE_MOTOR: u8 = 0x08;
+
+/// A cartridge using the MBC5 memory bank controller, with up to 8 MiB of ROM,
+/// 128 KiB of RAM and optionally a rumble motor.
+///
+/// Registers are written through the ROM area:
+///
+///   - 0x0000 - 0x1FFF: RAM enable, enabled by writing 0x0A
+///   - 0x2000 - 0x2FFF: Lower 8 bits of the ROM bank number
+///   - 0x3000 - 0x3FFF: Bit 8 of the ROM bank number
+///   - 0x4000 - 0x5FFF: RAM bank number (4 bits). On rumble cartridges bit 3
+///     drives the motor instead, leaving 3 bits of RAM bank number
+///
+/// Unlike earlier controllers ROM bank 0 can be mapped at 0x4000 - 0x7FFF.
+pub struct Mbc5 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    ram_enabled: bool,
+    /// ROM bank mapped at 0x4000 - 0x7FFF (9 bits)
+    rom_bank: u16,
+    ram_bank: u8,
+    has_rumble: bool,
+    rumble: bool,
+    /// Run on each change of the motor state
+    rumble_callback: Option<RumbleCallback>,
+    has_battery: bool
+}
+
+impl Mbc5 {
+    /// Create an MBC5 cartridge from ROM data with the given amount of RAM,
+    /// a rumble motor if `has_rumble` is set and a battery if `has_battery`
+    /// is set.
+    pub fn new(rom: Vec<u8>, ram_size_bytes: usize, has_rumble: bool, has_battery: bool) -> Self {
+        Self {
+            rom,
+            ram: vec![0; ram_size_bytes],
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            has_rumble,
+            rumble: false,
+            rumble_callback: None,
+            has_battery
+        }
+    }
+}
+
+impl Cartridge for Mbc5 {
+    fn read_byte(&self, address: Address) -> u8 {
+        match address {
+            0x0000..=0x3FFF => rom_bank_byte(&self.rom, 0, address),
+            0x4000..=0x7FFF => rom_bank_byte(&self.rom, self.rom_bank as usize, address),
+            0xA000..=0xBFFF => {
+                if !self.ram_enabled || self.ram.is_empty() {
+                    return 0xFF;
+                }
+                self.ram[ram_bank_offset(&self.ram, self.ram_bank as usize, address)]
+            },
+            _ => panic!("Unsupported read from address {:04X}", address)
+        }
+    }
+
+    fn write_byte(&mut self, address: Address, value: u8) {
+        match address {
+            // All 8 bits are compared, unlike earlier controllers
+            0x0000..=0x1FFF => self.ram_enabled = value == 0x0A,
+            0x2000..=0x2FFF => self.rom_bank = (self.rom_bank & 0x100) | value as u16,
+            0x3000..=0x3FFF => self.rom_bank = (self.rom_bank & 0xFF) | ((value & 0x01) as u16) << 8,
+            0x4000..=0x5FFF => {
+                if self.has_rumble {
+                    let rumble = value & RUMBLE_MOTOR != 0;
+                    if rumble != self.rumble {
+                        self.rumble = rumble;
+                        if let Some(callback) = &mut self.rumble_callback {
+                            callback(rumble);
+                        }
+                    }
+                    self.ram_bank = value & 0x07;
+                } else {
+                    self.ram_bank = value & 0x0F;
+                }
+            },
+            0x6000..=0x7FFF => {
+                // No registers here
+            },
+            0xA000..=0xBFFF => {
+                if self.ram_enabled && !self.ram.is_empty() {
+                    let offset = ram_bank_offset(&self.ram, self.ram_bank as usize, address);
+                    self.ram[offset] = value;
+                }
+            },
+            _ => panic!("Unsupported write to address {:04X}", address)
+        }
+    }
+
+    fn rumble(&self) -> bool {
+        self.rumble
+    }
+
+    fn connect_rumble(&mut self, callback: RumbleCallback) {
+        if self.has_rumble {
+            self.rumble_callback = Some(callback);
+        }
+    }
+
+    fn battery(&self) -> bool {
+        self.has_battery
+    }
+
+    fn save_ram(&self) -> Vec<u8> {
+        self.ram.clone()
+    }
+
+    fn load_save_ram(&mut self, data: &[u8]) {
+        load_ram(&mut self.ram, data);
+    }
+}
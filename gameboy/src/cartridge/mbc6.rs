@@ -0,0 +1,250 @@
+use super::*;
+
+/// Size of the MBC6's flash memory in bytes.
+pub const MBC6_FLASH_SIZE_BYTES: usize = 0x100000;
+/// Size of the MBC6's RAM in bytes.
+pub const MBC6_RAM_SIZE_BYTES: usize = 0x8000;
+
+/// Size of each of the two switchable ROM/flash windows in bytes.
+const ROM_WINDOW_SIZE_BYTES: usize = 0x2000;
+/// Size of each of the two switchable RAM windows in bytes.
+const RAM_WINDOW_SIZE_BYTES: usize = 0x1000;
+/// Size of the area of flash cleared by a sector erase in bytes.
+const FLASH_SECTOR_SIZE_BYTES: usize = 0x20000;
+/// Value read from flash address 0 in ID mode (Macronix).
+const FLASH_MANUFACTURER_ID: u8 = 0xC2;
+/// Value read from flash address 1 in ID mode.
+const FLASH_DEVICE_ID: u8 = 0x81;
+
+/// Steps of the flash command protocol. Every command starts by writing 0xAA
+/// to 0x5555 then 0x55 to 0x2AAA, erases repeat this sequence a second time.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum FlashState {
+    /// Reading flash contents
+    Read,
+    /// First unlock byte written
+    Unlock1,
+    /// Second unlock byte written, waiting for a command
+    Unlock2,
+    /// Reading the manufacturer and device IDs
+    Id,
+    /// Next write programs a byte
+    Program,
+    /// Erase command written, waiting for the second unlock sequence
+    Erase,
+    EraseUnlock1,
+    /// Second unlock sequence written, waiting for a sector or chip erase
+    EraseUnlock2
+}
+
+/// Flash chip on MBC6 cartridges, driven by a command protocol through its
+/// own addresses.
+struct Flash {
+    data: Vec<u8>,
+    state: FlashState
+}
+
+impl Flash {
+    fn new() -> Self {
+        Self { data: vec![0xFF; MBC6_FLASH_SIZE_BYTES], state: FlashState::Read }
+    }
+
+    fn read(&self, offset: usize) -> u8 {
+        match self.state {
+            FlashState::Id => match offset & 0x01 {
+                0 => FLASH_MANUFACTURER_ID,
+                _ => FLASH_DEVICE_ID
+            },
+            _ => self.data[offset]
+        }
+    }
+
+    fn write(&mut self, offset: usize, value: u8) {
+        // Only the lower 15 address bits are decoded for commands
+        let command_address = offset & 0x7FFF;
+        self.state = match (self.state, command_address, value) {
+            // The byte after a program command is always data, even 0xF0
+            (FlashState::Program, _, _) => {
+                // Programming can only clear bits, erasing sets them
+                self.data[offset] &= value;
+                FlashState::Read
+            },
+            // Otherwise writing 0xF0 anywhere returns to reading
+            (_, _, 0xF0) => FlashState::Read,
+            (FlashState::Read, 0x5555, 0xAA) | (FlashState::Id, 0x5555, 0xAA) => FlashState::Unlock1,
+            (FlashState::Unlock1, 0x2AAA, 0x55) => FlashState::Unlock2,
+            (FlashState::Unlock2, 0x5555, 0x90) => FlashState::Id,
+            (FlashState::Unlock2, 0x5555, 0xA0) => FlashState::Program,
+            (FlashState::Unlock2, 0x5555, 0x80) => FlashState::Erase,
+            (FlashState::Erase, 0x5555, 0xAA) => FlashState::EraseUnlock1,
+            (FlashState::EraseUnlock1, 0x2AAA, 0x55) => FlashState::EraseUnlock2,
+            (FlashState::EraseUnlock2, _, 0x30) => {
+                let sector = offset - offset % FLASH_SECTOR_SIZE_BYTES;
+                for byte in self.data[sector..sector + FLASH_SECTOR_SIZE_BYTES].iter_mut() {
+                    *byte = 0xFF;
+                }
+                FlashState::Read
+            },
+            (FlashState::EraseUnlock2, 0x5555, 0x10) => {
+                for byte in self.data.iter_mut() {
+                    *byte = 0xFF;
+                }
+                FlashState::Read
+            },
+            // Anything else aborts the command
+            (FlashState::Id, _, _) => FlashState::Id,
+            _ => FlashState::Read
+        };
+    }
+}
+
+/// Source of one of the two ROM/flash windows.
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct RomWindow {
+    bank: u8,
+    flash: bool
+}
+
+/// A cartridge using the MBC6 memory bank controller, with 1 MiB of ROM,
+/// 32 KiB of RAM and 1 MiB of flash.
+///
+/// 0x4000 - 0x5FFF and 0x6000 - 0x7FFF are separate 8 KiB windows which can
+/// each map any bank of ROM or flash. 0xA000 - 0xAFFF and 0xB000 - 0xBFFF are
+/// separate 4 KiB windows onto RAM. Registers are written through
+/// 0x0000 - 0x3FFF:
+///
+///   - 0x0000 - 0x03FF: RAM enable, enabled by writing 0x0A
+///   - 0x0400 - 0x07FF: RAM bank mapped at 0xA000 - 0xAFFF
+///   - 0x0800 - 0x0BFF: RAM bank mapped at 0xB000 - 0xBFFF
+///   - 0x0C00 - 0x0FFF: Flash enable (bit 0)
+///   - 0x1000: Flash write enable (bit 0)
+///   - 0x2000 - 0x27FF: Bank mapped at 0x4000 - 0x5FFF
+///   - 0x2800 - 0x2FFF: Source of 0x4000 - 0x5FFF, 0x00 for ROM or 0x08 for
+///     flash
+///   - 0x3000 - 0x37FF: Bank mapped at 0x6000 - 0x7FFF
+///   - 0x3800 - 0x3FFF: Source of 0x6000 - 0x7FFF
+///
+/// Flash commands are written through a window mapping flash, using flash
+/// addresses so 0x5555 is bank 2 offset 0x1555.
+pub struct Mbc6 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    flash: Flash,
+    ram_enabled: bool,
+    ram_banks: [u8; 2],
+    flash_enabled: bool,
+    flash_write_enabled: bool,
+    rom_windows: [RomWindow; 2]
+}
+
+impl Mbc6 {
+    pub fn new(rom: Vec<u8>) -> Self {
+        let window = RomWindow { bank: 0, flash: false };
+        Self {
+            rom,
+            ram: vec![0; MBC6_RAM_SIZE_BYTES],
+            flash: Flash::new(),
+            ram_enabled: false,
+            ram_banks: [0; 2],
+            flash_enabled: false,
+            flash_write_enabled: false,
+            rom_windows: [window; 2]
+        }
+    }
+
+    /// Contents of the flash memory, which should be saved with the
+    /// cartridge RAM.
+    pub fn flash(&self) -> &[u8] {
+        &self.flash.data
+    }
+
+    /// Restore the contents of the flash memory. Any data past the size of
+    /// the flash is ignored.
+    pub fn load_flash(&mut self, data: &[u8]) {
+        let length = cmp::min(data.len(), MBC6_FLASH_SIZE_BYTES);
+        self.flash.data[..length].copy_from_slice(&data[..length]);
+    }
+
+    /// Window and offset within it of an address in 0x4000 - 0x7FFF.
+    fn rom_window(&self, address: Address) -> (RomWindow, usize) {
+        let window = self.rom_windows[(address as usize - 0x4000) / ROM_WINDOW_SIZE_BYTES];
+        (window, window.bank as usize * ROM_WINDOW_SIZE_BYTES + (address as usize & (ROM_WINDOW_SIZE_BYTES - 1)))
+    }
+
+    /// Offset into RAM of an address in 0xA000 - 0xBFFF.
+    fn ram_offset(&self, address: Address) -> usize {
+        let bank = self.ram_banks[(address as usize - 0xA000) / RAM_WINDOW_SIZE_BYTES] as usize;
+        (bank * RAM_WINDOW_SIZE_BYTES + (address as usize & (RAM_WINDOW_SIZE_BYTES - 1))) % self.ram.len()
+    }
+}
+
+impl Cartridge for Mbc6 {
+    fn read_byte(&self, address: Address) -> u8 {
+        match address {
+            0x0000..=0x3FFF => rom_bank_byte(&self.rom, 0, address),
+            0x4000..=0x7FFF => {
+                let (window, offset) = self.rom_window(address);
+                if window.flash {
+                    if !self.flash_enabled {
+                        return 0xFF;
+                    }
+                    self.flash.read(offset % MBC6_FLASH_SIZE_BYTES)
+                } else {
+                    self.rom[offset % self.rom.len()]
+                }
+            },
+            0xA000..=0xBFFF => {
+                if !self.ram_enabled {
+                    return 0xFF;
+                }
+                self.ram[self.ram_offset(address)]
+            },
+            _ => panic!("Unsupported read from address {:04X}", address)
+        }
+    }
+
+    fn write_byte(&mut self, address: Address, value: u8) {
+        match address {
+            0x0000..=0x03FF => self.ram_enabled = value == 0x0A,
+            0x0400..=0x07FF => self.ram_banks[0] = value & 0x07,
+            0x0800..=0x0BFF => self.ram_banks[1] = value & 0x07,
+            0x0C00..=0x0FFF => self.flash_enabled = value & 0x01 != 0,
+            0x1000 => self.flash_write_enabled = value & 0x01 != 0,
+            0x2000..=0x27FF => self.rom_windows[0].bank = value & 0x7F,
+            0x2800..=0x2FFF => self.rom_windows[0].flash = value == 0x08,
+            0x3000..=0x37FF => self.rom_windows[1].bank = value & 0x7F,
+            0x3800..=0x3FFF => self.rom_windows[1].flash = value == 0x08,
+            0x4000..=0x7FFF => {
+                let (window, offset) = self.rom_window(address);
+                if window.flash && self.flash_enabled && self.flash_write_enabled {
+                    self.flash.write(offset % MBC6_FLASH_SIZE_BYTES, value);
+                }
+            },
+            0xA000..=0xBFFF if self.ram_enabled => {
+                let offset = self.ram_offset(address);
+                self.ram[offset] = value;
+            },
+            _ => {
+                // No registers here, or RAM is disabled
+            }
+        }
+    }
+
+    fn battery(&self) -> bool {
+        true
+    }
+
+    /// The RAM followed by the flash.
+    fn save_ram(&self) -> Vec<u8> {
+        let mut data = self.ram.clone();
+        data.extend_from_slice(self.flash());
+        data
+    }
+
+    fn load_save_ram(&mut self, data: &[u8]) {
+        load_ram(&mut self.ram, data);
+        if data.len() > MBC6_RAM_SIZE_BYTES {
+            self.load_flash(&data[MBC6_RAM_SIZE_BYTES..]);
+        }
+    }
+}
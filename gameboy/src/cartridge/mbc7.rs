@@ -0,0 +1,303 @@
+use super::*;
+
+/// Size of the 93LC56 EEPROM on MBC7 cartridges in bytes.
+pub const MBC7_EEPROM_SIZE_BYTES: usize = 0x100;
+/// Accelerometer reading with the cartridge held level.
+pub const ACCELEROMETER_CENTRE: u16 = 0x81D0;
+/// Change in accelerometer reading for 1g of tilt.
+pub const ACCELEROMETER_GRAVITY: f32 = 112.0;
+
+/// Number of 16-bit words in the EEPROM.
+const EEPROM_WORDS: usize = MBC7_EEPROM_SIZE_BYTES / 2;
+/// Number of bits making up an EEPROM command after its start bit, 2 bits
+/// of opcode and 8 of address.
+const EEPROM_COMMAND_BITS: u8 = 10;
+
+const EEPROM_CS: u8 = 0x80;
+const EEPROM_CLK: u8 = 0x40;
+const EEPROM_DI: u8 = 0x02;
+const EEPROM_DO: u8 = 0x01;
+
+/// States of the EEPROM serial protocol.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum EepromState {
+    /// Waiting for a start bit
+    Idle,
+    /// Shifting in a command, with the bits received so far
+    Command { bits: u16, count: u8 },
+    /// Shifting out words starting from the given address
+    Read { address: u8, count: u8 },
+    /// Shifting in a word to write to the given address, or every address
+    Write { address: Option<u8>, bits: u16, count: u8 }
+}
+
+/// 93LC56 serial EEPROM organised as 128 16-bit words, driven by bit banging
+/// its chip select, clock and data lines.
+///
+/// Commands are a start bit, 2 bits of opcode and 8 bits of address shifted
+/// in MSB first on each rising clock edge:
+///
+///   - 10 AAAAAAAA: READ, shifts out a dummy 0 bit then the words from the
+///     address onwards
+///   - 01 AAAAAAAA: WRITE, followed by 16 bits of data
+///   - 11 AAAAAAAA: ERASE, setting the word to 0xFFFF
+///   - 00 11xxxxxx: EWEN, enable writes and erases
+///   - 00 00xxxxxx: EWDS, disable writes and erases
+///   - 00 10xxxxxx: ERAL, erase all words
+///   - 00 01xxxxxx: WRAL, followed by 16 bits of data written to all words
+///
+/// Writes complete instantly, so the data out line always reports ready.
+struct Eeprom {
+    data: [u8; MBC7_EEPROM_SIZE_BYTES],
+    state: EepromState,
+    write_enabled: bool,
+    /// Chip select, clock and data in lines as last written
+    lines: u8,
+    data_out: bool
+}
+
+impl Eeprom {
+    fn new() -> Self {
+        Self {
+            data: [0xFF; MBC7_EEPROM_SIZE_BYTES],
+            state: EepromState::Idle,
+            write_enabled: false,
+            lines: 0x00,
+            data_out: true
+        }
+    }
+
+    fn word(&self, address: u8) -> u16 {
+        let offset = (address as usize % EEPROM_WORDS) * 2;
+        make_u16(self.data[offset], self.data[offset + 1])
+    }
+
+    fn set_word(&mut self, address: u8, value: u16) {
+        if !self.write_enabled {
+            return;
+        }
+        let offset = (address as usize % EEPROM_WORDS) * 2;
+        self.data[offset] = (value >> 8) as u8;
+        self.data[offset + 1] = value as u8;
+    }
+
+    fn read(&self) -> u8 {
+        self.lines | if self.data_out { EEPROM_DO } else { 0x00 }
+    }
+
+    fn write(&mut self, value: u8) {
+        let previous = self.lines;
+        self.lines = value & (EEPROM_CS | EEPROM_CLK | EEPROM_DI);
+
+        if self.lines & EEPROM_CS == 0 {
+            self.state = EepromState::Idle;
+            self.data_out = true;
+            return;
+        }
+
+        let rising_edge = previous & EEPROM_CLK == 0 && self.lines & EEPROM_CLK != 0;
+        if rising_edge {
+            self.clock(self.lines & EEPROM_DI != 0);
+        }
+    }
+
+    /// Shift a bit in on a rising clock edge.
+    fn clock(&mut self, bit: bool) {
+        self.state = match self.state {
+            EepromState::Idle if bit => EepromState::Command { bits: 0, count: 0 },
+            EepromState::Idle => EepromState::Idle,
+            EepromState::Command { bits, count } => {
+                let bits = bits << 1 | bit as u16;
+                if count + 1 < EEPROM_COMMAND_BITS {
+                    EepromState::Command { bits, count: count + 1 }
+                } else {
+                    self.command(bits)
+                }
+            },
+            EepromState::Read { address, count } => {
+                let word = self.word(address);
+                self.data_out = word & (0x8000 >> count) != 0;
+                if count == 15 {
+                    EepromState::Read { address: address.wrapping_add(1), count: 0 }
+                } else {
+                    EepromState::Read { address, count: count + 1 }
+                }
+            },
+            EepromState::Write { address, bits, count } => {
+                let bits = bits << 1 | bit as u16;
+                if count < 15 {
+                    EepromState::Write { address, bits, count: count + 1 }
+                } else {
+                    match address {
+                        Some(address) => self.set_word(address, bits),
+                        None => (0..EEPROM_WORDS as u8).for_each(|address| self.set_word(address, bits))
+                    }
+                    self.data_out = true;
+                    EepromState::Idle
+                }
+            }
+        };
+    }
+
+    /// Run a command once its opcode and address have been shifted in.
+    fn command(&mut self, bits: u16) -> EepromState {
+        let address = bits as u8;
+        match (bits >> 8, address >> 6) {
+            (0b10, _) => {
+                // Dummy bit before the data
+                self.data_out = false;
+                EepromState::Read { address, count: 0 }
+            },
+            (0b01, _) => EepromState::Write { address: Some(address), bits: 0, count: 0 },
+            (0b11, _) => {
+                self.set_word(address, 0xFFFF);
+                EepromState::Idle
+            },
+            (_, 0b11) => {
+                self.write_enabled = true;
+                EepromState::Idle
+            },
+            (_, 0b00) => {
+                self.write_enabled = false;
+                EepromState::Idle
+            },
+            (_, 0b10) => {
+                (0..EEPROM_WORDS as u8).for_each(|address| self.set_word(address, 0xFFFF));
+                EepromState::Idle
+            },
+            _ => EepromState::Write { address: None, bits: 0, count: 0 }
+        }
+    }
+}
+
+/// A cartridge using the MBC7 memory bank controller, with up to 2 MiB of ROM,
+/// a 2-axis accelerometer and a 93LC56 EEPROM in place of RAM.
+///
+/// Registers are written through the ROM area:
+///
+///   - 0x0000 - 0x1FFF: First register enable, enabled by writing 0x0A
+///   - 0x2000 - 0x3FFF: ROM bank number mapped at 0x4000 - 0x7FFF
+///   - 0x4000 - 0x5FFF: Second register enable, enabled by writing 0x40
+///
+/// With both enabled, bits 4-7 of addresses in 0xA000 - 0xAFFF select a
+/// register:
+///
+///   - 0xA00x: Writing 0x55 clears the accelerometer latch
+///   - 0xA01x: Writing 0xAA latches the accelerometer, once cleared
+///   - 0xA02x - 0xA05x: Latched X low and high, then Y low and high
+///   - 0xA06x: Always 0x00
+///   - 0xA07x: Always 0xFF
+///   - 0xA08x: EEPROM lines, bit 7 chip select, bit 6 clock, bit 1 data in and
+///     bit 0 data out
+pub struct Mbc7 {
+    rom: Vec<u8>,
+    eeprom: Eeprom,
+    enable1: bool,
+    enable2: bool,
+    rom_bank: u8,
+    /// Current accelerometer reading
+    accelerometer: (u16, u16),
+    /// Accelerometer reading as of the last latch
+    latched: (u16, u16),
+    /// Accelerometer latch cleared, ready to latch again
+    latch_cleared: bool
+}
+
+impl Mbc7 {
+    pub fn new(rom: Vec<u8>) -> Self {
+        let centre = (ACCELEROMETER_CENTRE, ACCELEROMETER_CENTRE);
+        Self {
+            rom,
+            eeprom: Eeprom::new(),
+            enable1: false,
+            enable2: false,
+            rom_bank: 1,
+            accelerometer: centre,
+            latched: (0x8000, 0x8000),
+            latch_cleared: false
+        }
+    }
+
+    /// Contents of the EEPROM, which should be saved as the cartridge RAM.
+    /// Words are stored most significant byte first.
+    pub fn eeprom(&self) -> &[u8] {
+        &self.eeprom.data
+    }
+
+    /// Restore the contents of the EEPROM. Any data past the size of the
+    /// EEPROM is ignored.
+    pub fn load_eeprom(&mut self, data: &[u8]) {
+        let length = cmp::min(data.len(), MBC7_EEPROM_SIZE_BYTES);
+        self.eeprom.data[..length].copy_from_slice(&data[..length]);
+    }
+
+    fn registers_enabled(&self) -> bool {
+        self.enable1 && self.enable2
+    }
+}
+
+impl Cartridge for Mbc7 {
+    fn read_byte(&self, address: Address) -> u8 {
+        match address {
+            0x0000..=0x3FFF => rom_bank_byte(&self.rom, 0, address),
+            0x4000..=0x7FFF => rom_bank_byte(&self.rom, self.rom_bank as usize, address),
+            0xA000..=0xAFFF if self.registers_enabled() => match (address >> 4) & 0x0F {
+                0x02 => self.latched.0 as u8,
+                0x03 => (self.latched.0 >> 8) as u8,
+                0x04 => self.latched.1 as u8,
+                0x05 => (self.latched.1 >> 8) as u8,
+                0x06 => 0x00,
+                0x08 => self.eeprom.read(),
+                _ => 0xFF
+            },
+            0xA000..=0xBFFF => 0xFF,
+            _ => panic!("Unsupported read from address {:04X}", address)
+        }
+    }
+
+    fn write_byte(&mut self, address: Address, value: u8) {
+        match address {
+            0x0000..=0x1FFF => {
+                self.enable1 = value == 0x0A;
+                if !self.enable1 {
+                    self.enable2 = false;
+                }
+            },
+            0x2000..=0x3FFF => self.rom_bank = value & 0x7F,
+            0x4000..=0x5FFF => self.enable2 = self.enable1 && value == 0x40,
+            0xA000..=0xAFFF if self.registers_enabled() => match ((address >> 4) & 0x0F, value) {
+                (0x00, 0x55) => {
+                    self.latched = (0x8000, 0x8000);
+                    self.latch_cleared = true;
+                },
+                (0x01, 0xAA) if self.latch_cleared => {
+                    self.latched = self.accelerometer;
+                    self.latch_cleared = false;
+                },
+                (0x08, _) => self.eeprom.write(value),
+                _ => {}
+            },
+            _ => {
+                // No registers here, or registers are disabled
+            }
+        }
+    }
+
+    fn set_accelerometer(&mut self, x: f32, y: f32) {
+        let reading = |g: f32| (ACCELEROMETER_CENTRE as f32 + g * ACCELEROMETER_GRAVITY) as u16;
+        self.accelerometer = (reading(x), reading(y));
+    }
+
+    fn battery(&self) -> bool {
+        true
+    }
+
+    /// The EEPROM, as the cartridge has no RAM.
+    fn save_ram(&self) -> Vec<u8> {
+        self.eeprom().to_vec()
+    }
+
+    fn load_save_ram(&mut self, data: &[u8]) {
+        self.load_eeprom(data);
+    }
+}
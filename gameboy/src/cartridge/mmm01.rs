@@ -0,0 +1,183 @@
+use super::*;
+
+/// ROM bank mapped at 0x0000 - 0x3FFF before a game is selected. Along with
+/// the next bank this is the last 32 KiB of the ROM, as the bank number
+/// wraps around.
+const MENU_ROM_BANK: u16 = 0x1FE;
+
+/// A multicart using the MMM01 memory bank controller, with up to 8 MiB of
+/// ROM and 128 KiB of RAM.
+///
+/// At boot the menu in the last 32 KiB of ROM is mapped at 0x0000 - 0x7FFF
+/// and every register bit can be written. The menu picks a game by setting
+/// the upper ROM and RAM bank bits and masking the lower ones, then writing
+/// the map enable bit. From then on only the unmasked bits can be changed,
+/// so the game sees an MBC1 with its own slice of ROM and RAM. Registers are
+/// written through the ROM area:
+///
+///   - 0x0000 - 0x1FFF: RAM enable (0x0A in bits 0-3), RAM bank mask
+///     (bits 4-5, menu only) and map enable (bit 6, menu only)
+///   - 0x2000 - 0x3FFF: ROM bank number bits 0-4, and bits 5-6 in the menu
+///   - 0x4000 - 0x5FFF: RAM bank number bits 0-1, and in the menu RAM bank
+///     bits 2-3 (bits 2-3), ROM bank bits 7-8 (bits 4-5) and banking mode
+///     write protect (bit 6)
+///   - 0x6000 - 0x7FFF: Banking mode (bit 0) and in the menu the ROM bank
+///     mask (bits 2-5), which protects ROM bank bits 1-4
+pub struct Mmm01 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    ram_enabled: bool,
+    /// A game has been selected, locking the masked register bits
+    mapped: bool,
+    /// ROM bank number (9 bits)
+    rom_bank: u16,
+    /// Bits set protect ROM bank bits 1-4
+    rom_bank_mask: u8,
+    /// RAM bank number (4 bits)
+    ram_bank: u8,
+    /// Bits set protect RAM bank bits 0-1
+    ram_bank_mask: u8,
+    /// Banking mode 1, as for MBC1
+    mode: bool,
+    mode_locked: bool,
+    has_battery: bool
+}
+
+impl Mmm01 {
+    /// Create an MMM01 cartridge from ROM data with the given amount of RAM,
+    /// backed by a battery if `has_battery` is set.
+    pub fn new(rom: Vec<u8>, ram_size_bytes: usize, has_battery: bool) -> Self {
+        Self {
+            rom,
+            ram: vec![0; ram_size_bytes],
+            ram_enabled: false,
+            mapped: false,
+            rom_bank: 0,
+            rom_bank_mask: 0,
+            ram_bank: 0,
+            ram_bank_mask: 0,
+            mode: false,
+            mode_locked: false,
+            has_battery
+        }
+    }
+
+    /// Indicates whether a game has been selected from the menu.
+    pub fn mapped(&self) -> bool {
+        self.mapped
+    }
+
+    /// Bits of the ROM bank number which can be changed by the game.
+    fn game_rom_bits(&self) -> u16 {
+        0x1F & !((self.rom_bank_mask as u16) << 1)
+    }
+
+    /// Bits of the RAM bank number which can be changed by the game.
+    fn game_ram_bits(&self) -> u8 {
+        0x03 & !self.ram_bank_mask
+    }
+
+    /// ROM bank mapped at 0x0000 - 0x3FFF, the first bank of the game.
+    fn low_rom_bank(&self) -> usize {
+        if !self.mapped {
+            return MENU_ROM_BANK as usize;
+        }
+        (self.rom_bank & !self.game_rom_bits()) as usize
+    }
+
+    /// ROM bank mapped at 0x4000 - 0x7FFF. As on MBC1 selecting the game's
+    /// bank 0 selects its bank 1 instead.
+    fn high_rom_bank(&self) -> usize {
+        if !self.mapped {
+            return MENU_ROM_BANK as usize + 1;
+        }
+        if self.rom_bank & self.game_rom_bits() == 0 {
+            (self.rom_bank | 0x01) as usize
+        } else {
+            self.rom_bank as usize
+        }
+    }
+
+    /// RAM bank mapped at 0xA000 - 0xBFFF. In mode 0 the game's RAM bank
+    /// bits are ignored.
+    fn ram_bank(&self) -> usize {
+        if self.mode {
+            self.ram_bank as usize
+        } else {
+            (self.ram_bank & !self.game_ram_bits()) as usize
+        }
+    }
+}
+
+impl Cartridge for Mmm01 {
+    fn read_byte(&self, address: Address) -> u8 {
+        match address {
+            0x0000..=0x3FFF => rom_bank_byte(&self.rom, self.low_rom_bank(), address),
+            0x4000..=0x7FFF => rom_bank_byte(&self.rom, self.high_rom_bank(), address),
+            0xA000..=0xBFFF => {
+                if !self.ram_enabled || self.ram.is_empty() {
+                    return 0xFF;
+                }
+                self.ram[ram_bank_offset(&self.ram, self.ram_bank(), address)]
+            },
+            _ => panic!("Unsupported read from address {:04X}", address)
+        }
+    }
+
+    fn write_byte(&mut self, address: Address, value: u8) {
+        match address {
+            0x0000..=0x1FFF => {
+                self.ram_enabled = value & 0x0F == 0x0A;
+                if !self.mapped {
+                    self.ram_bank_mask = (value >> 4) & 0x03;
+                    self.mapped = value & 0x40 != 0;
+                }
+            },
+            0x2000..=0x3FFF => {
+                let writable = if self.mapped { self.game_rom_bits() } else { 0x7F };
+                self.rom_bank = write_bits(self.rom_bank, value as u16, writable);
+            },
+            0x4000..=0x5FFF => {
+                if self.mapped {
+                    self.ram_bank = write_bits(self.ram_bank as u16, value as u16, self.game_ram_bits() as u16) as u8;
+                } else {
+                    self.ram_bank = value & 0x0F;
+                    self.rom_bank = (self.rom_bank & 0x7F) | ((value as u16 >> 4) & 0x03) << 7;
+                    self.mode_locked = value & 0x40 != 0;
+                }
+            },
+            0x6000..=0x7FFF => {
+                if !self.mode_locked {
+                    self.mode = value & 0x01 != 0;
+                }
+                if !self.mapped {
+                    self.rom_bank_mask = (value >> 2) & 0x0F;
+                }
+            },
+            0xA000..=0xBFFF => {
+                if self.ram_enabled && !self.ram.is_empty() {
+                    let offset = ram_bank_offset(&self.ram, self.ram_bank(), address);
+                    self.ram[offset] = value;
+                }
+            },
+            _ => panic!("Unsupported write to address {:04X}", address)
+        }
+    }
+
+    fn battery(&self) -> bool {
+        self.has_battery
+    }
+
+    fn save_ram(&self) -> Vec<u8> {
+        self.ram.clone()
+    }
+
+    fn load_save_ram(&mut self, data: &[u8]) {
+        load_ram(&mut self.ram, data);
+    }
+}
+
+/// Update the bits of a register which are currently writable.
+fn write_bits(register: u16, value: u16, writable: u16) -> u16 {
+    (register & !writable) | (value & writable)
+}
@@ -0,0 +1,277 @@
+use super::*;
+
+/// Number of cycles at normal speed in one second of real time.
+pub const CYCLES_PER_SECOND: u32 = 4_194_304;
+
+/// Address of the RTC seconds register, selected through 0x4000 - 0x5FFF.
+pub const RTC_SECONDS: u8 = 0x08;
+/// Address of the RTC minutes register.
+pub const RTC_MINUTES: u8 = 0x09;
+/// Address of the RTC hours register.
+pub const RTC_HOURS: u8 = 0x0A;
+/// Address of the register holding the lower 8 bits of the RTC day counter.
+pub const RTC_DAYS_LOW: u8 = 0x0B;
+/// Address of the register holding bit 8 of the RTC day counter, the halt
+/// flag and the day counter carry flag.
+pub const RTC_DAYS_HIGH: u8 = 0x0C;
+
+/// Size of the RTC footer appended to `.sav` files by VBA-M and BGB.
+pub const RTC_FOOTER_SIZE_BYTES: usize = 48;
+/// Size of the older form of the RTC footer, with a 32-bit timestamp.
+pub const RTC_FOOTER_LEGACY_SIZE_BYTES: usize = 44;
+
+const DAYS_HIGH_BIT: u8 = 0x01;
+const HALT_FLAG: u8 = 0x40;
+const CARRY_FLAG: u8 = 0x80;
+
+/// Real time clock of MBC3 cartridges.
+///
+/// The clock counts seconds, minutes, hours and a 9-bit day counter, setting
+/// a carry flag once the day counter overflows. Reads return a copy of the
+/// registers taken when the clock was last latched, while writes go straight
+/// to the running clock. Each counter only wraps when it reaches its usual
+/// limit, so values outside that range count up to the register's width.
+pub struct Rtc {
+    seconds: u8,
+    minutes: u8,
+    hours: u8,
+    /// Lower 8 bits of the day counter
+    days_low: u8,
+    /// Day counter bit 8, halt and carry flags
+    days_high: u8,
+    /// Registers as of the last latch, in register order
+    latched: [u8; 5],
+    /// Cycles elapsed towards the next second
+    cycles: u32
+}
+
+impl Rtc {
+    pub fn new() -> Self {
+        Self {
+            seconds: 0,
+            minutes: 0,
+            hours: 0,
+            days_low: 0,
+            days_high: 0,
+            latched: [0; 5],
+            cycles: 0
+        }
+    }
+
+    /// Advance the clock by the given number of cycles at normal speed,
+    /// unless it is halted.
+    pub fn tick(&mut self, cycles: Cycles) {
+        if self.days_high & HALT_FLAG != 0 {
+            return;
+        }
+
+        self.cycles += cycles as u32;
+        while self.cycles >= CYCLES_PER_SECOND {
+            self.cycles -= CYCLES_PER_SECOND;
+            self.increment_seconds();
+        }
+    }
+
+    /// Copy the running clock into the registers which are read.
+    pub fn latch(&mut self) {
+        self.latched = [self.seconds, self.minutes, self.hours, self.days_low, self.days_high];
+    }
+
+    /// Read one of the latched clock registers.
+    pub fn read(&self, register: u8) -> u8 {
+        match register {
+            RTC_SECONDS..=RTC_DAYS_HIGH => self.latched[(register - RTC_SECONDS) as usize],
+            _ => panic!("Unsupported RTC read from register {:02X}", register)
+        }
+    }
+
+    /// Write one of the running clock registers. Writing the seconds resets
+    /// the time until the next second.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use gameboy::cartridge::*;
+    /// let mut rtc = Rtc::new();
+    /// rtc.write(RTC_SECONDS, 59);
+    /// rtc.write(RTC_MINUTES, 59);
+    /// rtc.tick(0x8000);
+    /// rtc.latch();
+    /// assert_eq!(rtc.read(RTC_SECONDS), 59);
+    /// for _ in 0..(CYCLES_PER_SECOND / 0x8000) {
+    ///     rtc.tick(0x8000);
+    /// }
+    /// rtc.latch();
+    /// assert_eq!(rtc.read(RTC_SECONDS), 0);
+    /// assert_eq!(rtc.read(RTC_MINUTES), 0);
+    /// assert_eq!(rtc.read(RTC_HOURS), 1);
+    /// ```
+    pub fn write(&mut self, register: u8, value: u8) {
+        match register {
+            RTC_SECONDS => {
+                self.seconds = value & 0x3F;
+                self.cycles = 0;
+            },
+            RTC_MINUTES => self.minutes = value & 0x3F,
+            RTC_HOURS => self.hours = value & 0x1F,
+            RTC_DAYS_LOW => self.days_low = value,
+            RTC_DAYS_HIGH => self.days_high = value & (DAYS_HIGH_BIT | HALT_FLAG | CARRY_FLAG),
+            _ => panic!("Unsupported RTC write to register {:02X}", register)
+        }
+    }
+
+    /// Footer saving the clock at the given UNIX time.
+    pub fn footer(&self, timestamp: u64) -> RtcFooter {
+        RtcFooter {
+            live: [self.seconds, self.minutes, self.hours, self.days_low, self.days_high],
+            latched: self.latched,
+            timestamp
+        }
+    }
+
+    /// Restore the clock from a footer, then advance it by the time between
+    /// the footer being saved and the given UNIX time.
+    pub fn load_footer(&mut self, footer: &RtcFooter, timestamp: u64) {
+        for (index, &value) in footer.live.iter().enumerate() {
+            self.write(RTC_SECONDS + index as u8, value);
+        }
+        self.latched = footer.latched;
+        self.advance_seconds(timestamp.saturating_sub(footer.timestamp));
+    }
+
+    /// Advance the clock by the given number of seconds, unless it is halted.
+    fn advance_seconds(&mut self, mut seconds: u64) {
+        if self.days_high & HALT_FLAG != 0 {
+            return;
+        }
+
+        // Out of range values wrap at their register width, so step through
+        // those a second at a time
+        while seconds > 0 && (self.seconds >= 60 || self.minutes >= 60 || self.hours >= 24) {
+            self.increment_seconds();
+            seconds -= 1;
+        }
+
+        let seconds = self.seconds as u64 + seconds;
+        let minutes = self.minutes as u64 + seconds / 60;
+        let hours = self.hours as u64 + minutes / 60;
+        let days = (self.days_high & DAYS_HIGH_BIT) as u64 * 0x100 + self.days_low as u64 + hours / 24;
+        self.seconds = (seconds % 60) as u8;
+        self.minutes = (minutes % 60) as u8;
+        self.hours = (hours % 24) as u8;
+        self.days_low = days as u8;
+        self.days_high = (self.days_high & !DAYS_HIGH_BIT) | ((days >> 8) & 0x01) as u8;
+        if days >= 0x200 {
+            self.days_high |= CARRY_FLAG;
+        }
+    }
+
+    fn increment_seconds(&mut self) {
+        self.seconds = (self.seconds + 1) & 0x3F;
+        if self.seconds == 60 {
+            self.seconds = 0;
+            self.increment_minutes();
+        }
+    }
+
+    fn increment_minutes(&mut self) {
+        self.minutes = (self.minutes + 1) & 0x3F;
+        if self.minutes == 60 {
+            self.minutes = 0;
+            self.increment_hours();
+        }
+    }
+
+    fn increment_hours(&mut self) {
+        self.hours = (self.hours + 1) & 0x1F;
+        if self.hours == 24 {
+            self.hours = 0;
+            self.increment_days();
+        }
+    }
+
+    fn increment_days(&mut self) {
+        let (days_low, overflow) = self.days_low.overflowing_add(1);
+        self.days_low = days_low;
+        if !overflow {
+            return;
+        }
+
+        if self.days_high & DAYS_HIGH_BIT != 0 {
+            self.days_high = (self.days_high & !DAYS_HIGH_BIT) | CARRY_FLAG;
+        } else {
+            self.days_high |= DAYS_HIGH_BIT;
+        }
+    }
+}
+
+/// Clock state saved in the footer VBA-M and BGB append to `.sav` files after
+/// the cartridge RAM, so saves can move between emulators.
+///
+/// The footer holds the live registers, then the latched registers, in RTC
+/// register order as little endian 32-bit values, followed by the UNIX time
+/// it was saved at as a 64-bit value. Older saves use a 32-bit timestamp.
+///
+/// # Examples
+///
+/// ```
+/// # use gameboy::cartridge::*;
+/// let footer = RtcFooter { live: [1, 2, 3, 4, 0], latched: [0; 5], timestamp: 1_000_000 };
+/// let bytes = footer.to_bytes();
+/// assert_eq!(bytes.len(), RTC_FOOTER_SIZE_BYTES);
+/// assert_eq!(RtcFooter::from_bytes(&bytes), Some(footer));
+/// ```
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct RtcFooter {
+    /// Running clock registers
+    pub live: [u8; 5],
+    /// Registers as of the last latch
+    pub latched: [u8; 5],
+    /// UNIX time in seconds when the footer was saved
+    pub timestamp: u64
+}
+
+impl RtcFooter {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(RTC_FOOTER_SIZE_BYTES);
+        for &register in self.live.iter().chain(self.latched.iter()) {
+            bytes.extend_from_slice(&(register as u32).to_le_bytes());
+        }
+        bytes.extend_from_slice(&self.timestamp.to_le_bytes());
+        bytes
+    }
+
+    /// Parse a footer of either size, returning None if the data is too
+    /// short to be a footer.
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        if data.len() < RTC_FOOTER_LEGACY_SIZE_BYTES {
+            return None;
+        }
+
+        let value = |index: usize| {
+            let mut bytes = [0; 4];
+            bytes.copy_from_slice(&data[index * 4..index * 4 + 4]);
+            u32::from_le_bytes(bytes)
+        };
+        let mut live = [0; 5];
+        let mut latched = [0; 5];
+        for register in 0..5 {
+            live[register] = value(register) as u8;
+            latched[register] = value(register + 5) as u8;
+        }
+        let timestamp = if data.len() >= RTC_FOOTER_SIZE_BYTES {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(&data[40..48]);
+            u64::from_le_bytes(bytes)
+        } else {
+            value(10) as u64
+        };
+        Some(Self { live, latched, timestamp })
+    }
+}
+
+impl Default for Rtc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,340 @@
+use super::*;
+
+/// Size of the TAMA5's internal RAM in bytes.
+pub const TAMA5_RAM_SIZE_BYTES: usize = 0x20;
+/// Size of the clock state saved after the TAMA5's internal RAM in bytes,
+/// made up of the clock and alarm followed by a UNIX timestamp.
+pub const TAMA5_RTC_SIZE_BYTES: usize = CLOCK_SIZE_BYTES + 8;
+
+/// Size of the clock and alarm fields saved by `Tama5Clock::save`.
+const CLOCK_SIZE_BYTES: usize = 10;
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+/// Days before the date repeats, including the day of the week. The year
+/// wraps every 100 years, 25 of which are leap years.
+const DAYS_PER_CYCLE: u64 = (100 * 365 + 25) * 7;
+
+const REGISTER_BANK_LOW: u8 = 0x0;
+const REGISTER_BANK_HIGH: u8 = 0x1;
+const REGISTER_WRITE_LOW: u8 = 0x4;
+const REGISTER_WRITE_HIGH: u8 = 0x5;
+const REGISTER_COMMAND: u8 = 0x6;
+const REGISTER_ADDRESS: u8 = 0x7;
+const REGISTER_READY: u8 = 0xA;
+const REGISTER_READ_LOW: u8 = 0xC;
+const REGISTER_READ_HIGH: u8 = 0xD;
+
+const COMMAND_RAM_WRITE: u8 = 0x0;
+const COMMAND_RAM_READ: u8 = 0x1;
+const COMMAND_RTC_WRITE: u8 = 0x2;
+const COMMAND_RTC_READ: u8 = 0x3;
+
+/// Clock register holding the alarm flag on the alarm page.
+const RTC_ALARM_FLAG: u8 = 0xD;
+
+/// Real time clock of TAMA5 cartridges, laid out like a TC8521.
+///
+/// Each register holds one BCD digit. Page 0 holds the time, in order the
+/// seconds, minutes and hours (ones then tens), day of the week, then the
+/// day of the month, month and year (ones then tens). Page 1 holds the alarm
+/// minutes and hours at the same registers as the time, and a flag in
+/// register 0xD set once the time reaches the alarm.
+struct Tama5Clock {
+    seconds: u8,
+    minutes: u8,
+    hours: u8,
+    weekday: u8,
+    /// Day of the month, from 1
+    day: u8,
+    /// Month, from 1
+    month: u8,
+    /// Years since the last leap year, 0 to 99
+    year: u8,
+    alarm_minutes: u8,
+    alarm_hours: u8,
+    alarm: bool,
+    /// Cycles elapsed towards the next second
+    cycles: u32
+}
+
+impl Tama5Clock {
+    fn new() -> Self {
+        Self {
+            seconds: 0,
+            minutes: 0,
+            hours: 0,
+            weekday: 0,
+            day: 1,
+            month: 1,
+            year: 0,
+            alarm_minutes: 0,
+            alarm_hours: 0,
+            alarm: false,
+            cycles: 0
+        }
+    }
+
+    /// Clock and alarm in binary, one field per byte in the order declared.
+    fn save(&self) -> [u8; CLOCK_SIZE_BYTES] {
+        [
+            self.seconds, self.minutes, self.hours, self.weekday, self.day,
+            self.month, self.year, self.alarm_minutes, self.alarm_hours, self.alarm as u8
+        ]
+    }
+
+    /// Restore the clock and alarm from `save`, clamping them to valid values.
+    fn load(&mut self, data: &[u8]) {
+        self.seconds = cmp::min(data[0], 59);
+        self.minutes = cmp::min(data[1], 59);
+        self.hours = cmp::min(data[2], 23);
+        self.weekday = cmp::min(data[3], 6);
+        self.month = data[5].clamp(1, 12);
+        self.year = cmp::min(data[6], 99);
+        self.day = data[4].clamp(1, self.days_in_month());
+        self.alarm_minutes = cmp::min(data[7], 59);
+        self.alarm_hours = cmp::min(data[8], 23);
+        self.alarm = data[9] != 0;
+        self.cycles = 0;
+    }
+
+    fn tick(&mut self, cycles: Cycles) {
+        self.cycles += cycles as u32;
+        while self.cycles >= CYCLES_PER_SECOND {
+            self.cycles -= CYCLES_PER_SECOND;
+            self.increment_seconds();
+        }
+    }
+
+    /// Advance the clock by the given number of seconds, setting the alarm
+    /// flag if the alarm time passes.
+    fn advance_seconds(&mut self, seconds: u64) {
+        // Whole days leave the time of day unchanged and pass every alarm time
+        let days = seconds / SECONDS_PER_DAY;
+        if days > 0 && self.alarm_minutes < 60 && self.alarm_hours < 24 {
+            self.alarm = true;
+        }
+        for _ in 0..days % DAYS_PER_CYCLE {
+            self.increment_day();
+        }
+        for _ in 0..seconds % SECONDS_PER_DAY {
+            self.increment_seconds();
+        }
+    }
+
+    fn increment_seconds(&mut self) {
+        self.seconds += 1;
+        if self.seconds < 60 {
+            return;
+        }
+        self.seconds = 0;
+        self.minutes += 1;
+        if self.minutes >= 60 {
+            self.minutes = 0;
+            self.hours += 1;
+        }
+        if self.hours >= 24 {
+            self.hours = 0;
+            self.increment_day();
+        }
+        if self.minutes == self.alarm_minutes && self.hours == self.alarm_hours {
+            self.alarm = true;
+        }
+    }
+
+    fn increment_day(&mut self) {
+        self.weekday = (self.weekday + 1) % 7;
+        self.day += 1;
+        if self.day > self.days_in_month() {
+            self.day = 1;
+            self.month += 1;
+        }
+        if self.month > 12 {
+            self.month = 1;
+            self.year = (self.year + 1) % 100;
+        }
+    }
+
+    fn days_in_month(&self) -> u8 {
+        match self.month {
+            2 if self.year.is_multiple_of(4) => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31
+        }
+    }
+
+    /// Field holding the given register's digit, and whether the digit is the
+    /// tens rather than the ones.
+    fn field(&mut self, page: u8, register: u8) -> Option<(&mut u8, bool)> {
+        let field = match (page, register) {
+            (0, 0x0..=0x1) => &mut self.seconds,
+            (0, 0x2..=0x3) => &mut self.minutes,
+            (0, 0x4..=0x5) => &mut self.hours,
+            (0, 0x6) => &mut self.weekday,
+            (0, 0x7..=0x8) => &mut self.day,
+            (0, 0x9..=0xA) => &mut self.month,
+            (0, 0xB..=0xC) => &mut self.year,
+            (1, 0x2..=0x3) => &mut self.alarm_minutes,
+            (1, 0x4..=0x5) => &mut self.alarm_hours,
+            _ => return None
+        };
+        // Registers after the weekday are offset by one
+        let tens = if register > 0x6 { register & 0x01 == 0 } else { register & 0x01 != 0 };
+        Some((field, tens))
+    }
+
+    fn read(&mut self, page: u8, register: u8) -> u8 {
+        if page == 1 && register == RTC_ALARM_FLAG {
+            return self.alarm as u8;
+        }
+        match self.field(page, register) {
+            Some((field, true)) => *field / 10,
+            Some((field, false)) => *field % 10,
+            None => 0x0
+        }
+    }
+
+    fn write(&mut self, page: u8, register: u8, value: u8) {
+        if page == 1 && register == RTC_ALARM_FLAG {
+            self.alarm = value & 0x01 != 0;
+            return;
+        }
+        if page == 0 && register <= 0x1 {
+            self.cycles = 0;
+        }
+        let value = value % 10;
+        if let Some((field, tens)) = self.field(page, register) {
+            *field = if tens { value * 10 + *field % 10 } else { *field / 10 * 10 + value };
+        }
+    }
+}
+
+/// A cartridge using Bandai's TAMA5 controller, with up to 512 KiB of ROM,
+/// 32 bytes of internal RAM and a real time clock with an alarm.
+///
+/// Everything is accessed through 4-bit registers, selected by writing
+/// 0xA001 then read or written through 0xA000:
+///
+///   - 0x0, 0x1: ROM bank number mapped at 0x4000 - 0x7FFF, low then high
+///   - 0x4, 0x5: Value to write, low then high nibble
+///   - 0x6: Command in bits 1-3, and bit 4 of the RAM address or the clock
+///     page in bit 0
+///   - 0x7: Lower 4 bits of the RAM address or the clock register. Writing
+///     this runs the command
+///   - 0xA: Reads 0x1 once the controller is ready
+///   - 0xC, 0xD: Value read by the last command, low then high nibble
+///
+/// Commands are 0x0 to write RAM, 0x1 to read RAM, 0x2 to write a clock
+/// register with the low nibble of the value and 0x3 to read a clock
+/// register. Reads return the nibble in the lower 4 bits with the upper bits
+/// set.
+pub struct Tama5 {
+    rom: Vec<u8>,
+    ram: [u8; TAMA5_RAM_SIZE_BYTES],
+    clock: Tama5Clock,
+    register: u8,
+    registers: [u8; 16],
+    /// Value read by the last command
+    read_value: u8
+}
+
+impl Tama5 {
+    pub fn new(rom: Vec<u8>) -> Self {
+        Self {
+            rom,
+            ram: [0; TAMA5_RAM_SIZE_BYTES],
+            clock: Tama5Clock::new(),
+            register: 0,
+            registers: [0; 16],
+            read_value: 0
+        }
+    }
+
+    fn rom_bank(&self) -> usize {
+        (self.registers[REGISTER_BANK_LOW as usize] | (self.registers[REGISTER_BANK_HIGH as usize] & 0x01) << 4) as usize
+    }
+
+    /// Run the command in the command register, once the address is written.
+    fn run_command(&mut self) {
+        let command = self.registers[REGISTER_COMMAND as usize];
+        let low = self.registers[REGISTER_ADDRESS as usize];
+        let address = ((command & 0x01) << 4 | low) as usize;
+        let value = self.registers[REGISTER_WRITE_HIGH as usize] << 4 | self.registers[REGISTER_WRITE_LOW as usize];
+        match command >> 1 {
+            COMMAND_RAM_WRITE => self.ram[address] = value,
+            COMMAND_RAM_READ => self.read_value = self.ram[address],
+            COMMAND_RTC_WRITE => self.clock.write(command & 0x01, low, value),
+            COMMAND_RTC_READ => self.read_value = self.clock.read(command & 0x01, low),
+            _ => {}
+        }
+    }
+}
+
+impl Cartridge for Tama5 {
+    fn read_byte(&self, address: Address) -> u8 {
+        match address {
+            0x0000..=0x3FFF => rom_bank_byte(&self.rom, 0, address),
+            0x4000..=0x7FFF => rom_bank_byte(&self.rom, self.rom_bank(), address),
+            0xA000 => {
+                let nibble = match self.register {
+                    REGISTER_READY => 0x1,
+                    REGISTER_READ_LOW => self.read_value & 0x0F,
+                    REGISTER_READ_HIGH => self.read_value >> 4,
+                    _ => 0x0
+                };
+                0xF0 | nibble
+            },
+            0xA001..=0xBFFF => 0xFF,
+            _ => panic!("Unsupported read from address {:04X}", address)
+        }
+    }
+
+    fn write_byte(&mut self, address: Address, value: u8) {
+        match address {
+            0xA000 => {
+                self.registers[self.register as usize] = value & 0x0F;
+                if self.register == REGISTER_ADDRESS {
+                    self.run_command();
+                }
+            },
+            0xA001 => self.register = value & 0x0F,
+            _ => {
+                // No registers here
+            }
+        }
+    }
+
+    fn tick(&mut self, cycles: Cycles) {
+        self.clock.tick(cycles);
+    }
+
+    fn battery(&self) -> bool {
+        true
+    }
+
+    fn save_ram(&self) -> Vec<u8> {
+        self.ram.to_vec()
+    }
+
+    fn load_save_ram(&mut self, data: &[u8]) {
+        load_ram(&mut self.ram, data);
+    }
+
+    /// The clock and alarm, one field per byte from the seconds to the
+    /// alarm flag, followed by the UNIX time as a little endian 64-bit
+    /// value. Other emulators have no common format for the TAMA5's clock.
+    fn save_rtc(&self, timestamp: u64) -> Vec<u8> {
+        let mut footer = self.clock.save().to_vec();
+        footer.extend_from_slice(&timestamp.to_le_bytes());
+        footer
+    }
+
+    fn load_rtc(&mut self, footer: &[u8], timestamp: u64) {
+        if footer.len() < TAMA5_RTC_SIZE_BYTES {
+            return;
+        }
+        self.clock.load(&footer[..CLOCK_SIZE_BYTES]);
+        let mut saved = [0; 8];
+        saved.copy_from_slice(&footer[CLOCK_SIZE_BYTES..TAMA5_RTC_SIZE_BYTES]);
+        self.clock.advance_seconds(timestamp.saturating_sub(u64::from_le_bytes(saved)));
+    }
+}
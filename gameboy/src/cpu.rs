@@ -1,11 +1,22 @@
 use super::*;
+use super::operations::Reg16;
 use super::registers::*;
 
 /// State information for the Gameboy CPU.
 pub struct Cpu {
     pub r: Registers,
     pub pc: Address,
-    pub sp: Address
+    pub sp: Address,
+    /// Interrupt master enable (IME) flag
+    pub ime: bool,
+    /// Set by EI, IME is only enabled after the following instruction
+    pub ime_scheduled: bool,
+    /// Sleeping after HALT until an enabled interrupt is requested
+    pub halted: bool,
+    /// HALT bug triggered, the next opcode byte will be read twice
+    pub halt_bug: bool,
+    /// Clocks stopped after STOP until a joypad input line goes low
+    pub stopped: bool
 }
 
 impl Cpu {
@@ -13,7 +24,50 @@ impl Cpu {
         Self {
             r: Registers::new(),
             pc: 0x0100,
-            sp: 0xFFFE
+            sp: 0xFFFE,
+            ime: false,
+            ime_scheduled: false,
+            halted: false,
+            halt_bug: false,
+            stopped: false
         }
     }
+
+    /// Read a 16-bit register pair, including the stack pointer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use gameboy::cpu::Cpu;
+    /// # use gameboy::operations::Reg16;
+    /// let cpu = Cpu::new();
+    /// assert_eq!(cpu.reg16(Reg16::SP), 0xFFFE);
+    /// assert_eq!(cpu.reg16(Reg16::HL), 0x014D);
+    /// ```
+    pub fn reg16(&self, reg: Reg16) -> u16 {
+        match reg {
+            Reg16::AF => self.r.af(),
+            Reg16::BC => self.r.bc(),
+            Reg16::DE => self.r.de(),
+            Reg16::HL => self.r.hl(),
+            Reg16::SP => self.sp
+        }
+    }
+
+    /// Write a 16-bit register pair, including the stack pointer.
+    pub fn set_reg16(&mut self, reg: Reg16, value: u16) {
+        match reg {
+            Reg16::AF => self.r.set_af(value),
+            Reg16::BC => self.r.set_bc(value),
+            Reg16::DE => self.r.set_de(value),
+            Reg16::HL => self.r.set_hl(value),
+            Reg16::SP => self.sp = value
+        }
+    }
+}
+
+impl Default for Cpu {
+    fn default() -> Self {
+        Self::new()
+    }
 }
@@ -0,0 +1,221 @@
+use std::cmp;
+use super::*;
+
+/// Address of the OAM DMA source and start register (DMA).
+pub const DMA_ADDRESS: Address = 0xFF46;
+/// Address of the HDMA source high byte register (HDMA1).
+pub const HDMA1_ADDRESS: Address = 0xFF51;
+/// Address of the HDMA source low byte register (HDMA2).
+pub const HDMA2_ADDRESS: Address = 0xFF52;
+/// Address of the HDMA destination high byte register (HDMA3).
+pub const HDMA3_ADDRESS: Address = 0xFF53;
+/// Address of the HDMA destination low byte register (HDMA4).
+pub const HDMA4_ADDRESS: Address = 0xFF54;
+/// Address of the HDMA length, mode and start register (HDMA5).
+pub const HDMA5_ADDRESS: Address = 0xFF55;
+
+/// Number of bytes copied by each block of an HDMA transfer.
+pub const HDMA_BLOCK_SIZE_BYTES: u16 = 0x10;
+/// Number of bytes copied by an OAM DMA transfer, filling all of OAM.
+pub const OAM_DMA_LENGTH_BYTES: u16 = 0xA0;
+/// Cycles taken to copy each byte of an OAM DMA transfer, 640 in total.
+pub const OAM_DMA_CYCLES_PER_BYTE: Cycles = 4;
+
+/// OAM DMA controller (DMA).
+///
+/// Writing DMA starts copying 160 bytes from `value << 8` into OAM, one byte
+/// every 4 cycles. While the transfer runs the CPU can only reach HRAM and
+/// the I/O registers. Writing DMA again restarts the transfer from the new
+/// source. As with `Hdma`, `Memory` performs the copies.
+pub struct OamDma {
+    /// Value last written to DMA
+    register: u8,
+    source: Address,
+    /// Number of bytes copied so far
+    position: u16,
+    /// Cycles elapsed towards copying the next byte
+    cycles: Cycles,
+    active: bool
+}
+
+impl OamDma {
+    pub fn new() -> Self {
+        Self { register: 0xFF, source: 0xFF00, position: 0, cycles: 0, active: false }
+    }
+
+    /// Indicates whether a transfer is in progress.
+    pub fn active(&self) -> bool {
+        self.active
+    }
+
+    /// Read DMA, which returns the value last written.
+    pub fn read(&self) -> u8 {
+        self.register
+    }
+
+    /// Write DMA, starting or restarting a transfer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use gameboy::dma::*;
+    /// let mut dma = OamDma::new();
+    /// dma.write(0xC1);
+    /// assert!(dma.active());
+    /// assert_eq!(dma.tick(6), 1);
+    /// assert_eq!(dma.next_byte(), (0xC100, 0xFE00));
+    /// ```
+    pub fn write(&mut self, value: u8) {
+        self.register = value;
+        // Sources above 0xDFFF read from the WRAM mirror
+        let source = (value as Address) << 8;
+        self.source = if source >= 0xE000 { source - 0x2000 } else { source };
+        self.position = 0;
+        self.cycles = 0;
+        self.active = true;
+    }
+
+    /// Advance the transfer by the given number of cycles. Returns the number
+    /// of bytes which should now be copied using `next_byte`.
+    pub fn tick(&mut self, cycles: Cycles) -> u16 {
+        if !self.active {
+            return 0;
+        }
+
+        self.cycles += cycles;
+        let bytes = cmp::min(self.cycles / OAM_DMA_CYCLES_PER_BYTE, OAM_DMA_LENGTH_BYTES - self.position);
+        self.cycles -= bytes * OAM_DMA_CYCLES_PER_BYTE;
+        bytes
+    }
+
+    /// Source address and OAM destination address of the next byte to copy,
+    /// advancing past it. The transfer ends after its last byte.
+    pub fn next_byte(&mut self) -> (Address, Address) {
+        let byte = (self.source + self.position, 0xFE00 + self.position);
+        self.position += 1;
+        if self.position == OAM_DMA_LENGTH_BYTES {
+            self.active = false;
+        }
+        byte
+    }
+}
+
+impl Default for OamDma {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Kinds of HDMA transfer.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum HdmaMode {
+    /// No transfer in progress.
+    Idle,
+    /// General purpose DMA, copying every block at once while the CPU is
+    /// halted.
+    GeneralPurpose,
+    /// H-Blank DMA, copying a single block at the start of each horizontal
+    /// blank.
+    HBlank
+}
+
+/// CGB VRAM DMA controller (HDMA1-HDMA5).
+///
+/// This only tracks the transfer registers, `Memory` performs the copies
+/// since the source may be anywhere on the bus.
+pub struct Hdma {
+    source: Address,
+    /// Offset of the destination into VRAM.
+    destination: Address,
+    /// Number of 16 byte blocks remaining minus 1, as read from HDMA5.
+    remaining: u8,
+    mode: HdmaMode
+}
+
+impl Hdma {
+    pub fn new() -> Self {
+        Self { source: 0x0000, destination: 0x0000, remaining: 0x7F, mode: HdmaMode::Idle }
+    }
+
+    /// Kind of transfer currently in progress.
+    pub fn mode(&self) -> HdmaMode {
+        self.mode
+    }
+
+    /// Read one of the HDMA registers. Only HDMA5 is readable, returning the
+    /// remaining length in bits 0-6 and bit 7 clear while an H-Blank
+    /// transfer is active.
+    pub fn read(&self, address: Address) -> u8 {
+        match address {
+            HDMA1_ADDRESS..=HDMA4_ADDRESS => 0xFF,
+            HDMA5_ADDRESS => {
+                let inactive = if self.mode == HdmaMode::Idle { 0x80 } else { 0x00 };
+                inactive | self.remaining
+            },
+            _ => panic!("Unsupported HDMA read from address {:04X}", address)
+        }
+    }
+
+    /// Write one of the HDMA registers.
+    ///
+    /// # Examples
+    ///
+    /// Writing HDMA5 with bit 7 clear during an H-Blank transfer cancels it:
+    ///
+    /// ```
+    /// # use gameboy::dma::*;
+    /// let mut hdma = Hdma::new();
+    /// hdma.write(HDMA5_ADDRESS, 0x83);
+    /// assert_eq!(hdma.mode(), HdmaMode::HBlank);
+    /// assert_eq!(hdma.read(HDMA5_ADDRESS), 0x03);
+    /// hdma.write(HDMA5_ADDRESS, 0x00);
+    /// assert_eq!(hdma.mode(), HdmaMode::Idle);
+    /// assert_eq!(hdma.read(HDMA5_ADDRESS), 0x83);
+    /// ```
+    pub fn write(&mut self, address: Address, value: u8) {
+        match address {
+            HDMA1_ADDRESS => self.source = (self.source & 0x00FF) | (value as Address) << 8,
+            HDMA2_ADDRESS => self.source = (self.source & 0xFF00) | (value & 0xF0) as Address,
+            HDMA3_ADDRESS => {
+                self.destination = (self.destination & 0x00FF) | ((value & 0x1F) as Address) << 8;
+            },
+            HDMA4_ADDRESS => {
+                self.destination = (self.destination & 0xFF00) | (value & 0xF0) as Address;
+            },
+            HDMA5_ADDRESS => {
+                if self.mode == HdmaMode::HBlank && value & 0x80 == 0 {
+                    self.mode = HdmaMode::Idle;
+                } else {
+                    self.remaining = value & 0x7F;
+                    self.mode = if value & 0x80 != 0 {
+                        HdmaMode::HBlank
+                    } else {
+                        HdmaMode::GeneralPurpose
+                    };
+                }
+            },
+            _ => panic!("Unsupported HDMA write to address {:04X}", address)
+        }
+    }
+
+    /// Source address and VRAM destination address of the next block to
+    /// copy, advancing past it. The transfer ends after its last block.
+    pub fn next_block(&mut self) -> (Address, Address) {
+        let block = (self.source, 0x8000 | self.destination);
+        self.source = self.source.wrapping_add(HDMA_BLOCK_SIZE_BYTES);
+        self.destination = (self.destination + HDMA_BLOCK_SIZE_BYTES) & 0x1FF0;
+
+        let (remaining, finished) = self.remaining.overflowing_sub(1);
+        self.remaining = remaining & 0x7F;
+        if finished {
+            self.mode = HdmaMode::Idle;
+        }
+        block
+    }
+}
+
+impl Default for Hdma {
+    fn default() -> Self {
+        Self::new()
+    }
+}
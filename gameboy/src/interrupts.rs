@@ -0,0 +1,133 @@
+use super::*;
+
+/// Address of the interrupt flags (IF) register.
+pub const IF_ADDRESS: Address = 0xFF0F;
+/// Address of the interrupt enable (IE) register.
+pub const IE_ADDRESS: Address = 0xFFFF;
+
+/// Interrupt sources, in order of decreasing priority. Each value is the bit
+/// used for the source in both the IF and IE registers.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Interrupt {
+    /// PPU entered vertical blank.
+    VBlank = 0x01,
+    /// One of the enabled LCD STAT conditions became true.
+    LcdStat = 0x02,
+    /// TIMA overflowed.
+    Timer = 0x04,
+    /// A serial transfer completed.
+    Serial = 0x08,
+    /// A joypad input line went low.
+    Joypad = 0x10
+}
+
+/// All interrupt sources in priority order.
+pub const INTERRUPTS: [Interrupt; 5] = [
+    Interrupt::VBlank,
+    Interrupt::LcdStat,
+    Interrupt::Timer,
+    Interrupt::Serial,
+    Interrupt::Joypad,
+];
+
+impl Interrupt {
+    /// Address the CPU jumps to when servicing this interrupt.
+    pub fn vector(self) -> Address {
+        match self {
+            Interrupt::VBlank => 0x0040,
+            Interrupt::LcdStat => 0x0048,
+            Interrupt::Timer => 0x0050,
+            Interrupt::Serial => 0x0058,
+            Interrupt::Joypad => 0x0060
+        }
+    }
+
+    /// Returns the highest priority interrupt whose bit is set in the given
+    /// value, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use gameboy::interrupts::Interrupt;
+    /// assert_eq!(Interrupt::highest_priority(0x14), Some(Interrupt::Timer));
+    /// assert_eq!(Interrupt::highest_priority(0x00), None);
+    /// ```
+    pub fn highest_priority(bits: u8) -> Option<Interrupt> {
+        INTERRUPTS.iter().find(|interrupt| bits & **interrupt as u8 != 0).cloned()
+    }
+}
+
+/// Interrupt controller holding the IF and IE registers.
+///
+/// Peripherals raise interrupts through `request`, the CPU queries `pending`
+/// and acknowledges the interrupt it services.
+pub struct Interrupts {
+    /// Interrupt flags (IF), only the lower 5 bits are used.
+    flags: u8,
+    /// Interrupt enable (IE), all 8 bits are R/W.
+    enable: u8
+}
+
+impl Interrupts {
+    pub fn new() -> Self {
+        Self { flags: 0x00, enable: 0x00 }
+    }
+
+    /// Raise an interrupt by setting its bit in IF.
+    pub fn request(&mut self, interrupt: Interrupt) {
+        self.flags |= interrupt as u8;
+    }
+
+    /// Clear an interrupt's bit in IF, done when the CPU services it.
+    pub fn acknowledge(&mut self, interrupt: Interrupt) {
+        self.flags &= !(interrupt as u8);
+    }
+
+    /// Bits of interrupts which are both requested and enabled.
+    pub fn pending(&self) -> u8 {
+        self.flags & self.enable & 0x1F
+    }
+
+    /// Highest priority interrupt which is both requested and enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use gameboy::interrupts::{Interrupt, Interrupts};
+    /// let mut interrupts = Interrupts::new();
+    /// interrupts.request(Interrupt::Joypad);
+    /// interrupts.request(Interrupt::Serial);
+    /// assert_eq!(interrupts.next_pending(), None);
+    /// interrupts.write_enable(0xFF);
+    /// assert_eq!(interrupts.next_pending(), Some(Interrupt::Serial));
+    /// ```
+    pub fn next_pending(&self) -> Option<Interrupt> {
+        Interrupt::highest_priority(self.pending())
+    }
+
+    /// Read the IF register. The upper 3 bits are unused and always read high.
+    pub fn read_flags(&self) -> u8 {
+        self.flags | 0xE0
+    }
+
+    /// Write the IF register.
+    pub fn write_flags(&mut self, value: u8) {
+        self.flags = value & 0x1F;
+    }
+
+    /// Read the IE register.
+    pub fn read_enable(&self) -> u8 {
+        self.enable
+    }
+
+    /// Write the IE register.
+    pub fn write_enable(&mut self, value: u8) {
+        self.enable = value;
+    }
+}
+
+impl Default for Interrupts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
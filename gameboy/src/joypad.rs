@@ -0,0 +1,96 @@
+use super::*;
+
+/// Address of the joypad (P1) register.
+pub const P1_ADDRESS: Address = 0xFF00;
+
+/// Buttons on the Game Boy. The value of each is its bit in the joypad state,
+/// with the direction keys in the low nibble and the action buttons in the
+/// high nibble so that each nibble lines up with the P1 input lines.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Button {
+    Right = 0x01,
+    Left = 0x02,
+    Up = 0x04,
+    Down = 0x08,
+    A = 0x10,
+    B = 0x20,
+    Select = 0x40,
+    Start = 0x80
+}
+
+/// Joypad matrix exposed through the P1 register.
+///
+/// Software selects the direction keys (bit 4 low) and/or the action buttons
+/// (bit 5 low), and pressed buttons in the selected groups pull their input
+/// lines (bits 0-3) low.
+pub struct Joypad {
+    /// Select bits 4-5 as last written.
+    select: u8,
+    /// Currently pressed buttons, see `Button`.
+    pressed: u8
+}
+
+impl Joypad {
+    pub fn new() -> Self {
+        Self { select: 0x30, pressed: 0x00 }
+    }
+
+    /// Read the P1 register. Unused bits 6-7 always read high.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use gameboy::joypad::{Button, Joypad};
+    /// let mut joypad = Joypad::new();
+    /// joypad.press(Button::Start);
+    /// joypad.write(0x10);
+    /// assert_eq!(joypad.read(), 0xD7);
+    /// joypad.write(0x20);
+    /// assert_eq!(joypad.read(), 0xEF);
+    /// ```
+    pub fn read(&self) -> u8 {
+        0xC0 | self.select | (!self.lines() & 0x0F)
+    }
+
+    /// Write the P1 register. Only the select bits are writable.
+    pub fn write(&mut self, value: u8) {
+        self.select = value & 0x30;
+    }
+
+    /// Press a button. Returns true if an input line went from high to low,
+    /// which raises the joypad interrupt.
+    pub fn press(&mut self, button: Button) -> bool {
+        let before = self.lines();
+        self.pressed |= button as u8;
+        self.lines() & !before != 0
+    }
+
+    /// Release a button.
+    pub fn release(&mut self, button: Button) {
+        self.pressed &= !(button as u8);
+    }
+
+    /// Indicates whether any selected input line is currently low.
+    pub fn any_line_low(&self) -> bool {
+        self.lines() != 0
+    }
+
+    /// Input lines currently pulled low by the selected button groups, one
+    /// bit per line.
+    fn lines(&self) -> u8 {
+        let mut lines = 0x00;
+        if self.select & 0x10 == 0 {
+            lines |= self.pressed & 0x0F;
+        }
+        if self.select & 0x20 == 0 {
+            lines |= self.pressed >> 4;
+        }
+        lines
+    }
+}
+
+impl Default for Joypad {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,10 +1,16 @@
 pub mod cartridge;
 pub mod cpu;
+pub mod dma;
+pub mod interrupts;
+pub mod joypad;
 pub mod machine;
 pub mod memory;
 pub mod operations;
+pub mod ppu;
 pub mod registers;
 pub mod rom;
+pub mod save;
+pub mod timer;
 
 use std::fs::File;
 use std::io::Read;
@@ -36,7 +42,7 @@ pub struct GameboyError {
 impl GameboyError {
     /// Initialize a new error
     pub fn new(kind: GameboyErrorKind) -> Self {
-        Self { kind: kind }
+        Self { kind }
     }
 }
 
@@ -45,6 +51,16 @@ impl GameboyError {
 pub enum GameboyErrorKind {
     /// Cartridge is too small to be valid. Contains size of cartridge data given.
     CartridgeTooSmall(usize),
+    /// Cartridge header has an unknown ROM size code. Contains the code.
+    UnknownRomSize(u8),
+    /// Cartridge header has an unknown RAM size code. Contains the code.
+    UnknownRamSize(u8),
+    /// Cartridge header has an unknown destination code. Contains the code.
+    UnknownDestination(u8),
+    /// Cartridge header does not contain the Nintendo logo.
+    InvalidLogo,
+    /// Cartridge header checksum is wrong. Contains the checksum in the header and the computed checksum.
+    HeaderChecksumMismatch(u8, u8),
     /// Attempt to load too much data into memory. Contains load start address and data size.
     MemoryLoadOutOfBounds(Address, usize),
     /// Opcode prefix was not recognized.
@@ -56,6 +72,7 @@ pub enum GameboyErrorKind {
 }
 
 /// List of different support Game Boy types
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum GameboyType {
     /// DMG, this is the traditional monochrome gameboy
     DotMatrixGameboy,
@@ -63,9 +80,30 @@ pub enum GameboyType {
     ColorGameboy,
 }
 
+/// Strategies the PPU can use to draw each scanline
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum PpuRenderer {
+    /// Draw the whole line at the end of pixel transfer. Fast, but register
+    /// writes made during pixel transfer only take effect on the next line.
+    Scanline,
+    /// Model the background and sprite pixel FIFOs dot by dot, including
+    /// fetcher stalls and the variable length of pixel transfer.
+    PixelFifo,
+}
+
 /// Top-level emulator configuration
 pub struct Configuration {
-    gameboy_type: GameboyType,
+    pub gameboy_type: GameboyType,
+    pub ppu_renderer: PpuRenderer,
+}
+
+impl Default for Configuration {
+    fn default() -> Self {
+        Self {
+            gameboy_type: GameboyType::DotMatrixGameboy,
+            ppu_renderer: PpuRenderer::Scanline,
+        }
+    }
 }
 
 /// Read a rom file into a vector of bytes.
@@ -2,6 +2,9 @@ use super::*;
 use super::cpu::*;
 use super::memory::*;
 use super::operations::*;
+use super::ppu::CYCLES_PER_FRAME;
+use super::registers::Flag;
+use super::timer::DIV_ADDRESS;
 
 pub struct Machine {
     pub cpu: Cpu,
@@ -10,23 +13,674 @@ pub struct Machine {
 
 impl Machine {
     pub fn new(memory: Memory) -> Self {
-        Self { cpu: Cpu::new(), memory: memory }
+        Self { cpu: Cpu::new(), memory }
     }
 
+    /// Fetch, decode and execute a single instruction, or service a pending
+    /// interrupt, then advance the peripherals by the time it took.
+    ///
+    /// Returns the number of clock cycles taken by the instruction, including
+    /// any time the CPU was halted for DMA. For conditional instructions this
+    /// depends on whether or not the branch was taken.
     pub fn step(&mut self) -> GameboyResult<Cycles> {
-        let result = Operation::from_memory(self.cpu.pc, &self.memory);
-
-        if let Ok(operation) = result {
-            match operation.opcode {
-                Opcode::Nop => { self.cpu.pc += 1; Ok(1) },
-                _ => {
-                    return Err(GameboyError::new(
-                        GameboyErrorKind::Unknown(format!("Unimplemented: {:?}", operation.opcode))
-                    ));
+        if self.cpu.stopped {
+            // Clocks are stopped until a selected joypad input line goes low
+            if !self.memory.joypad.any_line_low() {
+                return Ok(4);
+            }
+            self.cpu.stopped = false;
+        }
+
+        let cycles = self.step_cpu()?;
+        self.memory.tick(cycles);
+
+        // The CPU is halted while DMA it started copies data
+        let stall_cycles = self.memory.take_stall_cycles();
+        if stall_cycles > 0 {
+            self.memory.tick(stall_cycles);
+        }
+
+        Ok(cycles + stall_cycles)
+    }
+
+    /// Run until the PPU completes a frame, after which it can be read from
+    /// `memory.ppu.frame_buffer()`. While the LCD is switched off this runs
+    /// for the length of one frame instead.
+    ///
+    /// Returns the number of CPU clock cycles taken.
+    pub fn run_frame(&mut self) -> GameboyResult<u32> {
+        let mut cycles = 0;
+        loop {
+            cycles += self.step()? as u32;
+            if self.memory.ppu.frame_ready() {
+                return Ok(cycles);
+            }
+
+            let frame_cycles = if self.memory.double_speed() {
+                CYCLES_PER_FRAME * 2
+            } else {
+                CYCLES_PER_FRAME
+            };
+            if !self.memory.ppu.lcd_enabled() && cycles >= frame_cycles {
+                return Ok(cycles);
+            }
+        }
+    }
+
+    /// Run the CPU for a single step without advancing the peripherals.
+    fn step_cpu(&mut self) -> GameboyResult<Cycles> {
+        if self.cpu.halted {
+            // Any requested and enabled interrupt wakes the CPU, even with
+            // IME cleared in which case it continues after the HALT.
+            if self.memory.interrupts.pending() == 0 {
+                return Ok(4);
+            }
+            self.cpu.halted = false;
+        }
+
+        if let Some(cycles) = self.service_interrupt() {
+            return Ok(cycles);
+        }
+
+        // EI only takes effect after the instruction following it, so an
+        // interrupt can be serviced no earlier than the next step.
+        if self.cpu.ime_scheduled {
+            self.cpu.ime_scheduled = false;
+            self.cpu.ime = true;
+        }
+
+        let operation = if self.cpu.halt_bug {
+            // PC is not incremented after fetching the prefix, so the prefix
+            // byte is read twice.
+            self.cpu.halt_bug = false;
+            let operation = Operation::from_memory_with_operands(
+                self.cpu.pc, self.cpu.pc, &self.memory
+            )?;
+            self.cpu.pc = self.cpu.pc.wrapping_add(operation.size() - 1);
+            operation
+        } else {
+            let operation = Operation::from_memory(self.cpu.pc, &self.memory)?;
+            self.cpu.pc = self.cpu.pc.wrapping_add(operation.size());
+            operation
+        };
+
+        self.execute(&operation)
+    }
+
+    /// Dispatch the highest priority pending interrupt if interrupts are
+    /// enabled. Returns the number of cycles taken if one was serviced.
+    fn service_interrupt(&mut self) -> Option<Cycles> {
+        if !self.cpu.ime {
+            return None;
+        }
+
+        let interrupt = self.memory.interrupts.next_pending()?;
+        self.cpu.ime = false;
+        self.memory.interrupts.acknowledge(interrupt);
+        self.push_word(self.cpu.pc);
+        self.cpu.pc = interrupt.vector();
+
+        Some(20)
+    }
+
+    /// Execute a decoded operation. The program counter is expected to already
+    /// point at the following instruction.
+    fn execute(&mut self, operation: &Operation) -> GameboyResult<Cycles> {
+        let cycles = match operation.opcode {
+            Opcode::Nop => 4,
+
+            // 8-bit loads
+            Opcode::Ld8RegImm(reg, value) => { self.cpu.r[reg] = value; 8 },
+            Opcode::Ld8RegReg(dst, src) => { self.cpu.r[dst] = self.cpu.r[src]; 4 },
+            Opcode::Ld8RegMemHl(reg) => {
+                let value = self.memory.read_byte(self.cpu.reg16(Reg16::HL));
+                self.cpu.r[reg] = value;
+                8
+            },
+            Opcode::St8MemHlReg(reg) => {
+                self.memory.write_byte(self.cpu.reg16(Reg16::HL), self.cpu.r[reg]);
+                8
+            },
+            Opcode::St8MemHlImm(value) => {
+                self.memory.write_byte(self.cpu.reg16(Reg16::HL), value);
+                12
+            },
+            Opcode::Ld8AccMem(reg) => {
+                let address = self.indirect_address(reg, operation.prefix);
+                self.cpu.r.a = self.memory.read_byte(address);
+                8
+            },
+            Opcode::St8MemRegAcc(reg) => {
+                let address = self.indirect_address(reg, operation.prefix);
+                self.memory.write_byte(address, self.cpu.r.a);
+                8
+            },
+            Opcode::Ld8AccMemImm(address) => {
+                self.cpu.r.a = self.memory.read_byte(address);
+                16
+            },
+            Opcode::St8MemImmAcc(address) => {
+                self.memory.write_byte(address, self.cpu.r.a);
+                16
+            },
+            Opcode::LdhAccMem(offset) => {
+                self.cpu.r.a = self.memory.read_byte(0xFF00 | offset as Address);
+                12
+            },
+            Opcode::LdhMemAcc(offset) => {
+                self.memory.write_byte(0xFF00 | offset as Address, self.cpu.r.a);
+                12
+            },
+            Opcode::LdcAccMem => {
+                self.cpu.r.a = self.memory.read_byte(0xFF00 | self.cpu.r.c as Address);
+                8
+            },
+            Opcode::LdcMemAcc => {
+                self.memory.write_byte(0xFF00 | self.cpu.r.c as Address, self.cpu.r.a);
+                8
+            },
+
+            // 16-bit loads
+            Opcode::Ld16RegImm(reg, value) => { self.cpu.set_reg16(reg, value); 12 },
+            Opcode::St16MemSp(address) => {
+                self.memory.write_byte(address, self.cpu.sp as u8);
+                self.memory.write_byte(address.wrapping_add(1), (self.cpu.sp >> 8) as u8);
+                20
+            },
+            Opcode::St16MemImmReg(address, reg) => {
+                let value = self.cpu.reg16(reg);
+                self.memory.write_byte(address, value as u8);
+                self.memory.write_byte(address.wrapping_add(1), (value >> 8) as u8);
+                20
+            },
+            Opcode::LdSpHl => { self.cpu.sp = self.cpu.reg16(Reg16::HL); 8 },
+            Opcode::LdHlSp(offset) => {
+                let value = self.add_sp_offset(offset);
+                self.cpu.set_reg16(Reg16::HL, value);
+                12
+            },
+            Opcode::Push(reg) => {
+                let value = self.cpu.reg16(reg);
+                self.push_word(value);
+                16
+            },
+            Opcode::Pop(reg) => {
+                let value = self.pop_word();
+                self.cpu.set_reg16(reg, value);
+                12
+            },
+
+            // 8-bit arithmetic and logic
+            Opcode::Add8Reg(reg) => { self.alu_add(self.cpu.r[reg], false); 4 },
+            Opcode::Add8AccHl => { self.alu_add(self.read_hl(), false); 8 },
+            Opcode::Add8Imm(value) => { self.alu_add(value, false); 8 },
+            Opcode::Adc8Reg(reg) => { self.alu_add(self.cpu.r[reg], true); 4 },
+            Opcode::Adc8AccHl => { self.alu_add(self.read_hl(), true); 8 },
+            Opcode::Adc8Imm(value) => { self.alu_add(value, true); 8 },
+            Opcode::Sub8Reg(reg) => { self.cpu.r.a = self.alu_sub(self.cpu.r[reg], false); 4 },
+            Opcode::Sub8AccHl => { self.cpu.r.a = self.alu_sub(self.read_hl(), false); 8 },
+            Opcode::Sub8Imm(value) => { self.cpu.r.a = self.alu_sub(value, false); 8 },
+            Opcode::Sbc8Reg(reg) => { self.cpu.r.a = self.alu_sub(self.cpu.r[reg], true); 4 },
+            Opcode::Sbc8AccHl => { self.cpu.r.a = self.alu_sub(self.read_hl(), true); 8 },
+            Opcode::Sbc8Imm(value) => { self.cpu.r.a = self.alu_sub(value, true); 8 },
+            Opcode::Cp8Reg(reg) => { self.alu_sub(self.cpu.r[reg], false); 4 },
+            Opcode::Cp8AccHl => { self.alu_sub(self.read_hl(), false); 8 },
+            Opcode::Cp8Imm(value) => { self.alu_sub(value, false); 8 },
+            Opcode::And8Reg(reg) => { self.alu_and(self.cpu.r[reg]); 4 },
+            Opcode::And8AccHl => { self.alu_and(self.read_hl()); 8 },
+            Opcode::And8Imm(value) => { self.alu_and(value); 8 },
+            Opcode::Or8Reg(reg) => { self.alu_or(self.cpu.r[reg]); 4 },
+            Opcode::Or8AccHl => { self.alu_or(self.read_hl()); 8 },
+            Opcode::Or8Imm(value) => { self.alu_or(value); 8 },
+            Opcode::Xor8Reg(reg) => { self.alu_xor(self.cpu.r[reg]); 4 },
+            Opcode::Xor8AccHl => { self.alu_xor(self.read_hl()); 8 },
+            Opcode::Xor8Imm(value) => { self.alu_xor(value); 8 },
+            Opcode::Inc8Reg(reg) => {
+                let value = self.alu_inc(self.cpu.r[reg]);
+                self.cpu.r[reg] = value;
+                4
+            },
+            Opcode::Inc8MemHl => {
+                let value = self.alu_inc(self.read_hl());
+                self.memory.write_byte(self.cpu.reg16(Reg16::HL), value);
+                12
+            },
+            Opcode::Dec8Reg(reg) => {
+                let value = self.alu_dec(self.cpu.r[reg]);
+                self.cpu.r[reg] = value;
+                4
+            },
+            Opcode::Dec8MemHl => {
+                let value = self.alu_dec(self.read_hl());
+                self.memory.write_byte(self.cpu.reg16(Reg16::HL), value);
+                12
+            },
+            Opcode::Daa => { self.daa(); 4 },
+            Opcode::Cpl => {
+                self.cpu.r.a = !self.cpu.r.a;
+                self.cpu.r.set_flag(Flag::N, true);
+                self.cpu.r.set_flag(Flag::H, true);
+                4
+            },
+            Opcode::Scf => {
+                self.cpu.r.set_flag(Flag::N, false);
+                self.cpu.r.set_flag(Flag::H, false);
+                self.cpu.r.set_flag(Flag::C, true);
+                4
+            },
+            Opcode::Ccf => {
+                let carry = self.cpu.r.flag(Flag::C);
+                self.cpu.r.set_flag(Flag::N, false);
+                self.cpu.r.set_flag(Flag::H, false);
+                self.cpu.r.set_flag(Flag::C, !carry);
+                4
+            },
+
+            // 16-bit arithmetic
+            Opcode::Inc16Reg(reg) => {
+                let value = self.cpu.reg16(reg).wrapping_add(1);
+                self.cpu.set_reg16(reg, value);
+                8
+            },
+            Opcode::Dec16Reg(reg) => {
+                let value = self.cpu.reg16(reg).wrapping_sub(1);
+                self.cpu.set_reg16(reg, value);
+                8
+            },
+            Opcode::Add16HlReg(reg) => {
+                let hl = self.cpu.reg16(Reg16::HL);
+                let value = self.cpu.reg16(reg);
+                let (result, carry) = hl.overflowing_add(value);
+                self.cpu.r.set_flag(Flag::N, false);
+                self.cpu.r.set_flag(Flag::H, (hl & 0x0FFF) + (value & 0x0FFF) > 0x0FFF);
+                self.cpu.r.set_flag(Flag::C, carry);
+                self.cpu.set_reg16(Reg16::HL, result);
+                8
+            },
+            Opcode::AddSp(offset) => {
+                self.cpu.sp = self.add_sp_offset(offset);
+                16
+            },
+
+            // Rotates on the accumulator. Unlike their CB-prefixed
+            // counterparts these always clear the zero flag.
+            Opcode::Rlca => {
+                let a = self.cpu.r.a;
+                self.cpu.r.a = a.rotate_left(1);
+                self.set_rotate_flags(a & 0x80 != 0);
+                4
+            },
+            Opcode::Rrca => {
+                let a = self.cpu.r.a;
+                self.cpu.r.a = a.rotate_right(1);
+                self.set_rotate_flags(a & 0x01 != 0);
+                4
+            },
+            Opcode::Rla => {
+                let a = self.cpu.r.a;
+                self.cpu.r.a = (a << 1) | self.cpu.r.flag(Flag::C) as u8;
+                self.set_rotate_flags(a & 0x80 != 0);
+                4
+            },
+            Opcode::Rra => {
+                let a = self.cpu.r.a;
+                self.cpu.r.a = (a >> 1) | (self.cpu.r.flag(Flag::C) as u8) << 7;
+                self.set_rotate_flags(a & 0x01 != 0);
+                4
+            },
+
+            // CB-prefixed rotates and shifts
+            Opcode::Rlc8Reg(reg) => { self.modify_reg8(reg, Self::alu_rlc); 8 },
+            Opcode::Rlc8MemHl => { self.modify_hl(Self::alu_rlc); 16 },
+            Opcode::Rrc8Reg(reg) => { self.modify_reg8(reg, Self::alu_rrc); 8 },
+            Opcode::Rrc8MemHl => { self.modify_hl(Self::alu_rrc); 16 },
+            Opcode::Rl8Reg(reg) => { self.modify_reg8(reg, Self::alu_rl); 8 },
+            Opcode::Rl8MemHl => { self.modify_hl(Self::alu_rl); 16 },
+            Opcode::Rr8Reg(reg) => { self.modify_reg8(reg, Self::alu_rr); 8 },
+            Opcode::Rr8MemHl => { self.modify_hl(Self::alu_rr); 16 },
+            Opcode::Sla8Reg(reg) => { self.modify_reg8(reg, Self::alu_sla); 8 },
+            Opcode::Sla8MemHl => { self.modify_hl(Self::alu_sla); 16 },
+            Opcode::Sra8Reg(reg) => { self.modify_reg8(reg, Self::alu_sra); 8 },
+            Opcode::Sra8MemHl => { self.modify_hl(Self::alu_sra); 16 },
+            Opcode::Swap8Reg(reg) => { self.modify_reg8(reg, Self::alu_swap); 8 },
+            Opcode::Swap8MemHl => { self.modify_hl(Self::alu_swap); 16 },
+            Opcode::Srl8Reg(reg) => { self.modify_reg8(reg, Self::alu_srl); 8 },
+            Opcode::Srl8MemHl => { self.modify_hl(Self::alu_srl); 16 },
+
+            // CB-prefixed single bit operations. BIT on (HL) only reads memory
+            // so it is faster than the read-modify-write RES and SET.
+            Opcode::Bit8Reg(bit, reg) => { self.alu_bit(bit, self.cpu.r[reg]); 8 },
+            Opcode::Bit8MemHl(bit) => { self.alu_bit(bit, self.read_hl()); 12 },
+            Opcode::Res8Reg(bit, reg) => {
+                self.cpu.r[reg] &= !(1 << bit);
+                8
+            },
+            Opcode::Res8MemHl(bit) => {
+                self.memory.write_byte(self.cpu.reg16(Reg16::HL), self.read_hl() & !(1 << bit));
+                16
+            },
+            Opcode::Set8Reg(bit, reg) => {
+                self.cpu.r[reg] |= 1 << bit;
+                8
+            },
+            Opcode::Set8MemHl(bit) => {
+                self.memory.write_byte(self.cpu.reg16(Reg16::HL), self.read_hl() | (1 << bit));
+                16
+            },
+
+            // Jumps, calls and returns
+            Opcode::JpImm(address) => { self.cpu.pc = address; 16 },
+            Opcode::JpHl => { self.cpu.pc = self.cpu.reg16(Reg16::HL); 4 },
+            Opcode::Jp(cond, address) => {
+                if self.condition(cond) {
+                    self.cpu.pc = address;
+                    16
+                } else {
+                    12
+                }
+            },
+            Opcode::JrImm(offset) => {
+                self.cpu.pc = self.cpu.pc.wrapping_add(offset as Address);
+                12
+            },
+            Opcode::Jr(cond, offset) => {
+                if self.condition(cond) {
+                    self.cpu.pc = self.cpu.pc.wrapping_add(offset as Address);
+                    12
+                } else {
+                    8
+                }
+            },
+            Opcode::Call(address) => {
+                self.push_word(self.cpu.pc);
+                self.cpu.pc = address;
+                24
+            },
+            Opcode::CallCond(cond, address) => {
+                if self.condition(cond) {
+                    self.push_word(self.cpu.pc);
+                    self.cpu.pc = address;
+                    24
+                } else {
+                    12
                 }
+            },
+            Opcode::Ret => { self.cpu.pc = self.pop_word(); 16 },
+            Opcode::RetCond(cond) => {
+                if self.condition(cond) {
+                    self.cpu.pc = self.pop_word();
+                    20
+                } else {
+                    8
+                }
+            },
+            Opcode::Reti => {
+                self.cpu.pc = self.pop_word();
+                self.cpu.ime = true;
+                16
+            },
+            Opcode::Rst(vector) => {
+                self.push_word(self.cpu.pc);
+                self.cpu.pc = vector as Address;
+                16
+            },
+
+            // CPU control
+            Opcode::Di => {
+                self.cpu.ime = false;
+                self.cpu.ime_scheduled = false;
+                4
+            },
+            Opcode::Ei => { self.cpu.ime_scheduled = true; 4 },
+            Opcode::Halt => {
+                if !self.cpu.ime && self.memory.interrupts.pending() != 0 {
+                    // HALT with IME cleared and an interrupt already pending
+                    // does not halt, and triggers the HALT bug instead.
+                    self.cpu.halt_bug = true;
+                } else {
+                    self.cpu.halted = true;
+                }
+                4
+            },
+            Opcode::Stop => {
+                // Entering STOP resets the divider
+                self.memory.write_byte(DIV_ADDRESS, 0x00);
+                // A CGB speed switch requested through KEY1 is performed
+                // instead of stopping the clocks
+                if !self.memory.switch_speed() {
+                    self.cpu.stopped = true;
+                }
+                4
+            },
+        };
+
+        Ok(cycles)
+    }
+
+    fn set_rotate_flags(&mut self, carry: bool) {
+        self.cpu.r.set_flags(false, false, false, carry);
+    }
+
+    /// Evaluate a branch condition against the current flags.
+    fn condition(&self, cond: Condition) -> bool {
+        match cond {
+            Condition::Z => self.cpu.r.flag(Flag::Z),
+            Condition::NZ => !self.cpu.r.flag(Flag::Z),
+            Condition::C => self.cpu.r.flag(Flag::C),
+            Condition::NC => !self.cpu.r.flag(Flag::C)
+        }
+    }
+
+    /// Resolve the address for `LD A, (r16)` and `LD (r16), A`.
+    ///
+    /// The HL forms of these instructions post-increment (0x22, 0x2A) or
+    /// post-decrement (0x32, 0x3A) HL, which is only distinguishable through
+    /// the opcode prefix.
+    fn indirect_address(&mut self, reg: Reg16, prefix: u8) -> Address {
+        let address = self.cpu.reg16(reg);
+        match prefix {
+            0x22 | 0x2A => self.cpu.set_reg16(Reg16::HL, address.wrapping_add(1)),
+            0x32 | 0x3A => self.cpu.set_reg16(Reg16::HL, address.wrapping_sub(1)),
+            _ => {}
+        }
+        address
+    }
+
+    fn read_hl(&self) -> u8 {
+        self.memory.read_byte(self.cpu.reg16(Reg16::HL))
+    }
+
+    /// Apply an operation to an 8-bit register, storing the result back.
+    fn modify_reg8(&mut self, reg: Reg8, operation: fn(&mut Self, u8) -> u8) {
+        let value = operation(self, self.cpu.r[reg]);
+        self.cpu.r[reg] = value;
+    }
+
+    /// Apply an operation to the byte at (HL), storing the result back.
+    fn modify_hl(&mut self, operation: fn(&mut Self, u8) -> u8) {
+        let value = operation(self, self.read_hl());
+        self.memory.write_byte(self.cpu.reg16(Reg16::HL), value);
+    }
+
+    fn push_word(&mut self, value: u16) {
+        self.cpu.sp = self.cpu.sp.wrapping_sub(1);
+        self.memory.write_byte(self.cpu.sp, (value >> 8) as u8);
+        self.cpu.sp = self.cpu.sp.wrapping_sub(1);
+        self.memory.write_byte(self.cpu.sp, value as u8);
+    }
+
+    fn pop_word(&mut self) -> u16 {
+        let lsb = self.memory.read_byte(self.cpu.sp);
+        self.cpu.sp = self.cpu.sp.wrapping_add(1);
+        let msb = self.memory.read_byte(self.cpu.sp);
+        self.cpu.sp = self.cpu.sp.wrapping_add(1);
+        make_u16(msb, lsb)
+    }
+
+    /// Computes SP + offset for `ADD SP, s8` and `LD HL, SP + s8`.
+    ///
+    /// Half-carry and carry are computed from the unsigned addition of the
+    /// low byte of SP and the offset, and the zero flag is always cleared.
+    fn add_sp_offset(&mut self, offset: Offset8) -> u16 {
+        let sp = self.cpu.sp;
+        let value = offset as u8 as u16;
+        self.cpu.r.set_flags(
+            false,
+            false,
+            (sp & 0x000F) + (value & 0x000F) > 0x000F,
+            (sp & 0x00FF) + value > 0x00FF
+        );
+        sp.wrapping_add(offset as u16)
+    }
+
+    /// ADD and ADC, storing the result in the accumulator.
+    fn alu_add(&mut self, value: u8, use_carry: bool) {
+        let a = self.cpu.r.a;
+        let carry = (use_carry && self.cpu.r.flag(Flag::C)) as u8;
+        let result = a.wrapping_add(value).wrapping_add(carry);
+        self.cpu.r.set_flags(
+            result == 0,
+            false,
+            (a & 0x0F) + (value & 0x0F) + carry > 0x0F,
+            a as u16 + value as u16 + carry as u16 > 0xFF
+        );
+        self.cpu.r.a = result;
+    }
+
+    /// SUB, SBC and CP. Returns the result without storing it so that CP can
+    /// discard it.
+    fn alu_sub(&mut self, value: u8, use_carry: bool) -> u8 {
+        let a = self.cpu.r.a;
+        let carry = (use_carry && self.cpu.r.flag(Flag::C)) as u8;
+        let result = a.wrapping_sub(value).wrapping_sub(carry);
+        self.cpu.r.set_flags(
+            result == 0,
+            true,
+            (a & 0x0F) < (value & 0x0F) + carry,
+            (a as u16) < value as u16 + carry as u16
+        );
+        result
+    }
+
+    fn alu_and(&mut self, value: u8) {
+        self.cpu.r.a &= value;
+        self.cpu.r.set_flags(self.cpu.r.a == 0, false, true, false);
+    }
+
+    fn alu_or(&mut self, value: u8) {
+        self.cpu.r.a |= value;
+        self.cpu.r.set_flags(self.cpu.r.a == 0, false, false, false);
+    }
+
+    fn alu_xor(&mut self, value: u8) {
+        self.cpu.r.a ^= value;
+        self.cpu.r.set_flags(self.cpu.r.a == 0, false, false, false);
+    }
+
+    /// 8-bit increment. Carry flag is left untouched.
+    fn alu_inc(&mut self, value: u8) -> u8 {
+        let result = value.wrapping_add(1);
+        self.cpu.r.set_flag(Flag::Z, result == 0);
+        self.cpu.r.set_flag(Flag::N, false);
+        self.cpu.r.set_flag(Flag::H, value & 0x0F == 0x0F);
+        result
+    }
+
+    /// 8-bit decrement. Carry flag is left untouched.
+    fn alu_dec(&mut self, value: u8) -> u8 {
+        let result = value.wrapping_sub(1);
+        self.cpu.r.set_flag(Flag::Z, result == 0);
+        self.cpu.r.set_flag(Flag::N, true);
+        self.cpu.r.set_flag(Flag::H, value & 0x0F == 0x00);
+        result
+    }
+
+    /// Decimal adjust the accumulator after a BCD addition or subtraction.
+    fn daa(&mut self) {
+        let mut a = self.cpu.r.a;
+        let mut carry = self.cpu.r.flag(Flag::C);
+
+        if self.cpu.r.flag(Flag::N) {
+            if carry {
+                a = a.wrapping_sub(0x60);
+            }
+            if self.cpu.r.flag(Flag::H) {
+                a = a.wrapping_sub(0x06);
             }
         } else {
-            Err(result.err().unwrap())
+            if carry || a > 0x99 {
+                a = a.wrapping_add(0x60);
+                carry = true;
+            }
+            if self.cpu.r.flag(Flag::H) || (a & 0x0F) > 0x09 {
+                a = a.wrapping_add(0x06);
+            }
         }
+
+        self.cpu.r.a = a;
+        self.cpu.r.set_flag(Flag::Z, a == 0);
+        self.cpu.r.set_flag(Flag::H, false);
+        self.cpu.r.set_flag(Flag::C, carry);
+    }
+
+    /// Sets flags for the CB-prefixed rotates and shifts.
+    fn set_shift_flags(&mut self, result: u8, carry: bool) {
+        self.cpu.r.set_flags(result == 0, false, false, carry);
+    }
+
+    fn alu_rlc(&mut self, value: u8) -> u8 {
+        let result = value.rotate_left(1);
+        self.set_shift_flags(result, value & 0x80 != 0);
+        result
+    }
+
+    fn alu_rrc(&mut self, value: u8) -> u8 {
+        let result = value.rotate_right(1);
+        self.set_shift_flags(result, value & 0x01 != 0);
+        result
+    }
+
+    fn alu_rl(&mut self, value: u8) -> u8 {
+        let result = (value << 1) | self.cpu.r.flag(Flag::C) as u8;
+        self.set_shift_flags(result, value & 0x80 != 0);
+        result
+    }
+
+    fn alu_rr(&mut self, value: u8) -> u8 {
+        let result = (value >> 1) | (self.cpu.r.flag(Flag::C) as u8) << 7;
+        self.set_shift_flags(result, value & 0x01 != 0);
+        result
+    }
+
+    fn alu_sla(&mut self, value: u8) -> u8 {
+        let result = value << 1;
+        self.set_shift_flags(result, value & 0x80 != 0);
+        result
+    }
+
+    /// Arithmetic shift right, bit 7 is preserved.
+    fn alu_sra(&mut self, value: u8) -> u8 {
+        let result = (value >> 1) | (value & 0x80);
+        self.set_shift_flags(result, value & 0x01 != 0);
+        result
+    }
+
+    fn alu_srl(&mut self, value: u8) -> u8 {
+        let result = value >> 1;
+        self.set_shift_flags(result, value & 0x01 != 0);
+        result
+    }
+
+    fn alu_swap(&mut self, value: u8) -> u8 {
+        let result = value.rotate_left(4);
+        self.set_shift_flags(result, false);
+        result
+    }
+
+    /// Test a single bit. Carry flag is left untouched.
+    fn alu_bit(&mut self, bit: Imm3, value: u8) {
+        self.cpu.r.set_flag(Flag::Z, value & (1 << bit) == 0);
+        self.cpu.r.set_flag(Flag::N, false);
+        self.cpu.r.set_flag(Flag::H, true);
     }
 }
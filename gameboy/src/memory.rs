@@ -1,5 +1,10 @@
 use super::*;
 use super::cartridge::{Cartridge};
+use super::dma::*;
+use super::interrupts::*;
+use super::joypad::*;
+use super::ppu::*;
+use super::timer::*;
 
 // GameBoy contains 65,536 bytes of addressabel memory. While the whole space
 // is addressable, many of the addresses in this space are unavailable for
@@ -25,27 +30,216 @@ use super::cartridge::{Cartridge};
 
 /// Size of Gameboy main system memory in bytes.
 pub const GAMEBOY_MEMORY_SIZE_BYTES: usize = 0x10000;
+/// Size of work RAM in bytes. The CGB has 8 banks of 4 KiB, the DMG only
+/// uses the first 2.
+pub const WRAM_SIZE_BYTES: usize = 0x8000;
+/// Size of a single bank of work RAM in bytes.
+pub const WRAM_BANK_SIZE_BYTES: usize = 0x1000;
+
+/// Address of the CGB prepare speed switch register (KEY1).
+pub const KEY1_ADDRESS: Address = 0xFF4D;
+/// Address of the CGB WRAM bank select register (SVBK).
+pub const SVBK_ADDRESS: Address = 0xFF70;
 
 /// Represents the total memory contained in the GameBoy
 pub struct Memory {
     pub data: [u8; GAMEBOY_MEMORY_SIZE_BYTES],
-    pub cartridge: Box<Cartridge>
+    pub cartridge: Box<dyn Cartridge>,
+    /// Work RAM, bank 0 is mapped at 0xC000 and the bank selected by SVBK at
+    /// 0xD000
+    wram: [u8; WRAM_SIZE_BYTES],
+    /// Enables the CGB registers
+    cgb: bool,
+    /// Lower 3 bits of SVBK as last written
+    svbk: u8,
+    /// CPU running at double speed (CGB only)
+    double_speed: bool,
+    /// Speed switch requested through KEY1, performed by the next STOP
+    speed_switch_armed: bool,
+    /// Cycles the CPU must be halted for while DMA copies data
+    stall_cycles: Cycles,
+    /// Interrupt controller, mapped at IF (0xFF0F) and IE (0xFFFF)
+    pub interrupts: Interrupts,
+    /// Joypad, mapped at P1 (0xFF00)
+    pub joypad: Joypad,
+    /// Timer, mapped at DIV, TIMA, TMA and TAC (0xFF04 - 0xFF07)
+    pub timer: Timer,
+    /// PPU, mapped at VRAM, OAM and the LCD registers (0xFF40 - 0xFF4B,
+    /// VBK and 0xFF68 - 0xFF6B on CGB)
+    pub ppu: Ppu,
+    /// OAM DMA, mapped at DMA (0xFF46)
+    pub oam_dma: OamDma,
+    /// CGB VRAM DMA, mapped at HDMA1 - HDMA5 (0xFF51 - 0xFF55)
+    pub hdma: Hdma
 }
 
-// TODO: Figure out how to handle I/O DMA addresses (callbacks?)
 // TODO: Figure out if we can allocate less memory since cartridge accounts for most.
 impl Memory {
     /// Allocate new GameBoy main system memory and initializes various areas.
-    pub fn new(cartridge: Box<Cartridge>) -> Self {
-        let mut result = Self {
+    pub fn new(cartridge: Box<dyn Cartridge>) -> Self {
+        Self::with_configuration(cartridge, &Configuration::default())
+    }
+
+    /// Allocate new GameBoy main system memory with peripherals set up for
+    /// the given configuration.
+    pub fn with_configuration(cartridge: Box<dyn Cartridge>, configuration: &Configuration) -> Self {
+        Self {
             data: [0; GAMEBOY_MEMORY_SIZE_BYTES],
-            cartridge: cartridge
-        };
+            cartridge,
+            wram: [0; WRAM_SIZE_BYTES],
+            cgb: configuration.gameboy_type == GameboyType::ColorGameboy,
+            svbk: 0,
+            double_speed: false,
+            speed_switch_armed: false,
+            stall_cycles: 0,
+            interrupts: Interrupts::new(),
+            joypad: Joypad::new(),
+            timer: Timer::new(),
+            ppu: Ppu::with_configuration(configuration),
+            oam_dma: OamDma::new(),
+            hdma: Hdma::new()
+        }
+    }
+
+    /// Advance all peripherals by the given number of CPU clock cycles.
+    ///
+    /// The timer and OAM DMA are clocked by the CPU and so run twice as fast
+    /// in double speed mode, while the PPU and cartridge keep running at the
+    /// same rate.
+    pub fn tick(&mut self, cycles: Cycles) {
+        if self.timer.tick(cycles) {
+            self.request_interrupt(Interrupt::Timer);
+        }
+
+        for _ in 0..self.oam_dma.tick(cycles) {
+            let (source, destination) = self.oam_dma.next_byte();
+            let value = self.read_bus(source);
+            self.ppu.oam[(destination - 0xFE00) as usize] = value;
+        }
+
+        let normal_speed_cycles = if self.double_speed { cycles / 2 } else { cycles };
+        self.ppu.tick(normal_speed_cycles, &mut self.interrupts);
+        self.cartridge.tick(normal_speed_cycles);
+
+        if self.ppu.hblank_started() && self.hdma.mode() == HdmaMode::HBlank {
+            self.copy_hdma_block();
+        }
+    }
+
+    /// Cycles the CPU must be halted for while DMA copies data, resetting
+    /// the count.
+    pub fn take_stall_cycles(&mut self) -> Cycles {
+        let cycles = self.stall_cycles;
+        self.stall_cycles = 0;
+        cycles
+    }
+
+    /// Begin a transfer after HDMA5 is written. General purpose transfers
+    /// are copied all at once. H-Blank transfers wait for the next horizontal
+    /// blank, unless the LCD is off in which case one block is copied now.
+    fn start_hdma(&mut self) {
+        match self.hdma.mode() {
+            HdmaMode::GeneralPurpose => {
+                while self.hdma.mode() == HdmaMode::GeneralPurpose {
+                    self.copy_hdma_block();
+                }
+            },
+            HdmaMode::HBlank if !self.ppu.lcd_enabled() => self.copy_hdma_block(),
+            _ => {}
+        }
+    }
+
+    /// Copy the next 16 byte block of an HDMA transfer into VRAM.
+    fn copy_hdma_block(&mut self) {
+        let (source, destination) = self.hdma.next_block();
+        for offset in 0..HDMA_BLOCK_SIZE_BYTES {
+            let value = self.read_bus(source.wrapping_add(offset));
+            self.ppu.write_vram(destination + offset, value);
+        }
+
+        // Each block takes the same time at either CPU speed
+        self.stall_cycles += if self.double_speed { 64 } else { 32 };
+    }
+
+    /// Indicates whether the CPU is running in CGB double speed mode.
+    pub fn double_speed(&self) -> bool {
+        self.double_speed
+    }
+
+    /// Perform a speed switch if one was requested through KEY1, done when
+    /// the CPU executes STOP. Returns true if the speed was switched.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate gameboy;
+    /// # use gameboy::{Configuration, GameboyType};
+    /// # use gameboy::cartridge::{Cartridge, RomOnly};
+    /// # use gameboy::memory::*;
+    /// # let cartridge: Box<dyn Cartridge> = Box::new(RomOnly::new(vec![0; 0x10000]));
+    /// let configuration = Configuration {
+    ///     gameboy_type: GameboyType::ColorGameboy,
+    ///     ..Configuration::default()
+    /// };
+    /// let mut memory = Memory::with_configuration(cartridge, &configuration);
+    /// memory.write_byte(KEY1_ADDRESS, 0x01);
+    /// assert!(memory.switch_speed());
+    /// assert_eq!(memory.read_byte(KEY1_ADDRESS), 0xFE);
+    /// assert!(!memory.switch_speed());
+    /// ```
+    pub fn switch_speed(&mut self) -> bool {
+        if !self.speed_switch_armed {
+            return false;
+        }
+        self.speed_switch_armed = false;
+        self.double_speed = !self.double_speed;
+        true
+    }
+
+    /// Offset into work RAM of an address in WRAM or its mirror.
+    fn wram_offset(&self, address: Address) -> usize {
+        let address = if address >= 0xE000 { address - 0x2000 } else { address };
+        if address < 0xD000 {
+            (address - 0xC000) as usize
+        } else {
+            self.wram_bank() * WRAM_BANK_SIZE_BYTES + (address - 0xD000) as usize
+        }
+    }
+
+    /// Work RAM bank mapped at 0xD000. Selecting bank 0 through SVBK maps
+    /// bank 1, which is always mapped on the DMG.
+    fn wram_bank(&self) -> usize {
+        if self.cgb && self.svbk != 0 { self.svbk as usize } else { 1 }
+    }
 
-        // Interrupt Flags (IF) initial value
-        result.data[0xFF0F] = 0xE0;
+    /// Raise an interrupt on behalf of a peripheral.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate gameboy;
+    /// # use gameboy::cartridge::{Cartridge, RomOnly};
+    /// # use gameboy::interrupts::Interrupt;
+    /// # let cartridge: Box<dyn Cartridge> = Box::new(RomOnly::new(vec![0; 0x10000]));
+    /// # let mut memory = gameboy::memory::Memory::new(cartridge);
+    /// memory.request_interrupt(Interrupt::Timer);
+    /// assert_eq!(memory.read_byte(0xFF0F), 0xE4);
+    /// ```
+    pub fn request_interrupt(&mut self, interrupt: Interrupt) {
+        self.interrupts.request(interrupt);
+    }
 
-        result
+    /// Press a joypad button, raising the joypad interrupt if this pulls one
+    /// of the selected input lines low.
+    pub fn press_button(&mut self, button: Button) {
+        if self.joypad.press(button) {
+            self.request_interrupt(Interrupt::Joypad);
+        }
+    }
+
+    /// Release a joypad button.
+    pub fn release_button(&mut self, button: Button) {
+        self.joypad.release(button);
     }
 
     /// Write a byte of data into memory handling special areas appropriately.
@@ -57,40 +251,99 @@ impl Memory {
     /// ```
     /// # extern crate gameboy;
     /// # use gameboy::cartridge::{Cartridge, RomOnly};
-    /// # let cartridge: Box<Cartridge> = Box::new(RomOnly::new(vec![0; 0x10000]));
+    /// # let cartridge: Box<dyn Cartridge> = Box::new(RomOnly::new(vec![0; 0x10000]));
     /// # let mut memory = gameboy::memory::Memory::new(cartridge);
     /// memory.write_byte(0xCABC, 0x12);
     /// ```
     pub fn write_byte(&mut self, address: Address, value: u8) {
+        if self.oam_dma_blocks(address) {
+            return;
+        }
+
         match address {
-            // Cartridge ROM
+            // Cartridge ROM, writes go to the memory bank controller
             0x0000..=0x7FFF => {
-                // Ignore, cannot write to ROM
+                self.cartridge.write_byte(address, value);
+            },
+            // VRAM
+            0x8000..=0x9FFF => {
+                self.ppu.write_vram(address, value);
             },
             // Cartridge RAM (if available)
             0xA000..=0xBFFF => {
                 self.cartridge.write_byte(address, value);
             },
-            // WRAM (mirrored at 0xE000 - 0xFDFF)
-            0xC000..=0xDDFF => {
-                self.data[address as usize] = value;
-                self.data[(address + 0x2000) as usize] = value;
+            // WRAM and its mirror (0xE000 - 0xFDFF mirrors 0xC000 - 0xDDFF)
+            0xC000..=0xFDFF => {
+                let offset = self.wram_offset(address);
+                self.wram[offset] = value;
             },
-            // WRAM Mirror (mirrors R/W to 0xC000 - 0xDDFF)
-            0xE000..=0xFDFF => {
-                self.data[address as usize] = value;
-                self.data[(address - 0x2000) as usize] = value;
+            // Object attribute memory (OAM)
+            0xFE00..=0xFE9F => {
+                self.ppu.write_oam(address, value);
             },
             // Unused RAM (0xFEA0 - 0xFEFF)
             0xFEA0..=0xFEFF => {
                 // Do nothing, ignore writes here
                 // TODO: Handle CGB mode weirdness
             },
+            // Joypad (P1, 0xFF00)
+            P1_ADDRESS => {
+                self.joypad.write(value);
+            },
+            // Timer (DIV, TIMA, TMA, TAC)
+            DIV_ADDRESS..=TAC_ADDRESS => {
+                self.timer.write(address, value);
+            },
             // Interrupt Flags (IF, 0xFF0F)
-            0xFF0F => {
-                // Only the lower 5-bits are R/W, the rest are always high. So
-                // we clear the upper 3 bits and then set them high on write.
-                self.data[address as usize] = (value & !0xE0) | 0xE0;
+            IF_ADDRESS => {
+                // Only the lower 5-bits are R/W, the rest are always high.
+                self.interrupts.write_flags(value);
+            },
+            // LCD registers (LCDC - LYC)
+            LCDC_ADDRESS..=LYC_ADDRESS => {
+                self.ppu.write(address, value, &mut self.interrupts);
+            },
+            // OAM DMA (DMA)
+            DMA_ADDRESS => {
+                self.oam_dma.write(value);
+            },
+            // LCD registers (BGP - WX)
+            BGP_ADDRESS..=WX_ADDRESS => {
+                self.ppu.write(address, value, &mut self.interrupts);
+            },
+            // CGB speed switch (KEY1)
+            KEY1_ADDRESS => {
+                if self.cgb {
+                    self.speed_switch_armed = value & 0x01 != 0;
+                }
+            },
+            // CGB WRAM bank (SVBK)
+            SVBK_ADDRESS => {
+                if self.cgb {
+                    self.svbk = value & 0x07;
+                }
+            },
+            // CGB VRAM DMA (HDMA1 - HDMA5)
+            HDMA1_ADDRESS..=HDMA5_ADDRESS => {
+                if self.cgb {
+                    self.hdma.write(address, value);
+                    if address == HDMA5_ADDRESS {
+                        self.start_hdma();
+                    }
+                }
+            },
+            // CGB VRAM bank (VBK)
+            VBK_ADDRESS => {
+                self.ppu.write(address, value, &mut self.interrupts);
+            },
+            // CGB palettes (BCPS - OCPD)
+            BCPS_ADDRESS..=OCPD_ADDRESS => {
+                self.ppu.write(address, value, &mut self.interrupts);
+            },
+            // Interrupt Enable (IE, 0xFFFF)
+            IE_ADDRESS => {
+                self.interrupts.write_enable(value);
             },
             _ => {
                 self.data[address as usize] = value
@@ -107,25 +360,76 @@ impl Memory {
     /// ```
     /// # extern crate gameboy;
     /// # use gameboy::cartridge::{Cartridge, RomOnly};
-    /// # let cartridge: Box<Cartridge> = Box::new(RomOnly::new(vec![0; 0x10000]));
+    /// # let cartridge: Box<dyn Cartridge> = Box::new(RomOnly::new(vec![0; 0x10000]));
     /// # let mut memory = gameboy::memory::Memory::new(cartridge);
     /// memory.write_byte(0xCABC, 0x12);
     /// assert_eq!(memory.read_byte(0xCABC), 0x12);
     /// ```
+    ///
+    /// During OAM DMA only HRAM and the I/O registers can be read:
+    ///
+    /// ```
+    /// # extern crate gameboy;
+    /// # use gameboy::cartridge::{Cartridge, RomOnly};
+    /// # let cartridge: Box<dyn Cartridge> = Box::new(RomOnly::new(vec![0; 0x10000]));
+    /// # let mut memory = gameboy::memory::Memory::new(cartridge);
+    /// memory.write_byte(0xCABC, 0x12);
+    /// memory.write_byte(0xFF80, 0x34);
+    /// memory.write_byte(0xFF46, 0xC0);
+    /// assert_eq!(memory.read_byte(0xCABC), 0xFF);
+    /// assert_eq!(memory.read_byte(0xFF80), 0x34);
+    /// ```
     pub fn read_byte(&self, address: Address) -> u8 {
+        if self.oam_dma_blocks(address) {
+            return 0xFF;
+        }
+        self.read_bus(address)
+    }
+
+    /// Indicates whether an access by the CPU to the given address conflicts
+    /// with an OAM DMA transfer in progress.
+    fn oam_dma_blocks(&self, address: Address) -> bool {
+        self.oam_dma.active() && address < 0xFF00
+    }
+
+    /// Read a byte of data from memory as seen by the DMA controllers, which
+    /// are not restricted by OAM DMA.
+    fn read_bus(&self, address: Address) -> u8 {
         match address {
             0x0000..=0x7FFF => self.cartridge.read_byte(address),
+            0x8000..=0x9FFF => self.ppu.read_vram(address),
             0xA000..=0xBFFF => self.cartridge.read_byte(address),
+            0xC000..=0xFDFF => self.wram[self.wram_offset(address)],
+            0xFE00..=0xFE9F => self.ppu.read_oam(address),
+            P1_ADDRESS => self.joypad.read(),
+            DIV_ADDRESS..=TAC_ADDRESS => self.timer.read(address),
+            IF_ADDRESS => self.interrupts.read_flags(),
+            LCDC_ADDRESS..=LYC_ADDRESS => self.ppu.read(address),
+            DMA_ADDRESS => self.oam_dma.read(),
+            BGP_ADDRESS..=WX_ADDRESS => self.ppu.read(address),
+            KEY1_ADDRESS if self.cgb => {
+                let speed = if self.double_speed { 0x80 } else { 0x00 };
+                0x7E | speed | self.speed_switch_armed as u8
+            },
+            SVBK_ADDRESS if self.cgb => 0xF8 | self.svbk,
+            HDMA1_ADDRESS..=HDMA5_ADDRESS if self.cgb => self.hdma.read(address),
+            KEY1_ADDRESS | SVBK_ADDRESS | HDMA1_ADDRESS..=HDMA5_ADDRESS => 0xFF,
+            VBK_ADDRESS => self.ppu.read(address),
+            BCPS_ADDRESS..=OCPD_ADDRESS => self.ppu.read(address),
+            IE_ADDRESS => self.interrupts.read_enable(),
             _ => self.data[address as usize]
         }
     }
 
+    /// Read a little-endian 16-bit word from memory. Words which straddle
+    /// the end of a cartridge area are read a byte at a time, so the second
+    /// byte comes from whatever is mapped after it.
     pub fn read_word(&self, address: Address) -> u16 {
         match address {
-            0x0000..=0x7FFF => self.cartridge.read_word(address),
-            0xA000..=0xBFFF => self.cartridge.read_word(address),
+            0x0000..=0x7FFE if !self.oam_dma.active() => self.cartridge.read_word(address),
+            0xA000..=0xBFFE if !self.oam_dma.active() => self.cartridge.read_word(address),
             _ => {
-                make_u16(self.read_byte(address), self.read_byte(address + 1))
+                make_u16(self.read_byte(address.wrapping_add(1)), self.read_byte(address))
             }
         }
     }
@@ -2,7 +2,7 @@ use super::*;
 use super::memory::Memory;
 
 /// 8-bit register constants
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Reg8 {
     B = 0,
     C = 1,
@@ -14,7 +14,7 @@ pub enum Reg8 {
 }
 
 /// 16-bit register constants
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Reg16 {
     BC = 0,
     DE = 1,
@@ -24,7 +24,7 @@ pub enum Reg16 {
 }
 
 /// Enumeration of jump conditions
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Condition {
     /// Zero flag is set
     Z,
@@ -37,7 +37,7 @@ pub enum Condition {
 }
 
 /// Enumeration of all operations for the Gameboy CPU.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Opcode {
     Adc8AccHl,
     Adc8Imm(Imm8),
@@ -50,6 +50,8 @@ pub enum Opcode {
     And8AccHl,
     And8Imm(Imm8),
     And8Reg(Reg8),
+    Bit8MemHl(Imm3),
+    Bit8Reg(Imm3, Reg8),
     Call(Imm16),
     CallCond(Condition, Imm16),
     Ccf,
@@ -63,6 +65,7 @@ pub enum Opcode {
     Dec8Reg(Reg8),
     Di,
     Ei,
+    Halt,
     Inc16Reg(Reg16),
     Inc8MemHl,
     Inc8Reg(Reg8),
@@ -70,6 +73,7 @@ pub enum Opcode {
     JpHl,
     JpImm(Imm16),
     Jr(Condition, Offset8),
+    JrImm(Offset8),
     Ld16RegImm(Reg16, Imm16),
     Ld8AccMem(Reg16),
     Ld8AccMemImm(Imm16),
@@ -91,23 +95,45 @@ pub enum Opcode {
     Push(Reg16),
     Ret,
     RetCond(Condition),
+    Res8MemHl(Imm3),
+    Res8Reg(Imm3, Reg8),
     Reti,
+    Rl8MemHl,
+    Rl8Reg(Reg8),
     Rla,
+    Rlc8MemHl,
+    Rlc8Reg(Reg8),
     Rlca,
+    Rr8MemHl,
+    Rr8Reg(Reg8),
     Rra,
+    Rrc8MemHl,
+    Rrc8Reg(Reg8),
     Rrca,
     Rst(Imm8),
     Sbc8AccHl,
     Sbc8Imm(Imm8),
     Sbc8Reg(Reg8),
     Scf,
+    Set8MemHl(Imm3),
+    Set8Reg(Imm3, Reg8),
+    Sla8MemHl,
+    Sla8Reg(Reg8),
+    Sra8MemHl,
+    Sra8Reg(Reg8),
+    Srl8MemHl,
+    Srl8Reg(Reg8),
     St16MemImmReg(Imm16, Reg16),
     St16MemSp(Imm16),
+    St8MemHlImm(Imm8),
+    St8MemHlReg(Reg8),
     St8MemRegAcc(Reg16),
     Stop,
     Sub8AccHl,
     Sub8Imm(Imm8),
     Sub8Reg(Reg8),
+    Swap8MemHl,
+    Swap8Reg(Reg8),
     Xor8AccHl,
     Xor8Imm(Imm8),
     Xor8Reg(Reg8)
@@ -145,6 +171,12 @@ fn prefix_into_reg8_2(prefix: u8) -> Reg8 {
     }
 }
 
+/// Extracts the bit index operand of the CB-prefixed BIT, RES and SET
+/// operations.
+fn prefix_into_imm3(prefix: u8) -> Imm3 {
+    (prefix >> 3) & 0x07
+}
+
 /// First method of converting a prefix into a 16-bit register.
 fn prefix_into_reg16_1(prefix: u8) -> Reg16 {
     match (prefix >> 4) & 0x03 {
@@ -184,37 +216,76 @@ fn prefix_into_cond(prefix: u8) -> Condition {
 }
 
 impl Operation {
+    /// Size of the encoded operation in bytes, including the prefix.
+    pub fn size(&self) -> u16 {
+        match self.opcode {
+            Opcode::Adc8Imm(_) | Opcode::Add8Imm(_) | Opcode::AddSp(_) |
+            Opcode::And8Imm(_) | Opcode::Cp8Imm(_) | Opcode::Jr(_, _) |
+            Opcode::JrImm(_) | Opcode::Ld8RegImm(_, _) | Opcode::LdHlSp(_) |
+            Opcode::LdhAccMem(_) | Opcode::LdhMemAcc(_) | Opcode::Or8Imm(_) |
+            Opcode::Sbc8Imm(_) | Opcode::St8MemHlImm(_) | Opcode::Stop |
+            Opcode::Sub8Imm(_) | Opcode::Xor8Imm(_) => 2,
+            Opcode::Bit8MemHl(_) | Opcode::Bit8Reg(_, _) | Opcode::Res8MemHl(_) |
+            Opcode::Res8Reg(_, _) | Opcode::Rl8MemHl | Opcode::Rl8Reg(_) |
+            Opcode::Rlc8MemHl | Opcode::Rlc8Reg(_) | Opcode::Rr8MemHl |
+            Opcode::Rr8Reg(_) | Opcode::Rrc8MemHl | Opcode::Rrc8Reg(_) |
+            Opcode::Set8MemHl(_) | Opcode::Set8Reg(_, _) | Opcode::Sla8MemHl |
+            Opcode::Sla8Reg(_) | Opcode::Sra8MemHl | Opcode::Sra8Reg(_) |
+            Opcode::Srl8MemHl | Opcode::Srl8Reg(_) | Opcode::Swap8MemHl |
+            Opcode::Swap8Reg(_) => 2,
+            Opcode::Call(_) | Opcode::CallCond(_, _) | Opcode::Jp(_, _) |
+            Opcode::JpImm(_) | Opcode::Ld16RegImm(_, _) |
+            Opcode::Ld8AccMemImm(_) | Opcode::St16MemImmReg(_, _) |
+            Opcode::St16MemSp(_) | Opcode::St8MemImmAcc(_) => 3,
+            _ => 1
+        }
+    }
+
     /// Translate raw series of bytes into a CPU operation.
     pub fn from_memory(pc: Address, memory: &Memory) -> GameboyResult<Operation> {
+        Self::from_memory_with_operands(pc, pc.wrapping_add(1), memory)
+    }
+
+    /// Translate an operation whose prefix is at `pc` and whose operand bytes
+    /// start at `operands`.
+    ///
+    /// These only differ when emulating the HALT bug, where the CPU fails to
+    /// increment PC after fetching the prefix so the prefix byte is read again
+    /// as the first operand byte.
+    pub fn from_memory_with_operands(
+        pc: Address,
+        operands: Address,
+        memory: &Memory
+    ) -> GameboyResult<Operation> {
         let prefix = memory.read_byte(pc);
 
         macro_rules! op {
             ( imm8 ) => {
-                memory.read_byte(pc + 1)
+                memory.read_byte(operands)
             };
             ( imm16 ) => {
-                memory.read_word(pc + 1)
+                memory.read_word(operands)
             };
             ( s8 ) => {
-                memory.read_byte(pc + 1) as Offset8
+                memory.read_byte(operands) as Offset8
             };
             ( $opcode:ident ) => {
-                Ok(Operation{ opcode: Opcode::$opcode, prefix: prefix })
+                Ok(Operation{ opcode: Opcode::$opcode, prefix })
             };
             ( $opcode:ident ( $arg:tt )) => {
-                Ok(Operation{ opcode: Opcode::$opcode(op!($arg)), prefix: prefix })
+                Ok(Operation{ opcode: Opcode::$opcode(op!($arg)), prefix })
             };
             ( $opcode:ident ( $arg:expr )) => {
-                Ok(Operation{ opcode: Opcode::$opcode(op!($arg)), prefix: prefix })
+                Ok(Operation{ opcode: Opcode::$opcode(op!($arg)), prefix })
             };
             ( $opcode:ident ( $argl:expr, $argr:tt )) => {
-                Ok(Operation{ opcode: Opcode::$opcode(op!($argl), op!($argr)), prefix: prefix })
+                Ok(Operation{ opcode: Opcode::$opcode(op!($argl), op!($argr)), prefix })
             };
             ( $opcode:ident ( $argl:tt, $argr:expr )) => {
-                Ok(Operation{ opcode: Opcode::$opcode(op!($argl), op!($argr)), prefix: prefix })
+                Ok(Operation{ opcode: Opcode::$opcode(op!($argl), op!($argr)), prefix })
             };
             ( $opcode:ident ( $argl:expr, $argr:expr )) => {
-                Ok(Operation{ opcode: Opcode::$opcode(op!($argl), op!($argr)), prefix: prefix })
+                Ok(Operation{ opcode: Opcode::$opcode(op!($argl), op!($argr)), prefix })
             };
             ( $ex:tt ) => {
                 $ex
@@ -243,13 +314,15 @@ impl Operation {
             0x0F => op!(Rrca),
             0x10 => op!(Stop),
             0x17 => op!(Rla),
+            0x18 => op!(JrImm(s8)),
             0x1F => op!(Rra),
             0x20 | 0x28 | 0x30 | 0x38 => op!(Jr(prefix_into_cond(prefix), s8)),
             0x27 => op!(Daa),
             0x2F => op!(Cpl),
             0x34 => op!(Inc8MemHl),
             0x35 => op!(Dec8MemHl),
-            0x36 => op!(Scf),
+            0x36 => op!(St8MemHlImm(imm8)),
+            0x37 => op!(Scf),
             0x3F => op!(Ccf),
             0x40 | 0x41 | 0x42 | 0x43 | 0x44 | 0x45 | 0x47 |
             0x48 | 0x49 | 0x4A | 0x4B | 0x4C | 0x4D | 0x4F |
@@ -260,6 +333,13 @@ impl Operation {
             0x78 | 0x79 | 0x7A | 0x7B | 0x7C | 0x7D | 0x7F => {
                 op!(Ld8RegReg(prefix_into_reg8_1(prefix), prefix_into_reg8_2(prefix)))
             },
+            0x46 | 0x4E | 0x56 | 0x5E | 0x66 | 0x6E | 0x7E => {
+                op!(Ld8RegMemHl(prefix_into_reg8_1(prefix)))
+            },
+            0x70 | 0x71 | 0x72 | 0x73 | 0x74 | 0x75 | 0x77 => {
+                op!(St8MemHlReg(prefix_into_reg8_2(prefix)))
+            },
+            0x76 => op!(Halt),
             0x80 | 0x81 | 0x82 | 0x83 | 0x84 | 0x85 | 0x87 => {
                 op!(Add8Reg(prefix_into_reg8_2(prefix)))
             },
@@ -303,7 +383,7 @@ impl Operation {
                 op!(Rst(prefix & 0x38))
             },
             0xC9 => op!(Ret),
-            0xCB => Self::from_alu_prefix(memory.read_byte(pc + 1)), // CB Prefix
+            0xCB => Self::from_alu_prefix(memory.read_byte(operands)), // CB Prefix
             0xCD => op!(Call(imm16)),
             0xCE => op!(Adc8Imm(imm8)),
             0xD6 => op!(Sub8Imm(imm8)),
@@ -334,11 +414,44 @@ impl Operation {
     // ALU operations starting with $CB prefix.
     //
     // In this method the $CB prefix is considered implied and the prefix
-    // provided is the byte following the $CB prefix.
+    // provided is the byte following the $CB prefix. Every byte following the
+    // $CB prefix is a valid operation. The low 3 bits select the operand in
+    // the same order as the 8-bit register encoding, with 6 meaning (HL).
     fn from_alu_prefix(prefix: u8) -> GameboyResult<Operation> {
-        Err(
-            GameboyError::new(GameboyErrorKind::UnknownAluOpcodePrefix(prefix))
-        )
+        let is_mem_hl = prefix & 0x07 == 0x06;
+
+        macro_rules! cb_op {
+            ( $reg_opcode:ident, $hl_opcode:ident ) => {
+                if is_mem_hl {
+                    Opcode::$hl_opcode
+                } else {
+                    Opcode::$reg_opcode(prefix_into_reg8_2(prefix))
+                }
+            };
+            ( $reg_opcode:ident, $hl_opcode:ident, imm3 ) => {
+                if is_mem_hl {
+                    Opcode::$hl_opcode(prefix_into_imm3(prefix))
+                } else {
+                    Opcode::$reg_opcode(prefix_into_imm3(prefix), prefix_into_reg8_2(prefix))
+                }
+            };
+        }
+
+        let opcode = match prefix {
+            0x00..=0x07 => cb_op!(Rlc8Reg, Rlc8MemHl),
+            0x08..=0x0F => cb_op!(Rrc8Reg, Rrc8MemHl),
+            0x10..=0x17 => cb_op!(Rl8Reg, Rl8MemHl),
+            0x18..=0x1F => cb_op!(Rr8Reg, Rr8MemHl),
+            0x20..=0x27 => cb_op!(Sla8Reg, Sla8MemHl),
+            0x28..=0x2F => cb_op!(Sra8Reg, Sra8MemHl),
+            0x30..=0x37 => cb_op!(Swap8Reg, Swap8MemHl),
+            0x38..=0x3F => cb_op!(Srl8Reg, Srl8MemHl),
+            0x40..=0x7F => cb_op!(Bit8Reg, Bit8MemHl, imm3),
+            0x80..=0xBF => cb_op!(Res8Reg, Res8MemHl, imm3),
+            0xC0..=0xFF => cb_op!(Set8Reg, Set8MemHl, imm3)
+        };
+
+        Ok(Operation { opcode, prefix: 0xCB })
     }
 }
 
@@ -0,0 +1,817 @@
+use super::*;
+use super::interrupts::{Interrupt, Interrupts};
+
+mod fifo;
+
+use self::fifo::PixelFifo;
+
+/// Width of the LCD in pixels.
+pub const SCREEN_WIDTH: usize = 160;
+/// Height of the LCD in pixels.
+pub const SCREEN_HEIGHT: usize = 144;
+
+/// Number of clock cycles (dots) taken by a single scanline.
+pub const CYCLES_PER_LINE: u16 = 456;
+/// Number of clock cycles taken by a full frame, including vertical blank.
+pub const CYCLES_PER_FRAME: u32 = CYCLES_PER_LINE as u32 * 154;
+
+/// Size of a single bank of video RAM in bytes.
+pub const VRAM_BANK_SIZE_BYTES: usize = 0x2000;
+/// Size of video RAM in bytes. The CGB has two banks, only the first is used
+/// on the DMG.
+pub const VRAM_SIZE_BYTES: usize = VRAM_BANK_SIZE_BYTES * 2;
+/// Size of each of the CGB background and sprite palette memories in bytes
+/// (8 palettes of 4 colours of 2 bytes each).
+pub const PALETTE_RAM_SIZE_BYTES: usize = 0x40;
+/// Size of object attribute memory in bytes (40 sprites of 4 bytes each).
+pub const OAM_SIZE_BYTES: usize = 0xA0;
+
+/// LCD control register.
+pub const LCDC_ADDRESS: Address = 0xFF40;
+/// LCD status register.
+pub const STAT_ADDRESS: Address = 0xFF41;
+/// Background vertical scroll.
+pub const SCY_ADDRESS: Address = 0xFF42;
+/// Background horizontal scroll.
+pub const SCX_ADDRESS: Address = 0xFF43;
+/// Current scanline (read-only).
+pub const LY_ADDRESS: Address = 0xFF44;
+/// Scanline compare.
+pub const LYC_ADDRESS: Address = 0xFF45;
+/// Background palette.
+pub const BGP_ADDRESS: Address = 0xFF47;
+/// Sprite palette 0.
+pub const OBP0_ADDRESS: Address = 0xFF48;
+/// Sprite palette 1.
+pub const OBP1_ADDRESS: Address = 0xFF49;
+/// Window Y position.
+pub const WY_ADDRESS: Address = 0xFF4A;
+/// Window X position plus 7.
+pub const WX_ADDRESS: Address = 0xFF4B;
+/// VRAM bank select (CGB only).
+pub const VBK_ADDRESS: Address = 0xFF4F;
+/// Background palette specification, the index into background palette
+/// memory (CGB only).
+pub const BCPS_ADDRESS: Address = 0xFF68;
+/// Background palette data (CGB only).
+pub const BCPD_ADDRESS: Address = 0xFF69;
+/// Sprite palette specification, the index into sprite palette memory (CGB
+/// only).
+pub const OCPS_ADDRESS: Address = 0xFF6A;
+/// Sprite palette data (CGB only).
+pub const OCPD_ADDRESS: Address = 0xFF6B;
+
+// LCDC bits
+const LCDC_BG_ENABLE: u8 = 0x01;
+const LCDC_SPRITE_ENABLE: u8 = 0x02;
+const LCDC_SPRITE_SIZE: u8 = 0x04;
+const LCDC_BG_MAP: u8 = 0x08;
+const LCDC_TILE_DATA: u8 = 0x10;
+const LCDC_WINDOW_ENABLE: u8 = 0x20;
+const LCDC_WINDOW_MAP: u8 = 0x40;
+const LCDC_LCD_ENABLE: u8 = 0x80;
+
+// STAT interrupt source enable bits
+const STAT_HBLANK_INTERRUPT: u8 = 0x08;
+const STAT_VBLANK_INTERRUPT: u8 = 0x10;
+const STAT_OAM_INTERRUPT: u8 = 0x20;
+const STAT_LYC_INTERRUPT: u8 = 0x40;
+
+// Attribute bits, shared by sprites and CGB background map attributes
+const ATTR_CGB_PALETTE: u8 = 0x07;
+const ATTR_BANK: u8 = 0x08;
+const ATTR_DMG_PALETTE: u8 = 0x10;
+const ATTR_FLIP_X: u8 = 0x20;
+const ATTR_FLIP_Y: u8 = 0x40;
+const ATTR_PRIORITY: u8 = 0x80;
+
+// Palette specification bits
+const PALETTE_INDEX: u8 = 0x3F;
+const PALETTE_AUTO_INCREMENT: u8 = 0x80;
+
+/// Maximum number of sprites displayed on a single scanline.
+const MAX_SPRITES_PER_LINE: usize = 10;
+
+/// Number of dots spent in OAM search at the start of each visible line.
+const OAM_SEARCH_CYCLES: u16 = 80;
+/// Number of dots spent transferring pixels to the LCD.
+const PIXEL_TRANSFER_CYCLES: u16 = 172;
+
+/// Colours of the four DMG shades as 15-bit RGB, from lightest to darkest.
+pub const DMG_SHADES: [u16; 4] = [0x7FFF, 0x56B5, 0x294A, 0x0000];
+
+/// PPU modes as reported in the lower 2 bits of STAT.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Mode {
+    HBlank = 0,
+    VBlank = 1,
+    OamSearch = 2,
+    PixelTransfer = 3
+}
+
+/// Finished image output by the PPU. Each pixel is a 15-bit RGB colour with
+/// red in the low bits, as used by the Game Boy Color.
+pub struct FrameBuffer {
+    pub pixels: [u16; SCREEN_WIDTH * SCREEN_HEIGHT]
+}
+
+impl FrameBuffer {
+    pub fn new() -> Self {
+        Self { pixels: [DMG_SHADES[0]; SCREEN_WIDTH * SCREEN_HEIGHT] }
+    }
+
+    /// Colour of the pixel at the given screen coordinate.
+    pub fn pixel(&self, x: usize, y: usize) -> u16 {
+        self.pixels[y * SCREEN_WIDTH + x]
+    }
+
+    /// Set the colour of the pixel at the given screen coordinate.
+    pub fn set_pixel(&mut self, x: usize, y: usize, colour: u16) {
+        self.pixels[y * SCREEN_WIDTH + x] = colour;
+    }
+}
+
+impl Default for FrameBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A pixel of the background, window or a sprite before its colour has been
+/// looked up.
+#[derive(Debug, Clone, Copy)]
+struct Pixel {
+    /// Colour index within the tile, 0-3.
+    colour: u8,
+    /// Palette number. On the DMG only used by sprites to select OBP0 or
+    /// OBP1.
+    palette: u8,
+    /// Priority attribute. When set on either the sprite or (CGB only) the
+    /// background, background colours 1-3 are drawn over the sprite.
+    bg_priority: bool,
+    /// Position of a sprite in OAM, which decides sprite priority on the CGB.
+    oam_index: u8
+}
+
+impl Pixel {
+    fn from_bg(colour: u8, attributes: u8) -> Self {
+        Self {
+            colour,
+            palette: attributes & ATTR_CGB_PALETTE,
+            bg_priority: attributes & ATTR_PRIORITY != 0,
+            oam_index: 0
+        }
+    }
+
+    fn from_sprite(sprite: &Sprite, colour: u8, cgb: bool) -> Self {
+        let palette = if cgb {
+            sprite.attributes & ATTR_CGB_PALETTE
+        } else if sprite.attributes & ATTR_DMG_PALETTE != 0 {
+            1
+        } else {
+            0
+        };
+
+        Self {
+            colour,
+            palette,
+            bg_priority: sprite.attributes & ATTR_PRIORITY != 0,
+            oam_index: sprite.index
+        }
+    }
+}
+
+/// CGB palette memory along with the specification register used to access
+/// it.
+struct PaletteRam {
+    data: [u8; PALETTE_RAM_SIZE_BYTES],
+    /// Index into palette memory in bits 0-5, auto increment in bit 7.
+    specification: u8
+}
+
+impl PaletteRam {
+    fn new() -> Self {
+        // Every colour starts out white
+        Self { data: [0xFF; PALETTE_RAM_SIZE_BYTES], specification: 0x00 }
+    }
+
+    /// Read the specification register. Bit 6 is unused and reads high.
+    fn read_specification(&self) -> u8 {
+        self.specification | 0x40
+    }
+
+    fn write_specification(&mut self, value: u8) {
+        self.specification = value & (PALETTE_AUTO_INCREMENT | PALETTE_INDEX);
+    }
+
+    /// Read palette memory at the current index. Palette memory is
+    /// inaccessible to the CPU while it is locked during pixel transfer.
+    fn read_data(&self, locked: bool) -> u8 {
+        if locked {
+            return 0xFF;
+        }
+        self.data[(self.specification & PALETTE_INDEX) as usize]
+    }
+
+    /// Write palette memory at the current index, then advance the index if
+    /// auto increment is enabled. The index advances even if the write is
+    /// ignored because palette memory is locked.
+    fn write_data(&mut self, value: u8, locked: bool) {
+        if !locked {
+            self.data[(self.specification & PALETTE_INDEX) as usize] = value;
+        }
+        if self.specification & PALETTE_AUTO_INCREMENT != 0 {
+            let index = (self.specification + 1) & PALETTE_INDEX;
+            self.specification = PALETTE_AUTO_INCREMENT | index;
+        }
+    }
+
+    /// 15-bit RGB colour of a colour index in one of the 8 palettes.
+    fn colour(&self, palette: u8, colour: u8) -> u16 {
+        let index = palette as usize * 8 + colour as usize * 2;
+        make_u16(self.data[index + 1], self.data[index]) & 0x7FFF
+    }
+}
+
+/// A sprite selected for display on the current scanline.
+#[derive(Debug, Clone, Copy)]
+struct Sprite {
+    /// Screen Y coordinate of the top of the sprite plus 16.
+    y: u8,
+    /// Screen X coordinate of the left of the sprite plus 8.
+    x: u8,
+    tile: u8,
+    attributes: u8,
+    /// Position of the sprite in OAM.
+    index: u8
+}
+
+/// The picture processing unit.
+///
+/// Each visible scanline walks through OAM search (mode 2), pixel transfer
+/// (mode 3) and horizontal blank (mode 0), followed by 10 lines of vertical
+/// blank (mode 1). Depending on the configured `PpuRenderer`, the current
+/// line is either rendered into the frame buffer as a whole at the end of
+/// pixel transfer, or pixel by pixel through the pixel FIFOs.
+///
+/// When configured as a `GameboyType::ColorGameboy` the PPU also provides a
+/// second bank of VRAM holding background map attributes and sprite tiles,
+/// and colour palette memory.
+pub struct Ppu {
+    pub vram: [u8; VRAM_SIZE_BYTES],
+    pub oam: [u8; OAM_SIZE_BYTES],
+    /// Enables the CGB features.
+    cgb: bool,
+    /// VRAM bank accessed by the CPU, 0 or 1.
+    vram_bank: u8,
+    bg_palettes: PaletteRam,
+    sprite_palettes: PaletteRam,
+    lcdc: u8,
+    /// Writable STAT interrupt enable bits (3-6).
+    stat: u8,
+    scy: u8,
+    scx: u8,
+    ly: u8,
+    lyc: u8,
+    bgp: u8,
+    obp0: u8,
+    obp1: u8,
+    wy: u8,
+    wx: u8,
+    mode: Mode,
+    renderer: PpuRenderer,
+    /// State of the pixel FIFOs, only used by `PpuRenderer::PixelFifo`.
+    fifo: PixelFifo,
+    /// Dot within the current scanline.
+    line_cycles: u16,
+    /// Internal line counter of the window, only incremented on lines where
+    /// the window was displayed.
+    window_line: u8,
+    /// Set once LY has matched WY during the current frame.
+    window_y_triggered: bool,
+    /// Current state of the STAT interrupt line. The interrupt is raised on
+    /// its rising edge.
+    stat_line: bool,
+    /// Sprites selected during OAM search for the current line.
+    line_sprites: Vec<Sprite>,
+    frame: FrameBuffer,
+    frame_ready: bool,
+    /// Set when horizontal blank starts on a visible line, used to drive
+    /// H-Blank DMA.
+    hblank_started: bool
+}
+
+impl Ppu {
+    pub fn new() -> Self {
+        Self::with_configuration(&Configuration::default())
+    }
+
+    pub fn with_configuration(configuration: &Configuration) -> Self {
+        Self {
+            vram: [0; VRAM_SIZE_BYTES],
+            oam: [0; OAM_SIZE_BYTES],
+            cgb: configuration.gameboy_type == GameboyType::ColorGameboy,
+            vram_bank: 0,
+            bg_palettes: PaletteRam::new(),
+            sprite_palettes: PaletteRam::new(),
+            lcdc: 0x91,
+            stat: 0x00,
+            scy: 0x00,
+            scx: 0x00,
+            ly: 0x00,
+            lyc: 0x00,
+            bgp: 0xFC,
+            obp0: 0xFF,
+            obp1: 0xFF,
+            wy: 0x00,
+            wx: 0x00,
+            mode: Mode::OamSearch,
+            renderer: configuration.ppu_renderer,
+            fifo: PixelFifo::new(),
+            line_cycles: 0,
+            window_line: 0,
+            window_y_triggered: false,
+            stat_line: false,
+            line_sprites: Vec::with_capacity(MAX_SPRITES_PER_LINE),
+            frame: FrameBuffer::new(),
+            frame_ready: false,
+            hblank_started: false
+        }
+    }
+
+    /// Current PPU mode.
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Indicates whether or not the LCD is switched on.
+    pub fn lcd_enabled(&self) -> bool {
+        self.lcdc & LCDC_LCD_ENABLE != 0
+    }
+
+    /// The most recently completed frame.
+    pub fn frame_buffer(&self) -> &FrameBuffer {
+        &self.frame
+    }
+
+    /// Returns true once for each frame completed since the last call.
+    pub fn frame_ready(&mut self) -> bool {
+        let ready = self.frame_ready;
+        self.frame_ready = false;
+        ready
+    }
+
+    /// Returns true once each time horizontal blank starts on a visible line.
+    pub fn hblank_started(&mut self) -> bool {
+        let started = self.hblank_started;
+        self.hblank_started = false;
+        started
+    }
+
+    /// Advance the PPU by the given number of clock cycles, raising VBlank and
+    /// STAT interrupts as required.
+    pub fn tick(&mut self, cycles: Cycles, interrupts: &mut Interrupts) {
+        if !self.lcd_enabled() {
+            return;
+        }
+
+        for _ in 0..cycles {
+            self.step(interrupts);
+        }
+    }
+
+    /// Read from the selected bank of VRAM. VRAM is inaccessible to the CPU
+    /// during pixel transfer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use gameboy::{Configuration, GameboyType};
+    /// # use gameboy::interrupts::Interrupts;
+    /// # use gameboy::ppu::*;
+    /// let configuration = Configuration {
+    ///     gameboy_type: GameboyType::ColorGameboy,
+    ///     ..Configuration::default()
+    /// };
+    /// let mut ppu = Ppu::with_configuration(&configuration);
+    /// let mut interrupts = Interrupts::new();
+    /// ppu.write(LCDC_ADDRESS, 0x00, &mut interrupts);
+    /// ppu.write_vram(0x8000, 0x12);
+    /// ppu.write(VBK_ADDRESS, 0x01, &mut interrupts);
+    /// ppu.write_vram(0x8000, 0x34);
+    /// assert_eq!(ppu.read_vram(0x8000), 0x34);
+    /// ppu.write(VBK_ADDRESS, 0x00, &mut interrupts);
+    /// assert_eq!(ppu.read_vram(0x8000), 0x12);
+    /// ```
+    pub fn read_vram(&self, address: Address) -> u8 {
+        if self.mode == Mode::PixelTransfer {
+            return 0xFF;
+        }
+        self.vram[self.vram_offset(address)]
+    }
+
+    /// Write to the selected bank of VRAM. Writes are ignored during pixel
+    /// transfer.
+    pub fn write_vram(&mut self, address: Address, value: u8) {
+        if self.mode != Mode::PixelTransfer {
+            let offset = self.vram_offset(address);
+            self.vram[offset] = value;
+        }
+    }
+
+    fn vram_offset(&self, address: Address) -> usize {
+        self.vram_bank as usize * VRAM_BANK_SIZE_BYTES + (address - 0x8000) as usize
+    }
+
+    /// Read from OAM. OAM is inaccessible to the CPU during OAM search and
+    /// pixel transfer.
+    pub fn read_oam(&self, address: Address) -> u8 {
+        if self.oam_locked() {
+            return 0xFF;
+        }
+        self.oam[(address - 0xFE00) as usize]
+    }
+
+    /// Write to OAM. Writes are ignored during OAM search and pixel transfer.
+    pub fn write_oam(&mut self, address: Address, value: u8) {
+        if !self.oam_locked() {
+            self.oam[(address - 0xFE00) as usize] = value;
+        }
+    }
+
+    /// Read one of the LCD registers.
+    pub fn read(&self, address: Address) -> u8 {
+        match address {
+            LCDC_ADDRESS => self.lcdc,
+            STAT_ADDRESS => {
+                let coincidence = if self.ly == self.lyc { 0x04 } else { 0x00 };
+                0x80 | self.stat | coincidence | self.mode as u8
+            },
+            SCY_ADDRESS => self.scy,
+            SCX_ADDRESS => self.scx,
+            LY_ADDRESS => self.ly,
+            LYC_ADDRESS => self.lyc,
+            BGP_ADDRESS => self.bgp,
+            OBP0_ADDRESS => self.obp0,
+            OBP1_ADDRESS => self.obp1,
+            WY_ADDRESS => self.wy,
+            WX_ADDRESS => self.wx,
+            // The CGB registers read high on the DMG
+            _ if !self.cgb => 0xFF,
+            VBK_ADDRESS => 0xFE | self.vram_bank,
+            BCPS_ADDRESS => self.bg_palettes.read_specification(),
+            BCPD_ADDRESS => self.bg_palettes.read_data(self.mode == Mode::PixelTransfer),
+            OCPS_ADDRESS => self.sprite_palettes.read_specification(),
+            OCPD_ADDRESS => self.sprite_palettes.read_data(self.mode == Mode::PixelTransfer),
+            _ => panic!("Unsupported PPU read from address {:04X}", address)
+        }
+    }
+
+    /// Write one of the LCD registers.
+    pub fn write(&mut self, address: Address, value: u8, interrupts: &mut Interrupts) {
+        match address {
+            LCDC_ADDRESS => {
+                let was_enabled = self.lcd_enabled();
+                self.lcdc = value;
+                if was_enabled && !self.lcd_enabled() {
+                    self.switch_off();
+                } else if !was_enabled && self.lcd_enabled() {
+                    self.switch_on();
+                }
+            },
+            STAT_ADDRESS => self.stat = value & 0x78,
+            SCY_ADDRESS => self.scy = value,
+            SCX_ADDRESS => self.scx = value,
+            LY_ADDRESS => {
+                // Read-only
+            },
+            LYC_ADDRESS => self.lyc = value,
+            BGP_ADDRESS => self.bgp = value,
+            OBP0_ADDRESS => self.obp0 = value,
+            OBP1_ADDRESS => self.obp1 = value,
+            WY_ADDRESS => self.wy = value,
+            WX_ADDRESS => self.wx = value,
+            _ if !self.cgb => {
+                // The CGB registers are not present on the DMG
+            },
+            VBK_ADDRESS => self.vram_bank = value & 0x01,
+            BCPS_ADDRESS => self.bg_palettes.write_specification(value),
+            BCPD_ADDRESS => {
+                let locked = self.mode == Mode::PixelTransfer;
+                self.bg_palettes.write_data(value, locked);
+            },
+            OCPS_ADDRESS => self.sprite_palettes.write_specification(value),
+            OCPD_ADDRESS => {
+                let locked = self.mode == Mode::PixelTransfer;
+                self.sprite_palettes.write_data(value, locked);
+            },
+            _ => panic!("Unsupported PPU write to address {:04X}", address)
+        }
+
+        if self.lcd_enabled() {
+            self.update_stat_line(interrupts);
+        }
+    }
+
+    /// Advance the PPU by a single dot.
+    fn step(&mut self, interrupts: &mut Interrupts) {
+        self.line_cycles += 1;
+
+        if self.ly < SCREEN_HEIGHT as u8 {
+            if self.line_cycles == OAM_SEARCH_CYCLES {
+                self.search_oam();
+                self.mode = Mode::PixelTransfer;
+                self.fifo.start_line(self.scx);
+            } else if self.mode == Mode::PixelTransfer {
+                match self.renderer {
+                    PpuRenderer::Scanline => {
+                        if self.line_cycles == OAM_SEARCH_CYCLES + PIXEL_TRANSFER_CYCLES {
+                            self.render_line();
+                            self.start_hblank();
+                        }
+                    },
+                    PpuRenderer::PixelFifo => {
+                        if self.fifo_step() {
+                            self.start_hblank();
+                        }
+                    }
+                }
+            }
+        }
+
+        if self.line_cycles == CYCLES_PER_LINE {
+            self.line_cycles = 0;
+            self.next_line(interrupts);
+        }
+
+        self.update_stat_line(interrupts);
+    }
+
+    /// Move on to the next scanline at the end of the current one.
+    fn next_line(&mut self, interrupts: &mut Interrupts) {
+        self.ly += 1;
+
+        if self.ly == SCREEN_HEIGHT as u8 {
+            self.mode = Mode::VBlank;
+            self.frame_ready = true;
+            interrupts.request(Interrupt::VBlank);
+        } else if self.ly == 154 {
+            self.ly = 0;
+            self.window_line = 0;
+            self.window_y_triggered = false;
+        }
+
+        if self.ly < SCREEN_HEIGHT as u8 {
+            self.mode = Mode::OamSearch;
+            if self.ly == self.wy {
+                self.window_y_triggered = true;
+            }
+        }
+    }
+
+    fn start_hblank(&mut self) {
+        self.mode = Mode::HBlank;
+        self.hblank_started = true;
+    }
+
+    fn switch_off(&mut self) {
+        self.ly = 0;
+        self.line_cycles = 0;
+        self.mode = Mode::HBlank;
+        self.stat_line = false;
+        // The LCD shows a blank screen while switched off
+        self.frame = FrameBuffer::new();
+        self.frame_ready = true;
+    }
+
+    fn switch_on(&mut self) {
+        self.ly = 0;
+        self.line_cycles = 0;
+        self.mode = Mode::OamSearch;
+        self.window_line = 0;
+        self.window_y_triggered = self.wy == 0;
+    }
+
+    fn oam_locked(&self) -> bool {
+        self.mode == Mode::OamSearch || self.mode == Mode::PixelTransfer
+    }
+
+    /// Recompute the STAT interrupt line, raising the interrupt when it goes
+    /// from low to high. Since all sources share one line, a new source does
+    /// not raise another interrupt while the line is already high.
+    fn update_stat_line(&mut self, interrupts: &mut Interrupts) {
+        let line = (self.stat & STAT_LYC_INTERRUPT != 0 && self.ly == self.lyc) ||
+            (self.stat & STAT_HBLANK_INTERRUPT != 0 && self.mode == Mode::HBlank) ||
+            (self.stat & STAT_VBLANK_INTERRUPT != 0 && self.mode == Mode::VBlank) ||
+            (self.stat & STAT_OAM_INTERRUPT != 0 && self.mode == Mode::OamSearch);
+
+        if line && !self.stat_line {
+            interrupts.request(Interrupt::LcdStat);
+        }
+        self.stat_line = line;
+    }
+
+    /// Select up to 10 sprites overlapping the current line in OAM order.
+    fn search_oam(&mut self) {
+        let height = self.sprite_height();
+        self.line_sprites.clear();
+
+        for (index, entry) in self.oam.chunks(4).enumerate() {
+            let top = entry[0] as i16 - 16;
+            let ly = self.ly as i16;
+            if ly >= top && ly < top + height as i16 {
+                self.line_sprites.push(Sprite {
+                    y: entry[0],
+                    x: entry[1],
+                    tile: entry[2],
+                    attributes: entry[3],
+                    index: index as u8
+                });
+                if self.line_sprites.len() == MAX_SPRITES_PER_LINE {
+                    break;
+                }
+            }
+        }
+
+        // On the DMG lower X coordinates win, with ties going to the earlier
+        // OAM entry. The sort is stable so OAM order is preserved within
+        // equal X. Sprites are also reached in this order by the pixel FIFO.
+        self.line_sprites.sort_by_key(|sprite| sprite.x);
+    }
+
+    fn sprite_height(&self) -> u8 {
+        if self.lcdc & LCDC_SPRITE_SIZE != 0 { 16 } else { 8 }
+    }
+
+    /// Indicates whether the window covers the given pixel of the current
+    /// line.
+    fn window_visible(&self, x: u8) -> bool {
+        self.lcdc & LCDC_WINDOW_ENABLE != 0 &&
+            self.window_y_triggered &&
+            x as u16 + 7 >= self.wx as u16
+    }
+
+    /// Render the current line into the frame buffer.
+    fn render_line(&mut self) {
+        let y = self.ly as usize;
+        let mut window_drawn = false;
+
+        for x in 0..SCREEN_WIDTH {
+            let bg = if !self.bg_displayed() {
+                Pixel::from_bg(0, 0)
+            } else if self.window_visible(x as u8) {
+                window_drawn = true;
+                let map_x = (x as u16 + 7 - self.wx as u16) as u8;
+                self.bg_pixel(self.lcdc & LCDC_WINDOW_MAP != 0, map_x, self.window_line)
+            } else {
+                let map_x = self.scx.wrapping_add(x as u8);
+                let map_y = self.scy.wrapping_add(self.ly);
+                self.bg_pixel(self.lcdc & LCDC_BG_MAP != 0, map_x, map_y)
+            };
+
+            // The opaque sprite with the highest priority owns the pixel, even
+            // if it is then hidden behind the background.
+            let mut sprites = self.line_sprites.iter().filter_map(|sprite| {
+                let colour = self.sprite_pixel(sprite, x as u8)?;
+                Some(Pixel::from_sprite(sprite, colour, self.cgb))
+            });
+            let sprite = if self.cgb {
+                sprites.min_by_key(|pixel| pixel.oam_index)
+            } else {
+                sprites.next()
+            };
+
+            let colour = self.mix(bg, sprite);
+            self.frame.set_pixel(x, y, colour);
+        }
+
+        if window_drawn {
+            self.window_line += 1;
+        }
+    }
+
+    /// Indicates whether the background and window are displayed. Clearing
+    /// LCDC bit 0 blanks them on the DMG, while on the CGB it only removes
+    /// their priority over sprites.
+    fn bg_displayed(&self) -> bool {
+        self.cgb || self.lcdc & LCDC_BG_ENABLE != 0
+    }
+
+    /// Combine a background pixel with the sprite pixel above it, if any,
+    /// and look up the resulting colour.
+    fn mix(&self, bg: Pixel, sprite: Option<Pixel>) -> u16 {
+        if self.cgb {
+            return self.mix_cgb(bg, sprite);
+        }
+
+        let bg_colour = if self.lcdc & LCDC_BG_ENABLE != 0 { bg.colour } else { 0 };
+
+        if let Some(sprite) = sprite {
+            let visible = self.lcdc & LCDC_SPRITE_ENABLE != 0 && sprite.colour != 0 &&
+                !(sprite.bg_priority && bg_colour != 0);
+            if visible {
+                let palette = if sprite.palette != 0 { self.obp1 } else { self.obp0 };
+                return DMG_SHADES[palette_shade(palette, sprite.colour)];
+            }
+        }
+
+        DMG_SHADES[palette_shade(self.bgp, bg_colour)]
+    }
+
+    fn mix_cgb(&self, bg: Pixel, sprite: Option<Pixel>) -> u16 {
+        if let Some(sprite) = sprite {
+            let bg_wins = self.lcdc & LCDC_BG_ENABLE != 0 && bg.colour != 0 &&
+                (bg.bg_priority || sprite.bg_priority);
+            if self.lcdc & LCDC_SPRITE_ENABLE != 0 && sprite.colour != 0 && !bg_wins {
+                return self.sprite_palettes.colour(sprite.palette, sprite.colour);
+            }
+        }
+
+        self.bg_palettes.colour(bg.palette, bg.colour)
+    }
+
+    /// Colour index of a sprite at screen column x on the current line, or
+    /// None if the sprite does not cover the column or is transparent there.
+    fn sprite_pixel(&self, sprite: &Sprite, x: u8) -> Option<u8> {
+        let column = (x as i16 + 8 - sprite.x as i16) as u8;
+        if column >= 8 {
+            return None;
+        }
+
+        let height = self.sprite_height();
+        let mut row = (self.ly as i16 + 16 - sprite.y as i16) as u8;
+        if sprite.attributes & ATTR_FLIP_Y != 0 {
+            row = height - 1 - row;
+        }
+        let column = if sprite.attributes & ATTR_FLIP_X != 0 { 7 - column } else { column };
+
+        // In 8x16 mode the tile index ignores bit 0 and the bottom half uses
+        // the following tile.
+        let tile = if height == 16 { sprite.tile & 0xFE } else { sprite.tile };
+        let offset = self.bank_offset(sprite.attributes) + tile as usize * 16 + row as usize * 2;
+        let colour = row_pixel(self.vram[offset], self.vram[offset + 1], column);
+
+        if colour == 0 { None } else { Some(colour) }
+    }
+
+    /// Pixel at the given coordinate of the background or window tile map.
+    fn bg_pixel(&self, high_map: bool, x: u8, y: u8) -> Pixel {
+        let (offset, attributes) = self.bg_tile_row(high_map, x, y);
+        let column = if attributes & ATTR_FLIP_X != 0 { 7 - x % 8 } else { x % 8 };
+        let colour = row_pixel(self.vram[offset], self.vram[offset + 1], column);
+        Pixel::from_bg(colour, attributes)
+    }
+
+    /// Offset into VRAM of the row of the background or window tile covering
+    /// the given coordinate of the tile map, along with the tile's attributes.
+    /// Attributes are always 0 on the DMG.
+    fn bg_tile_row(&self, high_map: bool, x: u8, y: u8) -> (usize, u8) {
+        let map_offset = (tile_map_address(high_map, x, y) - 0x8000) as usize;
+        let tile = self.vram[map_offset];
+        // CGB attributes are stored in bank 1 alongside the tile index
+        let attributes = if self.cgb { self.vram[VRAM_BANK_SIZE_BYTES + map_offset] } else { 0 };
+        let row = if attributes & ATTR_FLIP_Y != 0 { 7 - y % 8 } else { y % 8 };
+        let tile_offset = (self.bg_tile_address(tile) - 0x8000) as usize;
+        (self.bank_offset(attributes) + tile_offset + row as usize * 2, attributes)
+    }
+
+    /// Offset of the VRAM bank holding the tile data for the given sprite or
+    /// background attributes.
+    fn bank_offset(&self, attributes: u8) -> usize {
+        if self.cgb && attributes & ATTR_BANK != 0 { VRAM_BANK_SIZE_BYTES } else { 0 }
+    }
+
+    /// Address of a background or window tile. Depending on LCDC bit 4 tiles
+    /// are either indexed unsigned from 0x8000 or signed from 0x9000.
+    fn bg_tile_address(&self, tile: u8) -> Address {
+        if self.lcdc & LCDC_TILE_DATA != 0 {
+            0x8000 + tile as Address * 16
+        } else {
+            0x9000u16.wrapping_add((tile as i8 as i16 * 16) as u16)
+        }
+    }
+}
+
+impl Default for Ppu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Address of the tile map entry covering the given coordinate of the
+/// background or window.
+fn tile_map_address(high_map: bool, x: u8, y: u8) -> Address {
+    let map: Address = if high_map { 0x9C00 } else { 0x9800 };
+    map + (y as Address / 8) * 32 + x as Address / 8
+}
+
+/// Colour index of a pixel within a row of tile data.
+fn row_pixel(low: u8, high: u8, column: u8) -> u8 {
+    let bit = 7 - column;
+    ((high >> bit) & 0x01) << 1 | ((low >> bit) & 0x01)
+}
+
+/// Map a colour index through a DMG palette register to a shade.
+fn palette_shade(palette: u8, colour: u8) -> usize {
+    ((palette >> (colour * 2)) & 0x03) as usize
+}
@@ -0,0 +1,258 @@
+use std::collections::VecDeque;
+use super::*;
+
+/// Dots spent on the tile fetch which is thrown away at the start of each
+/// line.
+const STARTUP_CYCLES: u8 = 6;
+/// Dots the pixel FIFOs are stalled for while a sprite is fetched.
+const SPRITE_FETCH_CYCLES: u8 = 6;
+
+/// Steps of the background fetcher. Every step apart from `Push` takes 2
+/// dots, `Push` waits until the background FIFO is empty.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum FetcherStep {
+    Tile,
+    DataLow,
+    DataHigh,
+    Push
+}
+
+/// State of the background and sprite pixel FIFOs during pixel transfer.
+///
+/// The fetcher reads one tile row at a time from the background or window
+/// map and pushes its 8 pixels once the background FIFO runs empty. A pixel
+/// is shifted out to the LCD every dot that the background FIFO is not empty,
+/// mixed with the pixel shifted out of the sprite FIFO at the same time.
+/// Reaching the X position of a sprite stalls the FIFOs while its pixels are
+/// fetched and merged into the sprite FIFO.
+pub(super) struct PixelFifo {
+    bg: VecDeque<Pixel>,
+    sprites: VecDeque<Pixel>,
+    step: FetcherStep,
+    /// Dots spent in the current fetcher step.
+    step_cycles: u8,
+    /// Tile column being fetched, relative to the start of the line or the
+    /// window.
+    fetch_x: u8,
+    /// Offset into VRAM of the tile row being fetched.
+    tile_offset: usize,
+    /// CGB attributes of the tile being fetched.
+    attributes: u8,
+    data_low: u8,
+    data_high: u8,
+    fetching_window: bool,
+    /// Dots remaining before the FIFOs may run again.
+    stall_cycles: u8,
+    /// Pixels still to be dropped for fine scrolling by SCX.
+    discard: u8,
+    /// X coordinate of the next pixel output to the LCD.
+    lcd_x: u8,
+    /// Index of the next line sprite to be fetched.
+    next_sprite: usize
+}
+
+impl PixelFifo {
+    pub fn new() -> Self {
+        Self {
+            bg: VecDeque::with_capacity(8),
+            sprites: VecDeque::with_capacity(8),
+            step: FetcherStep::Tile,
+            step_cycles: 0,
+            fetch_x: 0,
+            tile_offset: 0,
+            attributes: 0x00,
+            data_low: 0x00,
+            data_high: 0x00,
+            fetching_window: false,
+            stall_cycles: 0,
+            discard: 0,
+            lcd_x: 0,
+            next_sprite: 0
+        }
+    }
+
+    /// Reset for the start of pixel transfer on a new line.
+    pub fn start_line(&mut self, scx: u8) {
+        self.bg.clear();
+        self.sprites.clear();
+        self.restart_fetcher();
+        self.fetching_window = false;
+        self.stall_cycles = STARTUP_CYCLES;
+        self.discard = scx & 0x07;
+        self.lcd_x = 0;
+        self.next_sprite = 0;
+    }
+
+    /// Switch the fetcher over to the window, throwing away any background
+    /// pixels already fetched.
+    fn start_window(&mut self) {
+        self.bg.clear();
+        self.restart_fetcher();
+        self.fetching_window = true;
+        self.discard = 0;
+    }
+
+    /// Indicates whether the background fetcher has finished fetching a tile
+    /// and the background FIFO has pixels, so a sprite can be fetched.
+    fn fetcher_ready(&self) -> bool {
+        self.step == FetcherStep::Push && !self.bg.is_empty()
+    }
+
+    fn restart_fetcher(&mut self) {
+        self.step = FetcherStep::Tile;
+        self.step_cycles = 0;
+        self.fetch_x = 0;
+    }
+}
+
+impl Ppu {
+    /// Run the pixel FIFOs for a single dot of pixel transfer. Returns true
+    /// once the last pixel of the line has been output.
+    pub(super) fn fifo_step(&mut self) -> bool {
+        if self.fifo.stall_cycles > 0 {
+            self.fifo.stall_cycles -= 1;
+            return false;
+        }
+
+        if !self.fifo.fetching_window && self.bg_displayed() &&
+            self.window_visible(self.fifo.lcd_x) {
+            self.fifo.start_window();
+        }
+
+        while let Some(sprite) = self.next_fifo_sprite() {
+            if self.lcdc & LCDC_SPRITE_ENABLE == 0 {
+                self.fifo.next_sprite += 1;
+                continue;
+            }
+
+            // The sprite fetch waits for the background fetcher to finish
+            // the tile it is currently fetching
+            if !self.fifo.fetcher_ready() {
+                self.fetch_step();
+                if !self.fifo.fetcher_ready() {
+                    return false;
+                }
+            }
+
+            self.load_sprite(sprite);
+            self.fifo.next_sprite += 1;
+            self.fifo.stall_cycles = SPRITE_FETCH_CYCLES - 1;
+            return false;
+        }
+
+        self.fetch_step();
+
+        let bg = match self.fifo.bg.pop_front() {
+            Some(pixel) => pixel,
+            None => return false
+        };
+        let sprite = self.fifo.sprites.pop_front();
+
+        if self.fifo.discard > 0 {
+            self.fifo.discard -= 1;
+            return false;
+        }
+
+        // Palettes and LCDC are read as each pixel is output, so changes made
+        // during pixel transfer take effect mid-line
+        let colour = self.mix(bg, sprite);
+        self.frame.set_pixel(self.fifo.lcd_x as usize, self.ly as usize, colour);
+        self.fifo.lcd_x += 1;
+
+        if self.fifo.lcd_x as usize == SCREEN_WIDTH {
+            if self.fifo.fetching_window {
+                self.window_line += 1;
+            }
+            return true;
+        }
+        false
+    }
+
+    /// The next line sprite to be fetched if the LCD has reached its X
+    /// position. Sprites are fetched once fine scrolling has finished.
+    fn next_fifo_sprite(&self) -> Option<Sprite> {
+        if self.fifo.discard > 0 {
+            return None;
+        }
+        self.line_sprites.get(self.fifo.next_sprite)
+            .filter(|sprite| sprite.x <= self.fifo.lcd_x + 8)
+            .cloned()
+    }
+
+    /// Advance the background fetcher by a single dot.
+    fn fetch_step(&mut self) {
+        if self.fifo.step == FetcherStep::Push {
+            if self.fifo.bg.is_empty() {
+                let flip = self.fifo.attributes & ATTR_FLIP_X != 0;
+                for column in 0..8 {
+                    let column = if flip { 7 - column } else { column };
+                    let colour = row_pixel(self.fifo.data_low, self.fifo.data_high, column);
+                    self.fifo.bg.push_back(Pixel::from_bg(colour, self.fifo.attributes));
+                }
+                self.fifo.fetch_x = self.fifo.fetch_x.wrapping_add(1);
+                // The first dot of the next tile fetch overlaps the push
+                self.fifo.step = FetcherStep::Tile;
+                self.fifo.step_cycles = 1;
+            }
+            return;
+        }
+
+        self.fifo.step_cycles += 1;
+        if self.fifo.step_cycles < 2 {
+            return;
+        }
+        self.fifo.step_cycles = 0;
+
+        match self.fifo.step {
+            FetcherStep::Tile => {
+                // SCX and SCY are read for every tile fetched
+                let (high_map, x, y) = if self.fifo.fetching_window {
+                    (self.lcdc & LCDC_WINDOW_MAP != 0, self.fifo.fetch_x.wrapping_mul(8), self.window_line)
+                } else {
+                    let x = self.scx.wrapping_add(self.fifo.fetch_x.wrapping_mul(8));
+                    (self.lcdc & LCDC_BG_MAP != 0, x, self.scy.wrapping_add(self.ly))
+                };
+                let (offset, attributes) = self.bg_tile_row(high_map, x, y);
+                self.fifo.tile_offset = offset;
+                self.fifo.attributes = attributes;
+                self.fifo.step = FetcherStep::DataLow;
+            },
+            FetcherStep::DataLow => {
+                self.fifo.data_low = self.vram[self.fifo.tile_offset];
+                self.fifo.step = FetcherStep::DataHigh;
+            },
+            FetcherStep::DataHigh => {
+                self.fifo.data_high = self.vram[self.fifo.tile_offset + 1];
+                self.fifo.step = FetcherStep::Push;
+            },
+            FetcherStep::Push => {}
+        }
+    }
+
+    /// Merge a sprite's pixels into the sprite FIFO. On the DMG pixels already
+    /// in the FIFO belong to higher priority sprites and are only replaced
+    /// where they are transparent. On the CGB the sprite earlier in OAM wins.
+    fn load_sprite(&mut self, sprite: Sprite) {
+        for position in 0..8 {
+            let x = self.fifo.lcd_x + position;
+            // Columns of sprites partially off the left of the screen were
+            // never reached
+            if x as u16 + 8 - sprite.x as u16 >= 8 {
+                break;
+            }
+
+            let colour = self.sprite_pixel(&sprite, x).unwrap_or(0);
+            let pixel = Pixel::from_sprite(&sprite, colour, self.cgb);
+            match self.fifo.sprites.get_mut(position as usize) {
+                Some(existing) => {
+                    let replace = existing.colour == 0 ||
+                        (self.cgb && colour != 0 && pixel.oam_index < existing.oam_index);
+                    if replace {
+                        *existing = pixel;
+                    }
+                },
+                None => self.fifo.sprites.push_back(pixel)
+            }
+        }
+    }
+}
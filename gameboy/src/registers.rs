@@ -1,3 +1,20 @@
+use super::*;
+use super::operations::Reg8;
+use std::ops::{Index, IndexMut};
+
+/// CPU flags stored in the upper nibble of the F register.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Flag {
+    /// Zero flag, set when the result of an operation is zero.
+    Z = 0x80,
+    /// Subtract flag, set when the last arithmetic operation was a subtraction.
+    N = 0x40,
+    /// Half-carry flag, set on carry out of (or borrow into) bit 3.
+    H = 0x20,
+    /// Carry flag, set on carry out of (or borrow into) bit 7.
+    C = 0x10
+}
+
 pub struct Registers {
     pub a: u8,
     f: u8,
@@ -22,4 +39,150 @@ impl Registers {
             l: 0x4D
         }
     }
+
+    /// Value of the flags register (F).
+    pub fn f(&self) -> u8 {
+        self.f
+    }
+
+    /// Set the flags register (F). The low nibble is not backed by any flag
+    /// and always reads as zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use gameboy::registers::Registers;
+    /// let mut registers = Registers::new();
+    /// registers.set_f(0xFF);
+    /// assert_eq!(registers.f(), 0xF0);
+    /// ```
+    pub fn set_f(&mut self, value: u8) {
+        self.f = value & 0xF0;
+    }
+
+    /// Indicates whether or not the given flag is set.
+    pub fn flag(&self, flag: Flag) -> bool {
+        self.f & flag as u8 != 0
+    }
+
+    /// Set or clear a single flag.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use gameboy::registers::{Flag, Registers};
+    /// let mut registers = Registers::new();
+    /// registers.set_flag(Flag::N, true);
+    /// assert!(registers.flag(Flag::N));
+    /// registers.set_flag(Flag::N, false);
+    /// assert!(!registers.flag(Flag::N));
+    /// ```
+    pub fn set_flag(&mut self, flag: Flag, value: bool) {
+        if value {
+            self.f |= flag as u8;
+        } else {
+            self.f &= !(flag as u8);
+        }
+    }
+
+    /// Set all four flags at once.
+    pub fn set_flags(&mut self, z: bool, n: bool, h: bool, c: bool) {
+        self.set_flag(Flag::Z, z);
+        self.set_flag(Flag::N, n);
+        self.set_flag(Flag::H, h);
+        self.set_flag(Flag::C, c);
+    }
+
+    /// Value of the AF register pair.
+    pub fn af(&self) -> u16 {
+        make_u16(self.a, self.f)
+    }
+
+    /// Set the AF register pair. The low nibble of F is forced to zero.
+    pub fn set_af(&mut self, value: u16) {
+        self.a = (value >> 8) as u8;
+        self.set_f(value as u8);
+    }
+
+    /// Value of the BC register pair.
+    pub fn bc(&self) -> u16 {
+        make_u16(self.b, self.c)
+    }
+
+    /// Set the BC register pair.
+    pub fn set_bc(&mut self, value: u16) {
+        self.b = (value >> 8) as u8;
+        self.c = value as u8;
+    }
+
+    /// Value of the DE register pair.
+    pub fn de(&self) -> u16 {
+        make_u16(self.d, self.e)
+    }
+
+    /// Set the DE register pair.
+    pub fn set_de(&mut self, value: u16) {
+        self.d = (value >> 8) as u8;
+        self.e = value as u8;
+    }
+
+    /// Value of the HL register pair.
+    pub fn hl(&self) -> u16 {
+        make_u16(self.h, self.l)
+    }
+
+    /// Set the HL register pair.
+    pub fn set_hl(&mut self, value: u16) {
+        self.h = (value >> 8) as u8;
+        self.l = value as u8;
+    }
+}
+
+impl Default for Registers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Read an 8-bit register by name.
+///
+/// # Examples
+///
+/// ```
+/// # use gameboy::operations::Reg8;
+/// # use gameboy::registers::Registers;
+/// let mut registers = Registers::new();
+/// registers[Reg8::D] = 0x12;
+/// assert_eq!(registers[Reg8::D], 0x12);
+/// assert_eq!(registers.d, 0x12);
+/// ```
+impl Index<Reg8> for Registers {
+    type Output = u8;
+
+    fn index(&self, reg: Reg8) -> &u8 {
+        match reg {
+            Reg8::A => &self.a,
+            Reg8::B => &self.b,
+            Reg8::C => &self.c,
+            Reg8::D => &self.d,
+            Reg8::E => &self.e,
+            Reg8::H => &self.h,
+            Reg8::L => &self.l
+        }
+    }
+}
+
+/// Write an 8-bit register by name.
+impl IndexMut<Reg8> for Registers {
+    fn index_mut(&mut self, reg: Reg8) -> &mut u8 {
+        match reg {
+            Reg8::A => &mut self.a,
+            Reg8::B => &mut self.b,
+            Reg8::C => &mut self.c,
+            Reg8::D => &mut self.d,
+            Reg8::E => &mut self.e,
+            Reg8::H => &mut self.h,
+            Reg8::L => &mut self.l
+        }
+    }
 }
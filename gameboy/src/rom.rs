@@ -1,6 +1,9 @@
-use super::{GameboyError, GameboyErrorKind, GameboyResult};
+use super::{make_u16, GameboyError, GameboyErrorKind, GameboyResult};
 use super::cartridge;
-use std::str;
+
+mod header;
+
+pub use self::header::*;
 
 /// Minimum size of a Gameboy cartridge (32 KB).
 pub const MINIMUM_CARTRIDGE_SIZE_BYTES: usize = 0x8000;
@@ -18,6 +21,10 @@ pub const NINTENDO_LOGO_BYTES: [u8; NINTENDO_LOGO_SIZE_BYTES] = [
     0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E,
 ];
 
+/// Size of the area at the end of an MMM01 ROM which is mapped at boot, and
+/// so holds the header, in bytes.
+const MMM01_MENU_SIZE_BYTES: usize = 0x8000;
+
 /// Represents a Gameboy ROM loaded from a ROM dump file.
 pub struct Rom {
     pub data: Vec<u8>
@@ -56,6 +63,56 @@ pub enum CartridgeKind {
     HuC1RamBattery = 0xFF
 }
 
+impl CartridgeKind {
+    /// Kind of cartridge with the given cartridge type code, if known.
+    pub fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0x00 => Some(Self::RomOnly),
+            0x01 => Some(Self::Mbc1),
+            0x02 => Some(Self::Mbc1Ram),
+            0x03 => Some(Self::Mbc1RamBattery),
+            0x05 => Some(Self::Mbc2),
+            0x06 => Some(Self::Mbc2Battery),
+            0x08 => Some(Self::RomRam),
+            0x09 => Some(Self::RomRamBattery),
+            0x0B => Some(Self::Mmm01),
+            0x0C => Some(Self::Mmm01Ram),
+            0x0D => Some(Self::Mmm01RamBattery),
+            0x0F => Some(Self::Mbc3TimerBattery),
+            0x10 => Some(Self::Mbc3TimerRamBattery),
+            0x11 => Some(Self::Mbc3),
+            0x12 => Some(Self::Mbc3Ram),
+            0x13 => Some(Self::Mbc3RamBattery),
+            0x19 => Some(Self::Mbc5),
+            0x1A => Some(Self::Mbc5Ram),
+            0x1B => Some(Self::Mbc5RamBattery),
+            0x1C => Some(Self::Mbc5Rumble),
+            0x1D => Some(Self::Mbc5RumbleRam), 
+            0x1E => Some(Self::Mbc5RumbleRamBattery),
+            0x20 => Some(Self::Mbc6),
+            0x22 => Some(Self::Mbc7SensorRumbleRamBattery),
+            0xFC => Some(Self::PocketCamera),
+            0xFD => Some(Self::BandaiTama5),
+            0xFE => Some(Self::HuC3),
+            0xFF => Some(Self::HuC1RamBattery),
+            _ => None
+        }
+    }
+
+    /// Indicates whether cartridges of this kind have a battery or other
+    /// memory which keeps its contents when powered off.
+    pub fn has_battery(&self) -> bool {
+        matches!(
+            self,
+            CartridgeKind::Mbc1RamBattery | CartridgeKind::Mbc2Battery | CartridgeKind::RomRamBattery |
+            CartridgeKind::Mmm01RamBattery | CartridgeKind::Mbc3TimerBattery | CartridgeKind::Mbc3TimerRamBattery |
+            CartridgeKind::Mbc3RamBattery | CartridgeKind::Mbc5RamBattery | CartridgeKind::Mbc5RumbleRamBattery |
+            CartridgeKind::Mbc6 | CartridgeKind::Mbc7SensorRumbleRamBattery | CartridgeKind::PocketCamera |
+            CartridgeKind::BandaiTama5 | CartridgeKind::HuC3 | CartridgeKind::HuC1RamBattery
+        )
+    }
+}
+
 impl Rom {
     /// Initialize a new ROM from the given data.
     ///
@@ -83,56 +140,126 @@ impl Rom {
             ));
         }
 
-        Ok(Self { data: data })
+        Ok(Self { data })
     }
 
     /// Consumes this ROM converting it into a cartridge if cartridge type is
     /// supported.
-    pub fn into_cartridge(self) -> Option<Box<cartridge::Cartridge>> {
-        match self.kind() {
-            Some(CartridgeKind::RomOnly) => Some(Box::new(cartridge::RomOnly::new(self.data))),
-            _ => None
+    pub fn into_cartridge(self) -> Option<Box<dyn cartridge::Cartridge>> {
+        let kind = self.kind()?;
+        let battery = kind.has_battery();
+        let ram_size_bytes = self.ram_size_bytes();
+        let cartridge: Box<dyn cartridge::Cartridge> = match kind {
+            CartridgeKind::RomOnly => Box::new(cartridge::RomOnly::new(self.data)),
+            CartridgeKind::RomRam | CartridgeKind::RomRamBattery => {
+                Box::new(cartridge::RomRam::new(self.data, ram_size_bytes, battery))
+            },
+            CartridgeKind::Mbc1 => Box::new(cartridge::Mbc1::new(self.data, 0, false)),
+            CartridgeKind::Mbc1Ram | CartridgeKind::Mbc1RamBattery => {
+                Box::new(cartridge::Mbc1::new(self.data, ram_size_bytes, battery))
+            },
+            CartridgeKind::Mbc2 | CartridgeKind::Mbc2Battery => Box::new(cartridge::Mbc2::new(self.data, battery)),
+            CartridgeKind::Mbc3 => Box::new(cartridge::Mbc3::new(self.data, 0, false, false)),
+            CartridgeKind::Mbc3Ram | CartridgeKind::Mbc3RamBattery => {
+                Box::new(cartridge::Mbc3::new(self.data, ram_size_bytes, false, battery))
+            },
+            CartridgeKind::Mbc3TimerBattery => Box::new(cartridge::Mbc3::new(self.data, 0, true, true)),
+            CartridgeKind::Mbc3TimerRamBattery => {
+                Box::new(cartridge::Mbc3::new(self.data, ram_size_bytes, true, true))
+            },
+            CartridgeKind::Mbc5 => Box::new(cartridge::Mbc5::new(self.data, 0, false, false)),
+            CartridgeKind::Mbc5Ram | CartridgeKind::Mbc5RamBattery => {
+                Box::new(cartridge::Mbc5::new(self.data, ram_size_bytes, false, battery))
+            },
+            CartridgeKind::Mbc5Rumble => Box::new(cartridge::Mbc5::new(self.data, 0, true, false)),
+            CartridgeKind::Mbc5RumbleRam | CartridgeKind::Mbc5RumbleRamBattery => {
+                Box::new(cartridge::Mbc5::new(self.data, ram_size_bytes, true, battery))
+            },
+            CartridgeKind::Mbc6 => Box::new(cartridge::Mbc6::new(self.data)),
+            CartridgeKind::Mbc7SensorRumbleRamBattery => Box::new(cartridge::Mbc7::new(self.data)),
+            CartridgeKind::HuC1RamBattery => Box::new(cartridge::HuC1::new(self.data, ram_size_bytes)),
+            CartridgeKind::HuC3 => Box::new(cartridge::HuC3::new(self.data, ram_size_bytes)),
+            CartridgeKind::PocketCamera => Box::new(cartridge::PocketCamera::new(self.data, ram_size_bytes)),
+            CartridgeKind::BandaiTama5 => Box::new(cartridge::Tama5::new(self.data)),
+            CartridgeKind::Mmm01 => Box::new(cartridge::Mmm01::new(self.data, 0, false)),
+            CartridgeKind::Mmm01Ram | CartridgeKind::Mmm01RamBattery => {
+                Box::new(cartridge::Mmm01::new(self.data, ram_size_bytes, battery))
+            }
+        };
+        Some(cartridge)
+    }
+
+    /// Offset of the 32 KiB of ROM containing the cartridge header.
+    ///
+    /// This is normally the start of the ROM, but MMM01 cartridges boot into
+    /// a menu stored in the last 32 KiB. The first bank of these belongs to
+    /// one of the games, so the header at the end is used if it has a valid
+    /// logo and an MMM01 cartridge type.
+    fn header_offset(&self) -> usize {
+        if self.data.len() <= MMM01_MENU_SIZE_BYTES {
+            return 0;
+        }
+
+        let offset = self.data.len() - MMM01_MENU_SIZE_BYTES;
+        let menu = &self.data[offset..];
+        if matches!(menu[0x0147], 0x0B..=0x0D) && NINTENDO_LOGO_BYTES[..] == menu[0x0104..=0x0133] {
+            offset
+        } else {
+            0
         }
     }
 
-    /// Returns the kind of ROM used based on the value at address 0x0147.
+    /// ROM data starting at the cartridge header's 32 KiB.
+    fn header(&self) -> &[u8] {
+        &self.data[self.header_offset()..]
+    }
+
+    /// Returns the kind of ROM used based on the value at address 0x0147 of
+    /// the header.
+    ///
+    /// # Examples
+    ///
+    /// MMM01 headers are found in the last 32 KiB:
+    ///
+    /// ```
+    /// # use gameboy::rom::*;
+    /// let mut cartridge_data = vec![0; 0x20000];
+    /// cartridge_data[0x0147] = 0x01;
+    /// cartridge_data[0x18104..0x18134].copy_from_slice(&NINTENDO_LOGO_BYTES);
+    /// cartridge_data[0x18147] = 0x0B;
+    /// let rom = Rom::new(cartridge_data).unwrap();
+    /// assert_eq!(rom.kind(), Some(CartridgeKind::Mmm01));
+    /// ```
     pub fn kind(&self) -> Option<CartridgeKind> {
-        match self.data[0x0147] {
-            0x00 => Some(CartridgeKind::RomOnly),
-            0x01 => Some(CartridgeKind::Mbc1),
-            0x02 => Some(CartridgeKind::Mbc1Ram),
-            0x03 => Some(CartridgeKind::Mbc1RamBattery),
-            0x05 => Some(CartridgeKind::Mbc2),
-            0x06 => Some(CartridgeKind::Mbc2Battery),
-            0x08 => Some(CartridgeKind::RomRam),
-            0x09 => Some(CartridgeKind::RomRamBattery),
-            0x0B => Some(CartridgeKind::Mmm01),
-            0x0C => Some(CartridgeKind::Mmm01Ram),
-            0x0D => Some(CartridgeKind::Mmm01RamBattery),
-            0x0F => Some(CartridgeKind::Mbc3TimerBattery),
-            0x10 => Some(CartridgeKind::Mbc3TimerRamBattery),
-            0x11 => Some(CartridgeKind::Mbc3),
-            0x12 => Some(CartridgeKind::Mbc3Ram),
-            0x13 => Some(CartridgeKind::Mbc3RamBattery),
-            0x19 => Some(CartridgeKind::Mbc5),
-            0x1A => Some(CartridgeKind::Mbc5Ram),
-            0x1B => Some(CartridgeKind::Mbc5RamBattery),
-            0x1C => Some(CartridgeKind::Mbc5Rumble),
-            0x1D => Some(CartridgeKind::Mbc5RumbleRam), 
-            0x1E => Some(CartridgeKind::Mbc5RumbleRamBattery),
-            0x20 => Some(CartridgeKind::Mbc6),
-            0x22 => Some(CartridgeKind::Mbc7SensorRumbleRamBattery),
-            0xFC => Some(CartridgeKind::PocketCamera),
-            0xFD => Some(CartridgeKind::BandaiTama5),
-            0xFE => Some(CartridgeKind::HuC3),
-            0xFF => Some(CartridgeKind::HuC1RamBattery),
-            _ => None
+        CartridgeKind::from_code(self.header()[0x0147])
+    }
+
+    /// Size of the cartridge RAM in bytes based on the value at address
+    /// 0x0149. Unknown values are treated as no RAM.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use gameboy::rom::{Rom, MINIMUM_CARTRIDGE_SIZE_BYTES};
+    /// let mut cartridge_data = vec![0; MINIMUM_CARTRIDGE_SIZE_BYTES];
+    /// cartridge_data[0x0149] = 0x03;
+    /// let rom = Rom::new(cartridge_data).unwrap();
+    /// assert_eq!(rom.ram_size_bytes(), 0x8000);
+    /// ```
+    pub fn ram_size_bytes(&self) -> usize {
+        match self.header()[0x0149] {
+            0x01 => 0x0800,
+            0x02 => 0x2000,
+            0x03 => 0x8000,
+            0x04 => 0x20000,
+            0x05 => 0x10000,
+            _ => 0
         }
     }
 
     /// Indicates whether or not the cartridge contains a valid Nintendo logo.
     pub fn has_valid_logo(&self) -> bool {
-        NINTENDO_LOGO_BYTES[..] == self.data[0x0104..=0x0133]
+        NINTENDO_LOGO_BYTES[..] == self.header()[0x0104..=0x0133]
     }
 
     /// Indicates whether or not the ROM has a valid header checksum.
@@ -149,7 +276,7 @@ impl Rom {
     pub fn has_valid_header_checksum(&self) -> bool {
         let mut checksum: u8 = 0x19;
         for index in 0x0134..=0x014D {
-            checksum = checksum.wrapping_add(self.data[index]);
+            checksum = checksum.wrapping_add(self.header()[index]);
         }
         checksum == 0x00
     }
@@ -165,36 +292,57 @@ impl Rom {
     ///   - For address in range [0x0150, <ROM END>]
     ///     - checksum += ROM[address]
     ///   - Validate checksum == (ROM[0x014E] << 8) | ROM[0x014F]
+    ///
+    /// For MMM01 cartridges the checksum bytes are those of the header at the
+    /// end of the ROM.
     pub fn has_valid_global_checksum(&self) -> bool {
         let mut checksum: u16 = 0x00;
-        let expected: u16 = (self.data[0x014E] as u16) << 8 | self.data[0x014F] as u16;
+        let header = self.header();
+        let expected: u16 = (header[0x014E] as u16) << 8 | header[0x014F] as u16;
+        let checksum_offset = self.header_offset() + 0x014E;
 
-        for index in 0x0000..0x014E {
-            checksum = checksum.wrapping_add(self.data[index] as u16);
-        }
-
-        for index in 0x0150..self.data.len() {
-            checksum = checksum.wrapping_add(self.data[index] as u16);
+        for (index, byte) in self.data.iter().enumerate() {
+            if index != checksum_offset && index != checksum_offset + 1 {
+                checksum = checksum.wrapping_add(*byte as u16);
+            }
         }
 
         checksum == expected
     }
 
-    /// Name of the game stored on the cartridge.
-    pub fn name(&self) -> String {
-        let mut name: Vec<u8> = Vec::new();
-
-        for ch in self.data[0x0134..=0x0143].iter() {
-            // Stop at the first zero byte as name strings appear NULL
-            // terminated.
-            if *ch == 0 {
-                break;
-            }
-
-            name.push(*ch);
-        }
+    /// Parse the cartridge header. For MMM01 cartridges this is the header
+    /// at the end of the ROM.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use gameboy::rom::*;
+    /// let mut cartridge_data = vec![0; MINIMUM_CARTRIDGE_SIZE_BYTES];
+    /// cartridge_data[0x014B] = 0x01;
+    /// let rom = Rom::new(cartridge_data).unwrap();
+    /// let header = rom.cartridge_header().unwrap();
+    /// assert_eq!(header.licensee.publisher(), Some("Nintendo"));
+    /// assert!(header.validate().is_err());
+    /// ```
+    pub fn cartridge_header(&self) -> GameboyResult<CartridgeHeader> {
+        CartridgeHeader::parse(self.header())
+    }
 
-        str::from_utf8(&name).unwrap().to_string()
+    /// Name of the game stored on the cartridge. This is the title from the
+    /// cartridge header, without the CGB flag or manufacturer code.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use gameboy::rom::{Rom, MINIMUM_CARTRIDGE_SIZE_BYTES};
+    /// let mut cartridge_data = vec![0; MINIMUM_CARTRIDGE_SIZE_BYTES];
+    /// cartridge_data[0x0134..0x0138].copy_from_slice(b"ZELD");
+    /// cartridge_data[0x0143] = 0xC0;
+    /// let rom = Rom::new(cartridge_data).unwrap();
+    /// assert_eq!(rom.name(), "ZELD");
+    /// ```
+    pub fn name(&self) -> String {
+        parse_title(self.header()).0
     }
 
     /// Size of the cartridge in bytes.
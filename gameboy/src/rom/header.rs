@@ -0,0 +1,456 @@
+use super::*;
+
+/// Size of the cartridge header, from the start of the ROM to the end of the
+/// header at 0x014F, in bytes.
+pub const CARTRIDGE_HEADER_END: usize = 0x0150;
+
+/// Old licensee code indicating that the new licensee code is used instead.
+const USE_NEW_LICENSEE: u8 = 0x33;
+
+/// Level of Color Gameboy support declared by the byte at 0x0143.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum CgbSupport {
+    /// Monochrome only. The byte is the last character of the title.
+    None,
+    /// Runs on both, with CGB enhancements (0x80).
+    Enhanced,
+    /// Runs on CGB only (0xC0).
+    Required
+}
+
+/// Market the cartridge was sold in, from the byte at 0x014A.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Destination {
+    Japan = 0x00,
+    Overseas = 0x01
+}
+
+/// Publisher of the game.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Licensee {
+    /// One byte code at 0x014B, used by games published before the SGB.
+    Old(u8),
+    /// Two character code at 0x0144 - 0x0145, used when the old code is
+    /// 0x33.
+    New(String)
+}
+
+impl Licensee {
+    /// Name of the publisher, if the code is known.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use gameboy::rom::Licensee;
+    /// assert_eq!(Licensee::Old(0x01).publisher(), Some("Nintendo"));
+    /// assert_eq!(Licensee::New("A4".to_string()).publisher(), Some("Konami"));
+    /// assert_eq!(Licensee::New("??".to_string()).publisher(), None);
+    /// ```
+    pub fn publisher(&self) -> Option<&'static str> {
+        match self {
+            Licensee::Old(code) => old_licensee_publisher(*code),
+            Licensee::New(code) => new_licensee_publisher(code)
+        }
+    }
+}
+
+/// The cartridge header stored at 0x0100 - 0x014F of the ROM.
+///
+/// Parsing only fails on values the rest of the header can't be understood
+/// without. The logo and checksums are only checked by `validate`, as plenty
+/// of homebrew gets them wrong.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CartridgeHeader {
+    /// Code run after the boot ROM, usually a NOP and a jump
+    pub entry_point: [u8; 4],
+    pub logo: [u8; NINTENDO_LOGO_SIZE_BYTES],
+    /// Title in upper case ASCII, up to the first NUL. Other bytes are
+    /// replaced with U+FFFD.
+    pub title: String,
+    /// Four character code found after the title on later CGB cartridges
+    pub manufacturer_code: Option<String>,
+    pub cgb_support: CgbSupport,
+    pub licensee: Licensee,
+    /// Supports SGB functions, which the SGB only honours with the new
+    /// licensee code
+    pub sgb: bool,
+    /// Byte at 0x0147, decoded by `kind`
+    pub cartridge_type: u8,
+    /// Number of 16 KiB ROM banks
+    pub rom_banks: usize,
+    pub rom_size_bytes: usize,
+    /// Number of 8 KiB RAM banks. The unofficial 2 KiB size counts as one
+    pub ram_banks: usize,
+    pub ram_size_bytes: usize,
+    pub destination: Destination,
+    pub mask_rom_version: u8,
+    pub header_checksum: u8,
+    /// Sum of every byte in the ROM except the checksum itself, big endian
+    pub global_checksum: u16,
+    /// Header checksum computed from 0x0134 - 0x014C
+    computed_header_checksum: u8
+}
+
+impl CartridgeHeader {
+    /// Parse the header of a ROM from its data, starting at 0x0000.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use gameboy::rom::*;
+    /// let mut data = vec![0; 0x8000];
+    /// data[0x0134..0x013A].copy_from_slice(b"TETRIS");
+    /// data[0x0143] = 0x80;
+    /// data[0x0148] = 0x01;
+    /// let header = CartridgeHeader::parse(&data).unwrap();
+    /// assert_eq!(header.title, "TETRIS");
+    /// assert_eq!(header.cgb_support, CgbSupport::Enhanced);
+    /// assert_eq!(header.rom_banks, 4);
+    /// ```
+    ///
+    /// Returns an error for a ROM size it doesn't know:
+    ///
+    /// ```
+    /// # use gameboy::rom::*;
+    /// let mut data = vec![0; 0x8000];
+    /// data[0x0148] = 0x20;
+    /// assert!(CartridgeHeader::parse(&data).is_err());
+    /// ```
+    pub fn parse(data: &[u8]) -> GameboyResult<Self> {
+        if data.len() < CARTRIDGE_HEADER_END {
+            return Err(GameboyError::new(GameboyErrorKind::CartridgeTooSmall(data.len())));
+        }
+
+        let mut entry_point = [0; 4];
+        entry_point.copy_from_slice(&data[0x0100..0x0104]);
+        let mut logo = [0; NINTENDO_LOGO_SIZE_BYTES];
+        logo.copy_from_slice(&data[0x0104..0x0134]);
+        let (title, manufacturer_code, cgb_support) = parse_title(data);
+
+        let licensee = if data[0x014B] == USE_NEW_LICENSEE {
+            Licensee::New(String::from_utf8_lossy(&data[0x0144..0x0146]).into_owned())
+        } else {
+            Licensee::Old(data[0x014B])
+        };
+
+        let rom_banks = match data[0x0148] {
+            code @ 0x00..=0x08 => 2 << code,
+            0x52 => 72,
+            0x53 => 80,
+            0x54 => 96,
+            code => return Err(GameboyError::new(GameboyErrorKind::UnknownRomSize(code)))
+        };
+        let (ram_banks, ram_size_bytes) = match data[0x0149] {
+            0x00 => (0, 0),
+            0x01 => (1, 0x0800),
+            0x02 => (1, 0x2000),
+            0x03 => (4, 0x8000),
+            0x04 => (16, 0x20000),
+            0x05 => (8, 0x10000),
+            code => return Err(GameboyError::new(GameboyErrorKind::UnknownRamSize(code)))
+        };
+        let destination = match data[0x014A] {
+            0x00 => Destination::Japan,
+            0x01 => Destination::Overseas,
+            code => return Err(GameboyError::new(GameboyErrorKind::UnknownDestination(code)))
+        };
+
+        Ok(Self {
+            entry_point,
+            logo,
+            title,
+            manufacturer_code,
+            cgb_support,
+            licensee,
+            sgb: data[0x0146] == 0x03,
+            cartridge_type: data[0x0147],
+            rom_banks,
+            rom_size_bytes: rom_banks * cartridge::ROM_BANK_SIZE_BYTES,
+            ram_banks,
+            ram_size_bytes,
+            destination,
+            mask_rom_version: data[0x014C],
+            header_checksum: data[0x014D],
+            global_checksum: make_u16(data[0x014E], data[0x014F]),
+            computed_header_checksum: data[0x0134..=0x014C].iter()
+                .fold(0u8, |checksum, byte| checksum.wrapping_sub(*byte).wrapping_sub(1))
+        })
+    }
+
+    /// Kind of cartridge from the cartridge type, if it is known.
+    pub fn kind(&self) -> Option<CartridgeKind> {
+        CartridgeKind::from_code(self.cartridge_type)
+    }
+
+    /// Check the logo and header checksum, which the boot ROM refuses to
+    /// start the game without.
+    pub fn validate(&self) -> GameboyResult<()> {
+        if self.logo != NINTENDO_LOGO_BYTES {
+            return Err(GameboyError::new(GameboyErrorKind::InvalidLogo));
+        }
+        if self.header_checksum != self.computed_header_checksum {
+            return Err(GameboyError::new(GameboyErrorKind::HeaderChecksumMismatch(
+                self.header_checksum, self.computed_header_checksum
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Split 0x0134 - 0x0143 into the title, manufacturer code and CGB flag.
+///
+/// Older cartridges use all 16 bytes for the title. CGB cartridges use the
+/// last byte as the CGB flag, and later ones the 4 bytes before it as the
+/// manufacturer code. That code is only recognized if it's 4 upper case
+/// letters or digits, as titles can also be 15 bytes long.
+pub(super) fn parse_title(data: &[u8]) -> (String, Option<String>, CgbSupport) {
+    let cgb_support = match data[0x0143] {
+        0xC0 => CgbSupport::Required,
+        0x80 => CgbSupport::Enhanced,
+        _ => CgbSupport::None
+    };
+
+    let code = &data[0x013F..0x0143];
+    let manufacturer_code = cgb_support != CgbSupport::None &&
+        code.iter().all(|byte| byte.is_ascii_uppercase() || byte.is_ascii_digit());
+    let title = match (cgb_support, manufacturer_code) {
+        (CgbSupport::None, _) => &data[0x0134..0x0144],
+        (_, true) => &data[0x0134..0x013F],
+        (_, false) => &data[0x0134..0x0143]
+    };
+
+    let length = title.iter().position(|byte| *byte == 0).unwrap_or(title.len());
+    let title = title[..length].iter()
+        .map(|&byte| if byte.is_ascii() { byte as char } else { char::REPLACEMENT_CHARACTER })
+        .collect();
+    let manufacturer_code = if manufacturer_code {
+        Some(String::from_utf8_lossy(code).into_owned())
+    } else {
+        None
+    };
+    (title, manufacturer_code, cgb_support)
+}
+
+fn new_licensee_publisher(code: &str) -> Option<&'static str> {
+    let publisher = match code {
+        "00" => "None",
+        "01" => "Nintendo Research & Development 1",
+        "08" => "Capcom",
+        "13" => "EA (Electronic Arts)",
+        "18" => "Hudson Soft",
+        "19" => "B-AI",
+        "20" => "KSS",
+        "22" => "Planning Office WADA",
+        "24" => "PCM Complete",
+        "25" => "San-X",
+        "28" => "Kemco",
+        "29" => "SETA Corporation",
+        "30" => "Viacom",
+        "31" => "Nintendo",
+        "32" => "Bandai",
+        "33" => "Ocean Software/Acclaim Entertainment",
+        "34" => "Konami",
+        "35" => "HectorSoft",
+        "37" => "Taito",
+        "38" => "Hudson Soft",
+        "39" => "Banpresto",
+        "41" => "Ubi Soft",
+        "42" => "Atlus",
+        "44" => "Malibu Interactive",
+        "46" => "Angel",
+        "47" => "Bullet-Proof Software",
+        "49" => "Irem",
+        "50" => "Absolute",
+        "51" => "Acclaim Entertainment",
+        "52" => "Activision",
+        "53" => "Sammy USA Corporation",
+        "54" => "Konami",
+        "55" => "Hi Tech Expressions",
+        "56" => "LJN",
+        "57" => "Matchbox",
+        "58" => "Mattel",
+        "59" => "Milton Bradley Company",
+        "60" => "Titus Interactive",
+        "61" => "Virgin Games Ltd.",
+        "64" => "Lucasfilm Games",
+        "67" => "Ocean Software",
+        "69" => "EA (Electronic Arts)",
+        "70" => "Infogrames",
+        "71" => "Interplay Entertainment",
+        "72" => "Broderbund",
+        "73" => "Sculptured Software",
+        "75" => "The Sales Curve Limited",
+        "78" => "THQ",
+        "79" => "Accolade",
+        "80" => "Misawa Entertainment",
+        "83" => "LOZC G.",
+        "86" => "Tokuma Shoten",
+        "87" => "Tsukuda Original",
+        "91" => "Chunsoft Co.",
+        "92" => "Video System",
+        "93" => "Ocean Software/Acclaim Entertainment",
+        "95" => "Varie",
+        "96" => "Yonezawa/S'Pal",
+        "97" => "Kaneko",
+        "99" => "Pack-In-Video",
+        "9H" => "Bottom Up",
+        "A4" => "Konami",
+        "BL" => "MTO",
+        "DK" => "Kodansha",
+        _ => return None
+    };
+    Some(publisher)
+}
+
+fn old_licensee_publisher(code: u8) -> Option<&'static str> {
+    let publisher = match code {
+        0x00 => "None",
+        0x01 => "Nintendo",
+        0x08 => "Capcom",
+        0x09 => "HOT-B",
+        0x0A => "Jaleco",
+        0x0B => "Coconuts Japan",
+        0x0C => "Elite Systems",
+        0x13 => "EA (Electronic Arts)",
+        0x18 => "Hudson Soft",
+        0x19 => "ITC Entertainment",
+        0x1A => "Yanoman",
+        0x1D => "Japan Clary",
+        0x1F => "Virgin Games Ltd.",
+        0x24 => "PCM Complete",
+        0x25 => "San-X",
+        0x28 => "Kemco",
+        0x29 => "SETA Corporation",
+        0x30 => "Infogrames",
+        0x31 => "Nintendo",
+        0x32 => "Bandai",
+        0x34 => "Konami",
+        0x35 => "HectorSoft",
+        0x38 => "Capcom",
+        0x39 => "Banpresto",
+        0x3C => "Entertainment Interactive",
+        0x3E => "Gremlin",
+        0x41 => "Ubi Soft",
+        0x42 => "Atlus",
+        0x44 => "Malibu Interactive",
+        0x46 => "Angel",
+        0x47 => "Spectrum HoloByte",
+        0x49 => "Irem",
+        0x4A => "Virgin Games Ltd.",
+        0x4D => "Malibu Interactive",
+        0x4F => "U.S. Gold",
+        0x50 => "Absolute",
+        0x51 => "Acclaim Entertainment",
+        0x52 => "Activision",
+        0x53 => "Sammy USA Corporation",
+        0x54 => "GameTek",
+        0x55 => "Park Place",
+        0x56 => "LJN",
+        0x57 => "Matchbox",
+        0x59 => "Milton Bradley Company",
+        0x5A => "Mindscape",
+        0x5B => "Romstar",
+        0x5C => "Naxat Soft",
+        0x5D => "Tradewest",
+        0x60 => "Titus Interactive",
+        0x61 => "Virgin Games Ltd.",
+        0x67 => "Ocean Software",
+        0x69 => "EA (Electronic Arts)",
+        0x6E => "Elite Systems",
+        0x6F => "Electro Brain",
+        0x70 => "Infogrames",
+        0x71 => "Interplay Entertainment",
+        0x72 => "Broderbund",
+        0x73 => "Sculptured Software",
+        0x75 => "The Sales Curve Limited",
+        0x78 => "THQ",
+        0x79 => "Accolade",
+        0x7A => "Triffix Entertainment",
+        0x7C => "MicroProse",
+        0x7F => "Kemco",
+        0x80 => "Misawa Entertainment",
+        0x83 => "LOZC G.",
+        0x86 => "Tokuma Shoten",
+        0x8B => "Bullet-Proof Software",
+        0x8C => "Vic Tokai Corp.",
+        0x8E => "Ape Inc.",
+        0x8F => "I'Max",
+        0x91 => "Chunsoft Co.",
+        0x92 => "Video System",
+        0x93 => "Tsubaraya Productions",
+        0x95 => "Varie",
+        0x96 => "Yonezawa/S'Pal",
+        0x97 => "Kemco",
+        0x99 => "Arc",
+        0x9A => "Nihon Bussan",
+        0x9B => "Tecmo",
+        0x9C => "Imagineer",
+        0x9D => "Banpresto",
+        0x9F => "Nova",
+        0xA1 => "Hori Electric",
+        0xA2 => "Bandai",
+        0xA4 => "Konami",
+        0xA6 => "Kawada",
+        0xA7 => "Takara",
+        0xA9 => "Technos Japan",
+        0xAA => "Broderbund",
+        0xAC => "Toei Animation",
+        0xAD => "Toho",
+        0xAF => "Namco",
+        0xB0 => "Acclaim Entertainment",
+        0xB1 => "ASCII Corporation or Nexsoft",
+        0xB2 => "Bandai",
+        0xB4 => "Square Enix",
+        0xB6 => "HAL Laboratory",
+        0xB7 => "SNK",
+        0xB9 => "Pony Canyon",
+        0xBA => "Culture Brain",
+        0xBB => "Sunsoft",
+        0xBD => "Sony Imagesoft",
+        0xBF => "Sammy Corporation",
+        0xC0 => "Taito",
+        0xC2 => "Kemco",
+        0xC3 => "Square",
+        0xC4 => "Tokuma Shoten",
+        0xC5 => "Data East",
+        0xC6 => "Tonkin House",
+        0xC8 => "Koei",
+        0xC9 => "UFL",
+        0xCA => "Ultra Games",
+        0xCB => "VAP, Inc.",
+        0xCC => "Use Corporation",
+        0xCD => "Meldac",
+        0xCE => "Pony Canyon",
+        0xCF => "Angel",
+        0xD0 => "Taito",
+        0xD1 => "SOFEL",
+        0xD2 => "Quest",
+        0xD3 => "Sigma Enterprises",
+        0xD4 => "ASK Kodansha Co.",
+        0xD6 => "Naxat Soft",
+        0xD7 => "Copya System",
+        0xD9 => "Banpresto",
+        0xDA => "Tomy",
+        0xDB => "LJN",
+        0xDD => "Nippon Computer Systems",
+        0xDE => "Human Ent.",
+        0xDF => "Altron",
+        0xE0 => "Jaleco",
+        0xE1 => "Towa Chiki",
+        0xE2 => "Yutaka",
+        0xE3 => "Varie",
+        0xE5 => "Epoch",
+        0xE7 => "Athena",
+        0xE8 => "Asmik Ace Entertainment",
+        0xE9 => "Natsume",
+        0xEA => "King Records",
+        0xEB => "Atlus",
+        0xEC => "Epic/Sony Records",
+        0xEE => "IGS",
+        0xF0 => "A Wave",
+        0xF3 => "Extreme Entertainment",
+        0xFF => "LJN",
+        _ => return None
+    };
+    Some(publisher)
+}
@@ -0,0 +1,106 @@
+use super::cartridge::Cartridge;
+use std::cmp;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Extension of save files, which are stored next to the ROM.
+pub const SAVE_FILE_EXTENSION: &str = "sav";
+
+/// A `.sav` file holding the battery backed RAM of a cartridge, in the raw
+/// format used by most other emulators. Cartridges with a clock have its
+/// state appended, as an `RtcFooter` for the MBC3, and their clock catches
+/// up on the time the emulator was closed when loaded.
+///
+/// Games write to save RAM whenever they like, so the host should `flush`
+/// periodically as well as on exit. Only changes are written, so flushing
+/// every second or so is cheap, though a running clock changes every second.
+pub struct SaveFile {
+    path: PathBuf,
+    /// Save RAM as last loaded or written
+    saved: Vec<u8>
+}
+
+impl SaveFile {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self { path: path.as_ref().to_path_buf(), saved: Vec::new() }
+    }
+
+    /// The save file next to the ROM at the given path, with the same name
+    /// and a `.sav` extension.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use gameboy::save::SaveFile;
+    /// # use std::path::Path;
+    /// let save_file = SaveFile::for_rom("roms/tetris.gb");
+    /// assert_eq!(save_file.path(), Path::new("roms/tetris.sav"));
+    /// ```
+    pub fn for_rom<P: AsRef<Path>>(rom_path: P) -> Self {
+        Self::new(rom_path.as_ref().with_extension(SAVE_FILE_EXTENSION))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Load the save file into the cartridge's save RAM. Returns false if the
+    /// cartridge has no battery or there is no save file yet.
+    pub fn load(&mut self, cartridge: &mut dyn Cartridge) -> io::Result<bool> {
+        if !cartridge.battery() {
+            return Ok(false);
+        }
+
+        let data = match fs::read(&self.path) {
+            Ok(data) => data,
+            Err(ref error) if error.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(error) => return Err(error)
+        };
+        let (ram, footer) = data.split_at(cmp::min(cartridge.save_ram().len(), data.len()));
+        cartridge.load_save_ram(ram);
+        let timestamp = unix_time();
+        cartridge.load_rtc(footer, timestamp);
+        self.saved = cartridge.save_ram();
+        self.saved.extend(cartridge.save_rtc(timestamp));
+        Ok(true)
+    }
+
+    /// Write the cartridge's save RAM to the save file if it has changed
+    /// since it was last loaded or written. Returns true if it was written.
+    pub fn flush(&mut self, cartridge: &dyn Cartridge) -> io::Result<bool> {
+        if !cartridge.battery() {
+            return Ok(false);
+        }
+
+        let mut data = cartridge.save_ram();
+        data.extend(cartridge.save_rtc(unix_time()));
+        if data.is_empty() || data == self.saved {
+            return Ok(false);
+        }
+        write_file_atomic(&self.path, &data)?;
+        self.saved = data;
+        Ok(true)
+    }
+}
+
+/// Replace the contents of a file so that it never holds partially written
+/// data, even if the emulator or machine crashes part way through. The data
+/// is written to a temporary file next to it, which then replaces it.
+pub fn write_file_atomic(path: &Path, data: &[u8]) -> io::Result<()> {
+    let mut temporary_name = path.file_name().map(OsString::from).unwrap_or_default();
+    temporary_name.push(".tmp");
+    let temporary_path = path.with_file_name(temporary_name);
+
+    let mut file = File::create(&temporary_path)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    fs::rename(&temporary_path, path)
+}
+
+/// Current UNIX time in seconds.
+fn unix_time() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or(0)
+}
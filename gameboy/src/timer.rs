@@ -0,0 +1,167 @@
+use super::*;
+
+/// Address of the divider register (DIV).
+pub const DIV_ADDRESS: Address = 0xFF04;
+/// Address of the timer counter (TIMA).
+pub const TIMA_ADDRESS: Address = 0xFF05;
+/// Address of the timer modulo (TMA).
+pub const TMA_ADDRESS: Address = 0xFF06;
+/// Address of the timer control register (TAC).
+pub const TAC_ADDRESS: Address = 0xFF07;
+
+/// Value of the internal divider when the boot ROM hands over to the
+/// cartridge on a DMG.
+const DIVIDER_INITIAL_VALUE: u16 = 0xABCC;
+
+/// Progress of TIMA through an overflow.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum TimaState {
+    /// Counting normally.
+    Counting,
+    /// TIMA overflowed during the last M-cycle and reads 0x00. TMA is loaded
+    /// and the interrupt raised at the end of the current M-cycle unless TIMA
+    /// is written first.
+    Overflowed,
+    /// TMA was loaded into TIMA during the last M-cycle. Writes to TIMA are
+    /// ignored and writes to TMA are also copied into TIMA.
+    Reloaded
+}
+
+/// The DIV/TIMA/TMA/TAC timer.
+///
+/// DIV is the upper byte of a 16-bit divider incremented every clock cycle.
+/// TIMA increments whenever the divider bit selected by TAC, ANDed with the
+/// TAC enable bit, goes from 1 to 0. Since this is edge triggered, writes to
+/// DIV and TAC which cause that signal to fall also increment TIMA.
+pub struct Timer {
+    divider: u16,
+    tima: u8,
+    tma: u8,
+    tac: u8,
+    state: TimaState
+}
+
+impl Timer {
+    pub fn new() -> Self {
+        Self {
+            divider: DIVIDER_INITIAL_VALUE,
+            tima: 0x00,
+            tma: 0x00,
+            tac: 0x00,
+            state: TimaState::Counting
+        }
+    }
+
+    /// Advance the timer by the given number of clock cycles. Returns true
+    /// if the timer interrupt should be raised.
+    pub fn tick(&mut self, cycles: Cycles) -> bool {
+        let mut interrupt = false;
+
+        // The timer only changes state on M-cycle boundaries
+        for _ in 0..(cycles / 4) {
+            match self.state {
+                TimaState::Reloaded => self.state = TimaState::Counting,
+                TimaState::Overflowed => {
+                    self.tima = self.tma;
+                    self.state = TimaState::Reloaded;
+                    interrupt = true;
+                },
+                TimaState::Counting => {}
+            }
+
+            let before = self.signal();
+            self.divider = self.divider.wrapping_add(4);
+            if before && !self.signal() {
+                self.increment();
+            }
+        }
+
+        interrupt
+    }
+
+    /// Read one of the timer registers.
+    pub fn read(&self, address: Address) -> u8 {
+        match address {
+            DIV_ADDRESS => (self.divider >> 8) as u8,
+            TIMA_ADDRESS => self.tima,
+            TMA_ADDRESS => self.tma,
+            TAC_ADDRESS => 0xF8 | self.tac,
+            _ => panic!("Unsupported timer read from address {:04X}", address)
+        }
+    }
+
+    /// Write one of the timer registers.
+    ///
+    /// # Examples
+    ///
+    /// Writing any value to DIV resets the divider:
+    ///
+    /// ```
+    /// # use gameboy::timer::*;
+    /// let mut timer = Timer::new();
+    /// timer.tick(1024);
+    /// timer.write(DIV_ADDRESS, 0x12);
+    /// assert_eq!(timer.read(DIV_ADDRESS), 0x00);
+    /// ```
+    pub fn write(&mut self, address: Address, value: u8) {
+        match address {
+            DIV_ADDRESS => {
+                let before = self.signal();
+                self.divider = 0;
+                if before {
+                    self.increment();
+                }
+            },
+            TIMA_ADDRESS => match self.state {
+                // Writing during the overflow cycle cancels the reload
+                TimaState::Overflowed => {
+                    self.tima = value;
+                    self.state = TimaState::Counting;
+                },
+                // TMA was just loaded and wins over the write
+                TimaState::Reloaded => {},
+                TimaState::Counting => self.tima = value
+            },
+            TMA_ADDRESS => {
+                self.tma = value;
+                if self.state == TimaState::Reloaded {
+                    self.tima = value;
+                }
+            },
+            TAC_ADDRESS => {
+                let before = self.signal();
+                self.tac = value & 0x07;
+                if before && !self.signal() {
+                    self.increment();
+                }
+            },
+            _ => panic!("Unsupported timer write to address {:04X}", address)
+        }
+    }
+
+    /// Divider bit selected by TAC ANDed with the timer enable bit. TIMA
+    /// increments on the falling edge of this signal.
+    fn signal(&self) -> bool {
+        let bit = match self.tac & 0x03 {
+            0 => 9,
+            1 => 3,
+            2 => 5,
+            _ => 7
+        };
+        self.tac & 0x04 != 0 && (self.divider >> bit) & 0x01 != 0
+    }
+
+    fn increment(&mut self) {
+        let (tima, overflow) = self.tima.overflowing_add(1);
+        self.tima = tima;
+        if overflow {
+            self.state = TimaState::Overflowed;
+        }
+    }
+}
+
+impl Default for Timer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,1348 @@
+extern crate gameboy;
+use gameboy::cartridge::*;
+use gameboy::rom::{CartridgeKind, Rom, NINTENDO_LOGO_BYTES};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// Creates ROM data where every byte of each 16 KiB bank holds its bank number
+fn banked_rom(banks: usize) -> Vec<u8> {
+    let mut data = vec![0; banks * ROM_BANK_SIZE_BYTES];
+    for (bank, chunk) in data.chunks_mut(ROM_BANK_SIZE_BYTES).enumerate() {
+        for byte in chunk.iter_mut() {
+            *byte = bank as u8;
+        }
+    }
+    data
+}
+
+#[test]
+fn test_mbc1_rom_banking() {
+    let mut cartridge = Mbc1::new(banked_rom(128), 0, false);
+    assert!(!cartridge.multicart());
+    assert_eq!(cartridge.read_byte(0x0000), 0x00);
+    assert_eq!(cartridge.read_byte(0x4000), 0x01);
+
+    cartridge.write_byte(0x2000, 0x05);
+    assert_eq!(cartridge.read_byte(0x7FFF), 0x05);
+
+    // Bank 0 selects bank 1, as does any value with the lower 5 bits clear
+    cartridge.write_byte(0x2000, 0x00);
+    assert_eq!(cartridge.read_byte(0x4000), 0x01);
+    cartridge.write_byte(0x2000, 0xE0);
+    assert_eq!(cartridge.read_byte(0x4000), 0x01);
+
+    // Upper bits come from BANK2, so bank 0x20 reads as 0x21
+    cartridge.write_byte(0x4000, 0x01);
+    assert_eq!(cartridge.read_byte(0x4000), 0x21);
+    cartridge.write_byte(0x2000, 0x1F);
+    cartridge.write_byte(0x4000, 0x03);
+    assert_eq!(cartridge.read_byte(0x4000), 0x7F);
+
+    // Mode 0 always maps bank 0 at 0x0000, mode 1 uses BANK2
+    assert_eq!(cartridge.read_byte(0x0000), 0x00);
+    cartridge.write_byte(0x6000, 0x01);
+    assert_eq!(cartridge.read_byte(0x0000), 0x60);
+    assert_eq!(cartridge.read_byte(0x4000), 0x7F);
+}
+
+#[test]
+fn test_mbc1_rom_bank_wraps() {
+    let mut cartridge = Mbc1::new(banked_rom(8), 0, false);
+    cartridge.write_byte(0x2000, 0x09);
+    assert_eq!(cartridge.read_byte(0x4000), 0x01);
+    cartridge.write_byte(0x4000, 0x01);
+    cartridge.write_byte(0x6000, 0x01);
+    assert_eq!(cartridge.read_byte(0x0000), 0x00);
+}
+
+#[test]
+fn test_mbc1_ram() {
+    let mut cartridge = Mbc1::new(banked_rom(4), 0x8000, false);
+
+    // RAM is disabled until 0x0A is written to the low nibble
+    cartridge.write_byte(0xA000, 0x12);
+    assert_eq!(cartridge.read_byte(0xA000), 0xFF);
+    cartridge.write_byte(0x0000, 0x1A);
+    cartridge.write_byte(0xA000, 0x12);
+    assert_eq!(cartridge.read_byte(0xA000), 0x12);
+
+    // RAM banks are only switched in mode 1
+    cartridge.write_byte(0x4000, 0x02);
+    assert_eq!(cartridge.read_byte(0xA000), 0x12);
+    cartridge.write_byte(0x6000, 0x01);
+    assert_eq!(cartridge.read_byte(0xA000), 0x00);
+    cartridge.write_byte(0xBFFF, 0x34);
+    assert_eq!(cartridge.read_byte(0xBFFF), 0x34);
+    cartridge.write_byte(0x6000, 0x00);
+    assert_eq!(cartridge.read_byte(0xBFFF), 0x00);
+    assert_eq!(cartridge.read_byte(0xA000), 0x12);
+
+    cartridge.write_byte(0x0000, 0x00);
+    assert_eq!(cartridge.read_byte(0xA000), 0xFF);
+}
+
+#[test]
+fn test_mbc1_without_ram() {
+    let mut cartridge = Mbc1::new(banked_rom(4), 0, false);
+    cartridge.write_byte(0x0000, 0x0A);
+    cartridge.write_byte(0xA000, 0x12);
+    assert_eq!(cartridge.read_byte(0xA000), 0xFF);
+}
+
+#[test]
+fn test_mbc1_multicart() {
+    let mut data = banked_rom(64);
+    for game in 0..4 {
+        let offset = game * 0x10 * ROM_BANK_SIZE_BYTES + 0x0104;
+        data[offset..offset + NINTENDO_LOGO_BYTES.len()].copy_from_slice(&NINTENDO_LOGO_BYTES);
+    }
+    let mut cartridge = Mbc1::new(data, 0, false);
+    assert!(cartridge.multicart());
+
+    // BANK2 selects bits 4-5 and bit 4 of BANK1 is ignored
+    cartridge.write_byte(0x2000, 0x12);
+    cartridge.write_byte(0x4000, 0x01);
+    assert_eq!(cartridge.read_byte(0x4000), 0x12);
+    cartridge.write_byte(0x6000, 0x01);
+    assert_eq!(cartridge.read_byte(0x0000), 0x10);
+    cartridge.write_byte(0x4000, 0x03);
+    assert_eq!(cartridge.read_byte(0x0000), 0x30);
+}
+
+#[test]
+fn test_mbc1_single_logo_not_multicart() {
+    let mut data = banked_rom(64);
+    data[0x0104..0x0134].copy_from_slice(&NINTENDO_LOGO_BYTES);
+    assert!(!Mbc1::new(data, 0, false).multicart());
+}
+
+#[test]
+fn test_rom_into_mbc1_cartridge() {
+    let mut data = banked_rom(4);
+    data[0x0147] = CartridgeKind::Mbc1RamBattery as u8;
+    data[0x0149] = 0x02;
+    let mut cartridge = Rom::new(data).unwrap().into_cartridge().unwrap();
+    cartridge.write_byte(0x2000, 0x03);
+    assert_eq!(cartridge.read_byte(0x4000), 0x03);
+    cartridge.write_byte(0x0000, 0x0A);
+    cartridge.write_byte(0xA123, 0x45);
+    assert_eq!(cartridge.read_byte(0xA123), 0x45);
+}
+
+#[test]
+fn test_mbc2_rom_banking() {
+    let mut cartridge = Mbc2::new(banked_rom(16), false);
+    assert_eq!(cartridge.read_byte(0x4000), 0x01);
+
+    // Address bit 8 selects the ROM bank register
+    cartridge.write_byte(0x2100, 0x0A);
+    assert_eq!(cartridge.read_byte(0x4000), 0x0A);
+    cartridge.write_byte(0x0100, 0x13);
+    assert_eq!(cartridge.read_byte(0x7FFF), 0x03);
+    cartridge.write_byte(0x3FFF, 0x00);
+    assert_eq!(cartridge.read_byte(0x4000), 0x01);
+
+    // Without bit 8 the write goes to RAM enable
+    cartridge.write_byte(0x2000, 0x05);
+    assert_eq!(cartridge.read_byte(0x4000), 0x01);
+    assert_eq!(cartridge.read_byte(0x0000), 0x00);
+}
+
+#[test]
+fn test_mbc2_ram() {
+    let mut cartridge = Mbc2::new(banked_rom(16), false);
+    cartridge.write_byte(0xA000, 0x05);
+    assert_eq!(cartridge.read_byte(0xA000), 0xFF);
+
+    cartridge.write_byte(0x0100, 0x0A);
+    assert_eq!(cartridge.read_byte(0xA000), 0xFF);
+    cartridge.write_byte(0x0000, 0x0A);
+    cartridge.write_byte(0xA000, 0x05);
+    assert_eq!(cartridge.read_byte(0xA000), 0xF5);
+
+    // Only the low nibble is stored, and RAM is echoed every 512 bytes
+    cartridge.write_byte(0xA1FF, 0xAB);
+    assert_eq!(cartridge.read_byte(0xA1FF), 0xFB);
+    assert_eq!(cartridge.read_byte(0xA3FF), 0xFB);
+    assert_eq!(cartridge.read_byte(0xBFFF), 0xFB);
+    cartridge.write_byte(0xB200, 0x0C);
+    assert_eq!(cartridge.read_byte(0xA000), 0xFC);
+    assert_eq!(MBC2_RAM_SIZE, 512);
+
+    cartridge.write_byte(0x1000, 0x00);
+    assert_eq!(cartridge.read_byte(0xA000), 0xFF);
+}
+
+#[test]
+fn test_rom_into_mbc2_cartridge() {
+    let mut data = banked_rom(16);
+    data[0x0147] = CartridgeKind::Mbc2Battery as u8;
+    let mut cartridge = Rom::new(data).unwrap().into_cartridge().unwrap();
+    cartridge.write_byte(0x2100, 0x07);
+    assert_eq!(cartridge.read_byte(0x4000), 0x07);
+}
+
+// Advances a cartridge by the given number of seconds in chunks small
+// enough for the cycle counter
+fn tick_seconds(cartridge: &mut dyn Cartridge, seconds: u32) {
+    for _ in 0..(seconds * (CYCLES_PER_SECOND / 0x8000)) {
+        cartridge.tick(0x8000);
+    }
+}
+
+// Selects an RTC register and reads it after latching the clock
+fn read_rtc(cartridge: &mut dyn Cartridge, register: u8) -> u8 {
+    cartridge.write_byte(0x6000, 0x00);
+    cartridge.write_byte(0x6000, 0x01);
+    cartridge.write_byte(0x4000, register);
+    cartridge.read_byte(0xA000)
+}
+
+#[test]
+fn test_mbc3_rom_banking() {
+    let mut cartridge = Mbc3::new(banked_rom(128), 0, false, false);
+    assert_eq!(cartridge.read_byte(0x4000), 0x01);
+    cartridge.write_byte(0x2000, 0x7F);
+    assert_eq!(cartridge.read_byte(0x4000), 0x7F);
+    cartridge.write_byte(0x2000, 0x80);
+    assert_eq!(cartridge.read_byte(0x4000), 0x01);
+    cartridge.write_byte(0x2000, 0x20);
+    assert_eq!(cartridge.read_byte(0x4000), 0x20);
+    assert_eq!(cartridge.read_byte(0x3FFF), 0x00);
+}
+
+#[test]
+fn test_mbc3_ram_banking() {
+    let mut cartridge = Mbc3::new(banked_rom(4), 0x8000, false, false);
+    cartridge.write_byte(0x0000, 0x0A);
+    for bank in 0..4 {
+        cartridge.write_byte(0x4000, bank);
+        cartridge.write_byte(0xA000, 0x10 + bank);
+    }
+    for bank in 0..4 {
+        cartridge.write_byte(0x4000, bank);
+        assert_eq!(cartridge.read_byte(0xA000), 0x10 + bank);
+    }
+
+    // There's no clock on this cartridge
+    cartridge.write_byte(0x4000, RTC_SECONDS);
+    assert_eq!(cartridge.read_byte(0xA000), 0xFF);
+}
+
+#[test]
+fn test_mbc3_rtc_latch() {
+    let mut cartridge = Mbc3::new(banked_rom(4), 0, true, false);
+    cartridge.write_byte(0x0000, 0x0A);
+    tick_seconds(&mut cartridge, 5);
+    assert_eq!(read_rtc(&mut cartridge, RTC_SECONDS), 5);
+
+    // Registers keep their latched value until latched again, which needs
+    // a write of 0x00 followed by 0x01
+    tick_seconds(&mut cartridge, 1);
+    assert_eq!(cartridge.read_byte(0xA000), 5);
+    cartridge.write_byte(0x6000, 0x01);
+    assert_eq!(cartridge.read_byte(0xA000), 5);
+    assert_eq!(read_rtc(&mut cartridge, RTC_SECONDS), 6);
+
+    cartridge.write_byte(0x0000, 0x00);
+    assert_eq!(cartridge.read_byte(0xA000), 0xFF);
+}
+
+#[test]
+fn test_mbc3_rtc_write_and_rollover() {
+    let mut cartridge = Mbc3::new(banked_rom(4), 0, true, false);
+    cartridge.write_byte(0x0000, 0x0A);
+    let registers = [(RTC_SECONDS, 59), (RTC_MINUTES, 59), (RTC_HOURS, 23),
+                     (RTC_DAYS_LOW, 0xFF), (RTC_DAYS_HIGH, 0x01)];
+    for (register, value) in registers.iter() {
+        cartridge.write_byte(0x4000, *register);
+        cartridge.write_byte(0xA000, *value);
+    }
+
+    tick_seconds(&mut cartridge, 1);
+    assert_eq!(read_rtc(&mut cartridge, RTC_SECONDS), 0);
+    assert_eq!(read_rtc(&mut cartridge, RTC_MINUTES), 0);
+    assert_eq!(read_rtc(&mut cartridge, RTC_HOURS), 0);
+    assert_eq!(read_rtc(&mut cartridge, RTC_DAYS_LOW), 0);
+    // Day counter overflow sets the carry flag, which stays set
+    assert_eq!(read_rtc(&mut cartridge, RTC_DAYS_HIGH), 0x80);
+    tick_seconds(&mut cartridge, 86400);
+    assert_eq!(read_rtc(&mut cartridge, RTC_DAYS_LOW), 1);
+    assert_eq!(read_rtc(&mut cartridge, RTC_DAYS_HIGH), 0x80);
+}
+
+#[test]
+fn test_mbc3_rtc_invalid_values_wrap() {
+    let mut cartridge = Mbc3::new(banked_rom(4), 0, true, false);
+    cartridge.write_byte(0x0000, 0x0A);
+    cartridge.write_byte(0x4000, RTC_SECONDS);
+    cartridge.write_byte(0xA000, 63);
+    tick_seconds(&mut cartridge, 1);
+    assert_eq!(read_rtc(&mut cartridge, RTC_SECONDS), 0);
+    assert_eq!(read_rtc(&mut cartridge, RTC_MINUTES), 0);
+}
+
+#[test]
+fn test_mbc3_rtc_halt() {
+    let mut cartridge = Mbc3::new(banked_rom(4), 0, true, false);
+    cartridge.write_byte(0x0000, 0x0A);
+    cartridge.write_byte(0x4000, RTC_DAYS_HIGH);
+    cartridge.write_byte(0xA000, 0x40);
+    tick_seconds(&mut cartridge, 3);
+    assert_eq!(read_rtc(&mut cartridge, RTC_SECONDS), 0);
+
+    cartridge.write_byte(0x4000, RTC_DAYS_HIGH);
+    cartridge.write_byte(0xA000, 0x00);
+    tick_seconds(&mut cartridge, 3);
+    assert_eq!(read_rtc(&mut cartridge, RTC_SECONDS), 3);
+}
+
+#[test]
+fn test_rom_into_mbc3_cartridge() {
+    let mut data = banked_rom(4);
+    data[0x0147] = CartridgeKind::Mbc3TimerRamBattery as u8;
+    data[0x0149] = 0x03;
+    let mut cartridge = Rom::new(data).unwrap().into_cartridge().unwrap();
+    cartridge.write_byte(0x0000, 0x0A);
+    cartridge.write_byte(0x4000, 0x03);
+    cartridge.write_byte(0xA000, 0x99);
+    assert_eq!(cartridge.read_byte(0xA000), 0x99);
+    tick_seconds(cartridge.as_mut(), 2);
+    assert_eq!(read_rtc(cartridge.as_mut(), RTC_SECONDS), 2);
+}
+
+#[test]
+fn test_mbc5_rom_banking() {
+    let mut data = banked_rom(512);
+    // Mark the upper 256 banks so they can be told apart
+    for bank in 256..512 {
+        data[bank * ROM_BANK_SIZE_BYTES + 1] = 0x80;
+    }
+    let mut cartridge = Mbc5::new(data, 0, false, false);
+    assert_eq!(cartridge.read_byte(0x4000), 0x01);
+
+    // Bank 0 can be mapped at 0x4000
+    cartridge.write_byte(0x2000, 0x00);
+    assert_eq!(cartridge.read_byte(0x4000), 0x00);
+    cartridge.write_byte(0x2FFF, 0xFF);
+    assert_eq!(cartridge.read_byte(0x4000), 0xFF);
+    assert_eq!(cartridge.read_byte(0x4001), 0xFF);
+
+    cartridge.write_byte(0x3000, 0x01);
+    assert_eq!(cartridge.read_byte(0x4000), 0xFF);
+    assert_eq!(cartridge.read_byte(0x4001), 0x80);
+    cartridge.write_byte(0x2000, 0x00);
+    assert_eq!(cartridge.read_byte(0x4000), 0x00);
+    assert_eq!(cartridge.read_byte(0x4001), 0x80);
+    assert_eq!(cartridge.read_byte(0x0001), 0x00);
+}
+
+#[test]
+fn test_mbc5_ram_banking() {
+    let mut cartridge = Mbc5::new(banked_rom(4), 0x20000, false, false);
+    cartridge.write_byte(0x0000, 0x1A);
+    cartridge.write_byte(0xA000, 0x12);
+    assert_eq!(cartridge.read_byte(0xA000), 0xFF);
+
+    cartridge.write_byte(0x0000, 0x0A);
+    for bank in 0..16 {
+        cartridge.write_byte(0x4000, bank);
+        cartridge.write_byte(0xB000, bank);
+    }
+    for bank in 0..16 {
+        cartridge.write_byte(0x4000, bank);
+        assert_eq!(cartridge.read_byte(0xB000), bank);
+    }
+    assert!(!cartridge.rumble());
+}
+
+#[test]
+fn test_mbc5_rumble() {
+    let mut cartridge = Mbc5::new(banked_rom(4), 0x8000, true, false);
+    cartridge.write_byte(0x0000, 0x0A);
+    cartridge.write_byte(0x4000, 0x01);
+    cartridge.write_byte(0xA000, 0x34);
+    assert!(!cartridge.rumble());
+
+    // Bit 3 drives the motor rather than selecting a RAM bank
+    cartridge.write_byte(0x4000, 0x09);
+    assert!(cartridge.rumble());
+    assert_eq!(cartridge.read_byte(0xA000), 0x34);
+    cartridge.write_byte(0x4000, 0x01);
+    assert!(!cartridge.rumble());
+}
+
+#[test]
+fn test_mbc5_rumble_callback() {
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let recorded = events.clone();
+    let mut data = banked_rom(4);
+    data[0x0147] = CartridgeKind::Mbc5Rumble as u8;
+    let mut cartridge = Rom::new(data).unwrap().into_cartridge().unwrap();
+    cartridge.connect_rumble(Box::new(move |rumble| recorded.borrow_mut().push(rumble)));
+
+    // Every edge is reported, however short the pulse, and repeated writes
+    // of the same state are not
+    for &value in &[0x08, 0x00, 0x08, 0x09, 0x01, 0x00] {
+        cartridge.write_byte(0x4000, value);
+    }
+    assert_eq!(*events.borrow(), vec![true, false, true, false]);
+}
+
+#[test]
+fn test_rom_into_mbc5_cartridge() {
+    let mut data = banked_rom(4);
+    data[0x0147] = CartridgeKind::Mbc5RumbleRamBattery as u8;
+    data[0x0149] = 0x02;
+    let mut cartridge = Rom::new(data).unwrap().into_cartridge().unwrap();
+    cartridge.write_byte(0x2000, 0x02);
+    assert_eq!(cartridge.read_byte(0x4000), 0x02);
+    cartridge.write_byte(0x4000, 0x08);
+    assert!(cartridge.rumble());
+}
+
+// Creates ROM data where the first byte of each 8 KiB bank holds its number
+fn mbc6_rom() -> Vec<u8> {
+    let mut data = vec![0; 0x100000];
+    for (bank, chunk) in data.chunks_mut(0x2000).enumerate() {
+        chunk[0] = bank as u8;
+    }
+    data
+}
+
+// Maps flash bank 2 at 0x4000 and flash bank 1 at 0x6000 so the flash
+// command addresses 0x5555 and 0x2AAA are at 0x5555 and 0x6AAA
+fn enable_mbc6_flash(cartridge: &mut Mbc6) {
+    cartridge.write_byte(0x0C00, 0x01);
+    cartridge.write_byte(0x1000, 0x01);
+    cartridge.write_byte(0x2000, 0x02);
+    cartridge.write_byte(0x2800, 0x08);
+    cartridge.write_byte(0x3000, 0x01);
+    cartridge.write_byte(0x3800, 0x08);
+}
+
+fn mbc6_flash_command(cartridge: &mut Mbc6, command: u8) {
+    cartridge.write_byte(0x5555, 0xAA);
+    cartridge.write_byte(0x6AAA, 0x55);
+    cartridge.write_byte(0x5555, command);
+}
+
+#[test]
+fn test_mbc6_rom_windows() {
+    let mut cartridge = Mbc6::new(mbc6_rom());
+    cartridge.write_byte(0x2000, 0x05);
+    cartridge.write_byte(0x3000, 0x7E);
+    assert_eq!(cartridge.read_byte(0x4000), 0x05);
+    assert_eq!(cartridge.read_byte(0x6000), 0x7E);
+    assert_eq!(cartridge.read_byte(0x0000), 0x00);
+    assert_eq!(cartridge.read_byte(0x2000), 0x01);
+}
+
+#[test]
+fn test_mbc6_ram_windows() {
+    let mut cartridge = Mbc6::new(mbc6_rom());
+    cartridge.write_byte(0x0000, 0x0A);
+    cartridge.write_byte(0x0400, 0x03);
+    cartridge.write_byte(0x0800, 0x03);
+    cartridge.write_byte(0xA010, 0x42);
+    assert_eq!(cartridge.read_byte(0xB010), 0x42);
+
+    cartridge.write_byte(0x0800, 0x07);
+    assert_eq!(cartridge.read_byte(0xB010), 0x00);
+    cartridge.write_byte(0xBFFF, 0x24);
+    cartridge.write_byte(0x0400, 0x07);
+    assert_eq!(cartridge.read_byte(0xAFFF), 0x24);
+
+    cartridge.write_byte(0x0000, 0x00);
+    assert_eq!(cartridge.read_byte(0xAFFF), 0xFF);
+}
+
+#[test]
+fn test_mbc6_flash_program_and_erase() {
+    let mut cartridge = Mbc6::new(mbc6_rom());
+    enable_mbc6_flash(&mut cartridge);
+    assert_eq!(cartridge.read_byte(0x4000), 0xFF);
+
+    mbc6_flash_command(&mut cartridge, 0xA0);
+    cartridge.write_byte(0x4123, 0x5A);
+    assert_eq!(cartridge.read_byte(0x4123), 0x5A);
+    assert_eq!(cartridge.flash()[0x4123], 0x5A);
+
+    // Without a command writes are ignored and programming only clears bits
+    cartridge.write_byte(0x4123, 0xFF);
+    assert_eq!(cartridge.read_byte(0x4123), 0x5A);
+    mbc6_flash_command(&mut cartridge, 0xA0);
+    cartridge.write_byte(0x4123, 0x0F);
+    assert_eq!(cartridge.read_byte(0x4123), 0x0A);
+
+    // Programming 0xF0 writes it rather than resetting the chip
+    mbc6_flash_command(&mut cartridge, 0xA0);
+    cartridge.write_byte(0x4124, 0xF0);
+    assert_eq!(cartridge.read_byte(0x4124), 0xF0);
+    assert_eq!(cartridge.flash()[0x4124], 0xF0);
+
+    mbc6_flash_command(&mut cartridge, 0x80);
+    cartridge.write_byte(0x5555, 0xAA);
+    cartridge.write_byte(0x6AAA, 0x55);
+    cartridge.write_byte(0x4000, 0x30);
+    assert_eq!(cartridge.read_byte(0x4123), 0xFF);
+}
+
+#[test]
+fn test_mbc6_flash_write_protect() {
+    let mut cartridge = Mbc6::new(mbc6_rom());
+    enable_mbc6_flash(&mut cartridge);
+    cartridge.write_byte(0x1000, 0x00);
+    mbc6_flash_command(&mut cartridge, 0xA0);
+    cartridge.write_byte(0x4123, 0x5A);
+    assert_eq!(cartridge.read_byte(0x4123), 0xFF);
+}
+
+#[test]
+fn test_mbc6_flash_id() {
+    let mut cartridge = Mbc6::new(mbc6_rom());
+    enable_mbc6_flash(&mut cartridge);
+    mbc6_flash_command(&mut cartridge, 0x90);
+    cartridge.write_byte(0x2000, 0x00);
+    assert_eq!(cartridge.read_byte(0x4000), 0xC2);
+    assert_eq!(cartridge.read_byte(0x4001), 0x81);
+
+    cartridge.write_byte(0x4000, 0xF0);
+    assert_eq!(cartridge.read_byte(0x4000), 0xFF);
+}
+
+#[test]
+fn test_mbc6_load_flash() {
+    let mut cartridge = Mbc6::new(mbc6_rom());
+    let mut flash = vec![0x00; MBC6_FLASH_SIZE_BYTES];
+    flash[0x2000] = 0x77;
+    cartridge.load_flash(&flash);
+    enable_mbc6_flash(&mut cartridge);
+    assert_eq!(cartridge.read_byte(0x6000), 0x77);
+}
+
+fn enabled_mbc7() -> Mbc7 {
+    let mut cartridge = Mbc7::new(banked_rom(8));
+    cartridge.write_byte(0x0000, 0x0A);
+    cartridge.write_byte(0x4000, 0x40);
+    cartridge
+}
+
+// Clocks a bit into the EEPROM with chip select held high, returning the
+// data out line after the rising edge
+fn eeprom_clock(cartridge: &mut Mbc7, bit: bool) -> bool {
+    let data = if bit { 0x02 } else { 0x00 };
+    cartridge.write_byte(0xA080, 0x80 | data);
+    cartridge.write_byte(0xA080, 0xC0 | data);
+    cartridge.read_byte(0xA080) & 0x01 != 0
+}
+
+// Sends a start bit followed by the given number of bits MSB first
+fn eeprom_command(cartridge: &mut Mbc7, bits: u32, count: u8) {
+    cartridge.write_byte(0xA080, 0x00);
+    cartridge.write_byte(0xA080, 0x80);
+    eeprom_clock(cartridge, true);
+    for index in (0..count).rev() {
+        eeprom_clock(cartridge, bits & (1 << index) != 0);
+    }
+}
+
+fn eeprom_read(cartridge: &mut Mbc7, address: u8) -> u16 {
+    eeprom_command(cartridge, 0b10 << 8 | address as u32, 10);
+    assert!(cartridge.read_byte(0xA080) & 0x01 == 0);
+    let mut word = 0;
+    for _ in 0..16 {
+        word = word << 1 | eeprom_clock(cartridge, false) as u16;
+    }
+    cartridge.write_byte(0xA080, 0x00);
+    word
+}
+
+#[test]
+fn test_mbc7_registers_enable() {
+    let mut cartridge = Mbc7::new(banked_rom(8));
+    assert_eq!(cartridge.read_byte(0xA060), 0xFF);
+    cartridge.write_byte(0x4000, 0x40);
+    assert_eq!(cartridge.read_byte(0xA060), 0xFF);
+    cartridge.write_byte(0x0000, 0x0A);
+    cartridge.write_byte(0x4000, 0x40);
+    assert_eq!(cartridge.read_byte(0xA060), 0x00);
+    assert_eq!(cartridge.read_byte(0xA070), 0xFF);
+    assert_eq!(cartridge.read_byte(0xB060), 0xFF);
+
+    cartridge.write_byte(0x2000, 0x05);
+    assert_eq!(cartridge.read_byte(0x4000), 0x05);
+}
+
+#[test]
+fn test_mbc7_accelerometer() {
+    let mut cartridge = enabled_mbc7();
+    cartridge.set_accelerometer(1.0, -0.5);
+
+    // Latching needs the latch to be cleared first
+    cartridge.write_byte(0xA010, 0xAA);
+    assert_eq!(cartridge.read_byte(0xA020), 0x00);
+    assert_eq!(cartridge.read_byte(0xA030), 0x80);
+
+    cartridge.write_byte(0xA000, 0x55);
+    cartridge.write_byte(0xA010, 0xAA);
+    let x = ACCELEROMETER_CENTRE + ACCELEROMETER_GRAVITY as u16;
+    let y = ACCELEROMETER_CENTRE - ACCELEROMETER_GRAVITY as u16 / 2;
+    assert_eq!(cartridge.read_byte(0xA020), x as u8);
+    assert_eq!(cartridge.read_byte(0xA030), (x >> 8) as u8);
+    assert_eq!(cartridge.read_byte(0xA040), y as u8);
+    assert_eq!(cartridge.read_byte(0xA050), (y >> 8) as u8);
+
+    // Further changes need another latch
+    cartridge.set_accelerometer(0.0, 0.0);
+    cartridge.write_byte(0xA010, 0xAA);
+    assert_eq!(cartridge.read_byte(0xA020), x as u8);
+}
+
+#[test]
+fn test_mbc7_eeprom_write_protect() {
+    let mut cartridge = enabled_mbc7();
+    eeprom_command(&mut cartridge, (0b01 << 8 | 0x05) << 16 | 0x1234, 26);
+    assert_eq!(eeprom_read(&mut cartridge, 0x05), 0xFFFF);
+}
+
+#[test]
+fn test_mbc7_eeprom_write_read_erase() {
+    let mut cartridge = enabled_mbc7();
+    // EWEN
+    eeprom_command(&mut cartridge, 0b00_1100_0000, 10);
+    // WRITE
+    eeprom_command(&mut cartridge, (0b01 << 8 | 0x05) << 16 | 0x1234, 26);
+    assert!(cartridge.read_byte(0xA080) & 0x01 != 0);
+    assert_eq!(eeprom_read(&mut cartridge, 0x05), 0x1234);
+    assert_eq!(cartridge.eeprom()[0x0A], 0x12);
+    assert_eq!(cartridge.eeprom()[0x0B], 0x34);
+
+    // ERASE
+    eeprom_command(&mut cartridge, 0b11 << 8 | 0x05, 10);
+    assert_eq!(eeprom_read(&mut cartridge, 0x05), 0xFFFF);
+
+    // WRAL then ERAL
+    eeprom_command(&mut cartridge, 0b00_0100_0000 << 16 | 0xA5A5, 26);
+    assert_eq!(eeprom_read(&mut cartridge, 0x00), 0xA5A5);
+    assert_eq!(eeprom_read(&mut cartridge, 0x7F), 0xA5A5);
+    eeprom_command(&mut cartridge, 0b00_1000_0000, 10);
+    assert_eq!(eeprom_read(&mut cartridge, 0x7F), 0xFFFF);
+
+    // EWDS
+    eeprom_command(&mut cartridge, 0b00_0000_0000, 10);
+    eeprom_command(&mut cartridge, (0b01 << 8 | 0x05) << 16 | 0x1234, 26);
+    assert_eq!(eeprom_read(&mut cartridge, 0x05), 0xFFFF);
+}
+
+#[test]
+fn test_mbc7_load_eeprom() {
+    let mut cartridge = enabled_mbc7();
+    let mut data = vec![0xFF; MBC7_EEPROM_SIZE_BYTES];
+    data[0x02] = 0xBE;
+    data[0x03] = 0xEF;
+    cartridge.load_eeprom(&data);
+    assert_eq!(eeprom_read(&mut cartridge, 0x01), 0xBEEF);
+}
+
+#[test]
+fn test_huc1_banking() {
+    let mut cartridge = HuC1::new(banked_rom(64), 0x8000);
+    cartridge.write_byte(0x2000, 0x3F);
+    assert_eq!(cartridge.read_byte(0x4000), 0x3F);
+    cartridge.write_byte(0x2000, 0x00);
+    assert_eq!(cartridge.read_byte(0x4000), 0x01);
+
+    cartridge.write_byte(0x0000, 0x0A);
+    cartridge.write_byte(0x4000, 0x02);
+    cartridge.write_byte(0xA000, 0x22);
+    cartridge.write_byte(0x4000, 0x01);
+    assert_eq!(cartridge.read_byte(0xA000), 0x00);
+    cartridge.write_byte(0x4000, 0x02);
+    assert_eq!(cartridge.read_byte(0xA000), 0x22);
+}
+
+#[test]
+fn test_huc1_infrared() {
+    let mut first = HuC1::new(banked_rom(4), 0x2000);
+    let mut second = HuC1::new(banked_rom(4), 0x2000);
+    let (left, right) = InfraredPort::pair();
+    first.connect_infrared(Box::new(left));
+    second.connect_infrared(Box::new(right));
+
+    first.write_byte(0x0000, 0x0E);
+    second.write_byte(0x0000, 0x0E);
+    assert_eq!(second.read_byte(0xA000), 0xC0);
+    first.write_byte(0xA000, 0x01);
+    assert_eq!(second.read_byte(0xA000), 0xC1);
+    assert_eq!(first.read_byte(0xA000), 0xC0);
+    first.write_byte(0xA000, 0x00);
+    assert_eq!(second.read_byte(0xA000), 0xC0);
+
+    // Writes in RAM mode don't reach the LED
+    first.write_byte(0x0000, 0x0A);
+    first.write_byte(0xA000, 0x01);
+    assert_eq!(second.read_byte(0xA000), 0xC0);
+    assert_eq!(first.read_byte(0xA000), 0x01);
+}
+
+// Runs a HuC3 RTC command and returns the response
+fn huc3_command(cartridge: &mut dyn Cartridge, command: u8) -> u8 {
+    cartridge.write_byte(0x0000, 0x0B);
+    cartridge.write_byte(0xA000, command);
+    cartridge.write_byte(0x0000, 0x0D);
+    assert_eq!(cartridge.read_byte(0xA000) & 0x01, 0x01);
+    cartridge.write_byte(0xA000, 0xFE);
+    cartridge.write_byte(0x0000, 0x0C);
+    cartridge.read_byte(0xA000)
+}
+
+// Reads the value of the RTC memory at the given address
+fn huc3_read_memory(cartridge: &mut dyn Cartridge, address: u8) -> u8 {
+    huc3_command(cartridge, 0x40 | (address & 0x0F));
+    huc3_command(cartridge, 0x50 | address >> 4);
+    huc3_command(cartridge, 0x10) & 0x0F
+}
+
+#[test]
+fn test_huc3_ram_modes() {
+    let mut cartridge = HuC3::new(banked_rom(128), 0x8000);
+    cartridge.write_byte(0x2000, 0x7F);
+    assert_eq!(cartridge.read_byte(0x4000), 0x7F);
+
+    cartridge.write_byte(0x0000, 0x0A);
+    cartridge.write_byte(0x4000, 0x03);
+    cartridge.write_byte(0xA000, 0x33);
+    assert_eq!(cartridge.read_byte(0xA000), 0x33);
+
+    // Mode 0 is read only
+    cartridge.write_byte(0x0000, 0x00);
+    cartridge.write_byte(0xA000, 0x44);
+    assert_eq!(cartridge.read_byte(0xA000), 0x33);
+}
+
+#[test]
+fn test_huc3_rtc_memory() {
+    let mut cartridge = HuC3::new(banked_rom(4), 0x2000);
+    huc3_command(&mut cartridge, 0x42);
+    huc3_command(&mut cartridge, 0x51);
+    huc3_command(&mut cartridge, 0x39);
+    huc3_command(&mut cartridge, 0x3A);
+    assert_eq!(huc3_read_memory(&mut cartridge, 0x12), 0x9);
+    assert_eq!(huc3_command(&mut cartridge, 0x10), 0x1A);
+    assert_eq!(huc3_command(&mut cartridge, 0x62), 0x61);
+}
+
+#[test]
+fn test_huc3_rtc_time() {
+    let mut cartridge = HuC3::new(banked_rom(4), 0x2000);
+    tick_seconds(&mut cartridge, 61 * 60);
+    huc3_command(&mut cartridge, 0x60);
+    // 61 minutes is 0x03D
+    assert_eq!(huc3_read_memory(&mut cartridge, 0x00), 0xD);
+    assert_eq!(huc3_read_memory(&mut cartridge, 0x01), 0x3);
+    assert_eq!(huc3_read_memory(&mut cartridge, 0x02), 0x0);
+    assert_eq!(huc3_read_memory(&mut cartridge, 0x03), 0x0);
+
+    // Set the time to 23:59 on day 2
+    huc3_command(&mut cartridge, 0x40);
+    huc3_command(&mut cartridge, 0x50);
+    for nibble in [0xF, 0x9, 0x5, 0x2, 0x0, 0x0, 0x0].iter() {
+        huc3_command(&mut cartridge, 0x30 | nibble);
+    }
+    huc3_command(&mut cartridge, 0x61);
+    tick_seconds(&mut cartridge, 60);
+    huc3_command(&mut cartridge, 0x60);
+    assert_eq!(huc3_read_memory(&mut cartridge, 0x00), 0x0);
+    assert_eq!(huc3_read_memory(&mut cartridge, 0x01), 0x0);
+    assert_eq!(huc3_read_memory(&mut cartridge, 0x02), 0x0);
+    assert_eq!(huc3_read_memory(&mut cartridge, 0x03), 0x3);
+}
+
+#[test]
+fn test_huc3_rtc_save() {
+    let mut cartridge = HuC3::new(banked_rom(4), 0x2000);
+    // 23:15 on day 0x134
+    huc3_command(&mut cartridge, 0x40);
+    huc3_command(&mut cartridge, 0x50);
+    for nibble in [0x3, 0x7, 0x5, 0x4, 0x3, 0x1, 0x0].iter() {
+        huc3_command(&mut cartridge, 0x30 | nibble);
+    }
+    huc3_command(&mut cartridge, 0x61);
+    let footer = cartridge.save_rtc(1000);
+    assert_eq!(footer.len(), HUC3_RTC_SIZE_BYTES);
+
+    // Closed for an hour and 30 seconds, into the next day
+    let mut restored = HuC3::new(banked_rom(4), 0x2000);
+    restored.load_rtc(&footer, 1000 + 60 * 60 + 30);
+    huc3_command(&mut restored, 0x60);
+    let time: Vec<u8> = (0..7).map(|address| huc3_read_memory(&mut restored, address)).collect();
+    assert_eq!(time, [0xF, 0x0, 0x0, 0x5, 0x3, 0x1, 0x0]);
+
+    // The 30 seconds carry over towards the next minute
+    tick_seconds(&mut restored, 30);
+    huc3_command(&mut restored, 0x60);
+    assert_eq!(huc3_read_memory(&mut restored, 0x00), 0x0);
+    assert_eq!(huc3_read_memory(&mut restored, 0x01), 0x1);
+}
+
+#[test]
+fn test_huc3_tone() {
+    let mut cartridge = HuC3::new(banked_rom(4), 0x2000);
+    assert_eq!(cartridge.speaker_tone(), None);
+    huc3_command(&mut cartridge, 0x47);
+    huc3_command(&mut cartridge, 0x52);
+    huc3_command(&mut cartridge, 0x35);
+    huc3_command(&mut cartridge, 0x6E);
+    assert_eq!(cartridge.speaker_tone(), Some(0x5));
+}
+
+#[test]
+fn test_huc3_tone_through_rom() {
+    let mut data = banked_rom(4);
+    data[0x0147] = CartridgeKind::HuC3 as u8;
+    data[0x0149] = 0x02;
+    let mut cartridge = Rom::new(data).unwrap().into_cartridge().unwrap();
+    assert_eq!(cartridge.speaker_tone(), None);
+    huc3_command(cartridge.as_mut(), 0x47);
+    huc3_command(cartridge.as_mut(), 0x52);
+    huc3_command(cartridge.as_mut(), 0x33);
+    huc3_command(cartridge.as_mut(), 0x6E);
+    assert_eq!(cartridge.speaker_tone(), Some(0x3));
+}
+
+#[test]
+fn test_huc3_infrared() {
+    let mut cartridge = HuC3::new(banked_rom(4), 0x2000);
+    let (mut remote, port) = InfraredPort::pair();
+    cartridge.connect_infrared(Box::new(port));
+    cartridge.write_byte(0x0000, 0x0E);
+    assert_eq!(cartridge.read_byte(0xA000), 0xC0);
+    remote.set_led(true);
+    assert_eq!(cartridge.read_byte(0xA000), 0xC1);
+    cartridge.write_byte(0xA000, 0x01);
+    assert!(remote.receiving());
+}
+
+#[test]
+fn test_rom_into_hudson_cartridges() {
+    let mut data = banked_rom(4);
+    data[0x0147] = CartridgeKind::HuC1RamBattery as u8;
+    data[0x0149] = 0x02;
+    assert!(Rom::new(data.clone()).unwrap().into_cartridge().is_some());
+    data[0x0147] = CartridgeKind::HuC3 as u8;
+    let mut cartridge = Rom::new(data).unwrap().into_cartridge().unwrap();
+    cartridge.write_byte(0x0000, 0x0A);
+    cartridge.write_byte(0xA000, 0x12);
+    assert_eq!(cartridge.read_byte(0xA000), 0x12);
+}
+
+#[test]
+fn test_mmm01_menu_mode() {
+    let mut cartridge = Mmm01::new(banked_rom(32), 0x8000, false);
+    assert!(!cartridge.mapped());
+    assert_eq!(cartridge.read_byte(0x0000), 30);
+    assert_eq!(cartridge.read_byte(0x4000), 31);
+
+    // Registers don't affect the menu's mapping
+    cartridge.write_byte(0x2000, 0x05);
+    assert_eq!(cartridge.read_byte(0x4000), 31);
+}
+
+#[test]
+fn test_mmm01_game_mode() {
+    let mut cartridge = Mmm01::new(banked_rom(32), 0x8000, false);
+    // Select the 8 bank game starting at bank 8, protecting ROM bank bits 3-4
+    cartridge.write_byte(0x2000, 0x08);
+    cartridge.write_byte(0x6000, 0x0C << 2);
+    cartridge.write_byte(0x0000, 0x40);
+    assert!(cartridge.mapped());
+    assert_eq!(cartridge.read_byte(0x0000), 8);
+    assert_eq!(cartridge.read_byte(0x4000), 9);
+
+    cartridge.write_byte(0x2000, 0x03);
+    assert_eq!(cartridge.read_byte(0x4000), 11);
+    cartridge.write_byte(0x2000, 0x1F);
+    assert_eq!(cartridge.read_byte(0x4000), 15);
+    cartridge.write_byte(0x2000, 0x00);
+    assert_eq!(cartridge.read_byte(0x4000), 9);
+    assert_eq!(cartridge.read_byte(0x0000), 8);
+
+    // The mask and map bits are locked once mapped
+    cartridge.write_byte(0x6000, 0x00);
+    cartridge.write_byte(0x0000, 0x00);
+    cartridge.write_byte(0x2000, 0x10);
+    assert!(cartridge.mapped());
+    assert_eq!(cartridge.read_byte(0x4000), 9);
+}
+
+#[test]
+fn test_mmm01_ram_banks() {
+    let mut cartridge = Mmm01::new(banked_rom(32), 0x8000, false);
+    // Give the game RAM bank 2 only by protecting both RAM bank bits
+    cartridge.write_byte(0x4000, 0x02);
+    cartridge.write_byte(0x0000, 0x7A);
+    cartridge.write_byte(0xA000, 0x22);
+    cartridge.write_byte(0x6000, 0x01);
+    cartridge.write_byte(0x4000, 0x00);
+    assert_eq!(cartridge.read_byte(0xA000), 0x22);
+
+    let mut other = Mmm01::new(banked_rom(32), 0x8000, false);
+    other.write_byte(0x0000, 0x4A);
+    other.write_byte(0xA000, 0x11);
+    other.write_byte(0x6000, 0x01);
+    other.write_byte(0x4000, 0x01);
+    assert_eq!(other.read_byte(0xA000), 0x00);
+    other.write_byte(0x4000, 0x00);
+    assert_eq!(other.read_byte(0xA000), 0x11);
+}
+
+#[test]
+fn test_rom_mmm01_header_detection() {
+    let mut data = banked_rom(32);
+    data[0x0147] = CartridgeKind::Mbc1 as u8;
+    let menu = data.len() - 0x8000;
+    data[menu + 0x0104..menu + 0x0134].copy_from_slice(&NINTENDO_LOGO_BYTES);
+    data[menu + 0x0147] = CartridgeKind::Mmm01Ram as u8;
+    data[menu + 0x0149] = 0x03;
+    let rom = Rom::new(data.clone()).unwrap();
+    assert_eq!(rom.kind(), Some(CartridgeKind::Mmm01Ram));
+    assert_eq!(rom.ram_size_bytes(), 0x8000);
+    assert!(rom.has_valid_logo());
+    let cartridge = rom.into_cartridge().unwrap();
+    assert_eq!(cartridge.read_byte(0x0000), 30);
+
+    // Without a logo the first bank's header is used
+    data[menu + 0x0104] = 0x00;
+    assert_eq!(Rom::new(data).unwrap().kind(), Some(CartridgeKind::Mbc1));
+}
+
+// Creates a camera with an exposure leaving the image unscaled, thresholds
+// of 0x40, 0x80 and 0xC0 for every pixel and the given image on its sensor,
+// with the registers mapped
+fn create_camera(left: u8, right: u8) -> PocketCamera {
+    let mut cartridge = PocketCamera::new(banked_rom(64), 0x20000);
+    let mut image = vec![left; CAMERA_WIDTH * CAMERA_HEIGHT];
+    for row in image.chunks_mut(CAMERA_WIDTH) {
+        for pixel in row[CAMERA_WIDTH / 2..].iter_mut() {
+            *pixel = right;
+        }
+    }
+    cartridge.set_camera_image(&image);
+
+    cartridge.write_byte(0x4000, 0x10);
+    cartridge.write_byte(0xA002, 0x10);
+    cartridge.write_byte(0xA003, 0x00);
+    for pixel in 0..16 {
+        cartridge.write_byte(0xA006 + pixel * 3, 0x40);
+        cartridge.write_byte(0xA007 + pixel * 3, 0x80);
+        cartridge.write_byte(0xA008 + pixel * 3, 0xC0);
+    }
+    cartridge
+}
+
+// Captures an image and maps RAM bank 0 to read it
+fn capture(cartridge: &mut PocketCamera) {
+    cartridge.write_byte(0xA000, 0x01);
+    while cartridge.capturing() {
+        cartridge.tick(0x1000);
+    }
+    cartridge.write_byte(0x4000, 0x00);
+}
+
+// Reads both bit planes of the first row of the tile with the given index
+fn camera_tile_row(cartridge: &PocketCamera, tile: u16) -> (u8, u8) {
+    let address = 0xA100 + tile * 16;
+    (cartridge.read_byte(address), cartridge.read_byte(address + 1))
+}
+
+#[test]
+fn test_camera_banking() {
+    let mut cartridge = PocketCamera::new(banked_rom(64), 0x20000);
+    cartridge.write_byte(0x2000, 0x3F);
+    assert_eq!(cartridge.read_byte(0x4000), 0x3F);
+
+    // RAM can be read without being enabled, but not written
+    cartridge.write_byte(0x4000, 0x0F);
+    cartridge.write_byte(0xA000, 0x12);
+    assert_eq!(cartridge.read_byte(0xA000), 0x00);
+    cartridge.write_byte(0x0000, 0x0A);
+    cartridge.write_byte(0xA000, 0x12);
+    assert_eq!(cartridge.read_byte(0xA000), 0x12);
+
+    // Only the first register reads back
+    cartridge.write_byte(0x4000, 0x10);
+    cartridge.write_byte(0xA001, 0x12);
+    assert_eq!(cartridge.read_byte(0xA001), 0x00);
+    assert_eq!(cartridge.read_byte(0xA000), 0x00);
+    cartridge.write_byte(0x4000, 0x0F);
+    assert_eq!(cartridge.read_byte(0xA000), 0x12);
+}
+
+#[test]
+fn test_camera_capture_timing() {
+    let mut cartridge = create_camera(0x00, 0xFF);
+    cartridge.write_byte(0xA000, 0x01);
+    assert_eq!(cartridge.read_byte(0xA000), 0x01);
+
+    // 129792 cycles, plus 2048 without bit 7 of register 1, plus 64 for
+    // each step of exposure
+    let cycles: u32 = 129_792 + 2048 + 0x1000 * 64;
+    for _ in 0..(cycles / 0x1000) {
+        cartridge.tick(0x1000);
+    }
+    cartridge.tick((cycles % 0x1000) as u16 - 1);
+    assert_eq!(cartridge.read_byte(0xA000), 0x01);
+    cartridge.tick(0x0001);
+    assert_eq!(cartridge.read_byte(0xA000), 0x00);
+}
+
+#[test]
+fn test_camera_capture_dithering() {
+    let mut cartridge = create_camera(0x00, 0xFF);
+    capture(&mut cartridge);
+    assert_eq!(camera_tile_row(&cartridge, 0), (0xFF, 0xFF));
+    assert_eq!(camera_tile_row(&cartridge, 15), (0x00, 0x00));
+    assert_eq!(camera_tile_row(&cartridge, 16 * 13 + 7), (0xFF, 0xFF));
+
+    let mut cartridge = create_camera(0x60, 0xA0);
+    capture(&mut cartridge);
+    assert_eq!(camera_tile_row(&cartridge, 0), (0x00, 0xFF));
+    assert_eq!(camera_tile_row(&cartridge, 8), (0xFF, 0x00));
+}
+
+#[test]
+fn test_camera_exposure_and_invert() {
+    // Half exposure darkens 0xA0 to 0x50
+    let mut cartridge = create_camera(0x60, 0xA0);
+    cartridge.write_byte(0xA002, 0x08);
+    capture(&mut cartridge);
+    assert_eq!(camera_tile_row(&cartridge, 8), (0x00, 0xFF));
+
+    let mut cartridge = create_camera(0x00, 0xFF);
+    cartridge.write_byte(0xA004, 0x08);
+    capture(&mut cartridge);
+    assert_eq!(camera_tile_row(&cartridge, 0), (0x00, 0x00));
+    assert_eq!(camera_tile_row(&cartridge, 15), (0xFF, 0xFF));
+}
+
+#[test]
+fn test_camera_gain_and_offset() {
+    let mut cartridge = create_camera(0x30, 0x30);
+    capture(&mut cartridge);
+    assert_eq!(camera_tile_row(&cartridge, 0), (0xFF, 0xFF));
+
+    // 6 dB of gain doubles 0x30 to 0x5F
+    let mut cartridge = create_camera(0x30, 0x30);
+    cartridge.write_byte(0xA001, 0x04);
+    capture(&mut cartridge);
+    assert_eq!(camera_tile_row(&cartridge, 0), (0x00, 0xFF));
+
+    let mut cartridge = create_camera(0x30, 0x30);
+    cartridge.write_byte(0xA001, 0x0A);
+    capture(&mut cartridge);
+    assert_eq!(camera_tile_row(&cartridge, 0), (0x00, 0x00));
+
+    // A positive offset of 16 steps brightens 0x30 to 0x70, and a negative
+    // one darkens 0x90 to 0x50
+    let mut cartridge = create_camera(0x30, 0x90);
+    cartridge.write_byte(0xA005, 0x30);
+    capture(&mut cartridge);
+    assert_eq!(camera_tile_row(&cartridge, 0), (0x00, 0xFF));
+    assert_eq!(camera_tile_row(&cartridge, 8), (0x00, 0x00));
+
+    let mut cartridge = create_camera(0x30, 0x90);
+    cartridge.write_byte(0xA005, 0x10);
+    capture(&mut cartridge);
+    assert_eq!(camera_tile_row(&cartridge, 8), (0x00, 0xFF));
+}
+
+#[test]
+fn test_camera_edge_enhancement() {
+    let mut cartridge = create_camera(0x60, 0xA0);
+    cartridge.write_byte(0xA004, 0xF0);
+    capture(&mut cartridge);
+    // Pixels either side of the edge are pushed apart, to black and white
+    assert_eq!(camera_tile_row(&cartridge, 7), (0x01, 0xFF));
+    assert_eq!(camera_tile_row(&cartridge, 8), (0x7F, 0x00));
+}
+
+#[test]
+fn test_rom_into_camera_cartridge() {
+    let mut data = banked_rom(64);
+    data[0x0147] = CartridgeKind::PocketCamera as u8;
+    data[0x0149] = 0x04;
+    let mut cartridge = Rom::new(data).unwrap().into_cartridge().unwrap();
+    cartridge.set_camera_image(&[0xFF; CAMERA_WIDTH * CAMERA_HEIGHT]);
+    cartridge.write_byte(0x4000, 0x10);
+    cartridge.write_byte(0xA000, 0x01);
+    assert_eq!(cartridge.read_byte(0xA000), 0x01);
+}
+
+// Writes a nibble to a TAMA5 register
+fn tama5_write(cartridge: &mut dyn Cartridge, register: u8, value: u8) {
+    cartridge.write_byte(0xA001, register);
+    cartridge.write_byte(0xA000, value);
+}
+
+// Runs a TAMA5 command on an address and returns the value it read
+fn tama5_command(cartridge: &mut dyn Cartridge, command: u8, address: u8, value: u8) -> u8 {
+    tama5_write(cartridge, 0x4, value & 0x0F);
+    tama5_write(cartridge, 0x5, value >> 4);
+    tama5_write(cartridge, 0x6, command << 1 | address >> 4);
+    tama5_write(cartridge, 0x7, address & 0x0F);
+    cartridge.write_byte(0xA001, 0x0C);
+    let low = cartridge.read_byte(0xA000) & 0x0F;
+    cartridge.write_byte(0xA001, 0x0D);
+    let high = cartridge.read_byte(0xA000) & 0x0F;
+    high << 4 | low
+}
+
+#[test]
+fn test_tama5_rom_banking() {
+    let mut cartridge = Tama5::new(banked_rom(32));
+    cartridge.write_byte(0xA001, 0x0A);
+    assert_eq!(cartridge.read_byte(0xA000), 0xF1);
+    tama5_write(&mut cartridge, 0x0, 0x03);
+    tama5_write(&mut cartridge, 0x1, 0x01);
+    assert_eq!(cartridge.read_byte(0x4000), 0x13);
+    assert_eq!(cartridge.read_byte(0x0000), 0x00);
+}
+
+#[test]
+fn test_tama5_ram() {
+    let mut cartridge = Tama5::new(banked_rom(2));
+    tama5_command(&mut cartridge, 0x0, 0x1F, 0xA5);
+    assert_eq!(tama5_command(&mut cartridge, 0x1, 0x1F, 0x00), 0xA5);
+    assert_eq!(cartridge.save_ram()[0x1F], 0xA5);
+}
+
+#[test]
+fn test_tama5_save() {
+    let mut cartridge = Tama5::new(banked_rom(2));
+    tama5_command(&mut cartridge, 0x0, 0x03, 0x42);
+    tama5_command(&mut cartridge, 0x2, 0x5, 1);
+    tama5_command(&mut cartridge, 0x2, 0x13, 3);
+    let ram = cartridge.save_ram();
+    let footer = cartridge.save_rtc(1000);
+    assert_eq!(ram.len(), TAMA5_RAM_SIZE_BYTES);
+    assert_eq!(footer.len(), TAMA5_RTC_SIZE_BYTES);
+
+    let mut restored = Tama5::new(banked_rom(2));
+    restored.load_save_ram(&ram);
+    restored.load_rtc(&footer, 1000);
+    assert_eq!(tama5_command(&mut restored, 0x1, 0x03, 0x00), 0x42);
+    assert_eq!(tama5_command(&mut restored, 0x3, 0x5, 0), 1);
+    assert_eq!(tama5_command(&mut restored, 0x3, 0x13, 0), 3);
+}
+
+#[test]
+fn test_tama5_clock_advances_while_closed() {
+    let mut cartridge = Tama5::new(banked_rom(2));
+    // 10:00:00 on the 31st of December, with the alarm at 10:05
+    for &(register, value) in &[(0x4, 0), (0x5, 1), (0x7, 1), (0x8, 3), (0x9, 2), (0xA, 1), (0x14, 5), (0x15, 1)] {
+        tama5_command(&mut cartridge, 0x2, register, value);
+    }
+    let footer = cartridge.save_rtc(1000);
+
+    // Closed for 4 minutes, so the alarm has not gone off
+    let mut restored = Tama5::new(banked_rom(2));
+    restored.load_rtc(&footer, 1000 + 4 * 60);
+    assert_eq!(tama5_command(&mut restored, 0x3, 0x2, 0), 4);
+    assert_eq!(tama5_command(&mut restored, 0x3, 0x1D, 0), 0);
+
+    // Closed for a day and 6 minutes, into the new year, passing the alarm
+    let mut restored = Tama5::new(banked_rom(2));
+    restored.load_rtc(&footer, 1000 + 24 * 60 * 60 + 6 * 60);
+    let date: Vec<u8> = (0x2..=0xB).map(|register| tama5_command(&mut restored, 0x3, register, 0)).collect();
+    assert_eq!(date, [6, 0, 0, 1, 1, 1, 0, 1, 0, 1]);
+    assert_eq!(tama5_command(&mut restored, 0x3, 0x1D, 0), 1);
+}
+
+#[test]
+fn test_tama5_rtc() {
+    let mut cartridge = Tama5::new(banked_rom(2));
+    // 23:59:58 on the 28th of February in a leap year
+    for &(register, value) in &[(0x0, 8), (0x1, 5), (0x2, 9), (0x3, 5), (0x4, 3), (0x5, 2), (0x7, 8), (0x8, 2), (0x9, 2)] {
+        tama5_command(&mut cartridge, 0x2, register, value);
+    }
+    tick_seconds(&mut cartridge, 2);
+    let time: Vec<u8> = (0x0..=0xA).map(|register| tama5_command(&mut cartridge, 0x3, register, 0)).collect();
+    assert_eq!(time, [0, 0, 0, 0, 0, 0, 1, 9, 2, 2, 0]);
+    tick_seconds(&mut cartridge, 24 * 60 * 60);
+    assert_eq!(tama5_command(&mut cartridge, 0x3, 0x7, 0), 1);
+    assert_eq!(tama5_command(&mut cartridge, 0x3, 0x9, 0), 3);
+}
+
+#[test]
+fn test_tama5_alarm() {
+    let mut cartridge = Tama5::new(banked_rom(2));
+    // The time is 00:01 and the alarm is set for 00:02
+    tama5_command(&mut cartridge, 0x2, 0x2, 1);
+    tama5_command(&mut cartridge, 0x2, 0x12, 2);
+    assert_eq!(tama5_command(&mut cartridge, 0x3, 0x1D, 0), 0);
+    tick_seconds(&mut cartridge, 59);
+    assert_eq!(tama5_command(&mut cartridge, 0x3, 0x1D, 0), 0);
+    tick_seconds(&mut cartridge, 1);
+    assert_eq!(tama5_command(&mut cartridge, 0x3, 0x1D, 0), 1);
+    tama5_command(&mut cartridge, 0x2, 0x1D, 0);
+    assert_eq!(tama5_command(&mut cartridge, 0x3, 0x1D, 0), 0);
+}
+
+#[test]
+fn test_rom_into_tama5_cartridge() {
+    let mut data = banked_rom(4);
+    data[0x0147] = CartridgeKind::BandaiTama5 as u8;
+    let mut cartridge = Rom::new(data).unwrap().into_cartridge().unwrap();
+    tama5_write(cartridge.as_mut(), 0x0, 0x02);
+    assert_eq!(cartridge.read_byte(0x4000), 0x02);
+}
+
+#[test]
+fn test_rom_ram() {
+    let mut cartridge = RomRam::new(banked_rom(2), 0x2000, true);
+    assert_eq!(cartridge.read_byte(0x4000), 0x01);
+    cartridge.write_byte(0xA000, 0x12);
+    cartridge.write_byte(0xBFFF, 0x34);
+    assert_eq!(cartridge.read_byte(0xA000), 0x12);
+    assert_eq!(cartridge.read_byte(0xBFFF), 0x34);
+    assert!(cartridge.battery());
+
+    let mut cartridge = RomRam::new(banked_rom(2), 0, false);
+    cartridge.write_byte(0xA000, 0x12);
+    assert_eq!(cartridge.read_byte(0xA000), 0xFF);
+}
+
+#[test]
+fn test_rom_into_rom_ram_cartridge() {
+    let mut data = banked_rom(2);
+    data[0x0147] = CartridgeKind::RomRamBattery as u8;
+    data[0x0149] = 0x02;
+    let mut cartridge = Rom::new(data).unwrap().into_cartridge().unwrap();
+    cartridge.write_byte(0xA000, 0x56);
+    assert_eq!(cartridge.save_ram().len(), 0x2000);
+    assert_eq!(cartridge.save_ram()[0], 0x56);
+    assert!(cartridge.battery());
+}
+
+#[test]
+fn test_battery_from_cartridge_kind() {
+    for (kind, battery) in [
+        (CartridgeKind::RomOnly, false),
+        (CartridgeKind::Mbc1Ram, false),
+        (CartridgeKind::Mbc1RamBattery, true),
+        (CartridgeKind::Mbc2Battery, true),
+        (CartridgeKind::Mbc3TimerBattery, true),
+        (CartridgeKind::Mbc5RumbleRam, false),
+        (CartridgeKind::Mbc7SensorRumbleRamBattery, true)
+    ] {
+        let mut data = banked_rom(4);
+        data[0x0147] = kind as u8;
+        data[0x0149] = 0x02;
+        let cartridge = Rom::new(data).unwrap().into_cartridge().unwrap();
+        assert_eq!(cartridge.battery(), battery);
+    }
+}
+
+#[test]
+fn test_save_ram_round_trip() {
+    let mut cartridge = Mbc5::new(banked_rom(4), 0x8000, false, true);
+    cartridge.write_byte(0x0000, 0x0A);
+    cartridge.write_byte(0x4000, 0x03);
+    cartridge.write_byte(0xA123, 0x77);
+
+    let mut restored = Mbc5::new(banked_rom(4), 0x8000, false, true);
+    restored.load_save_ram(&cartridge.save_ram());
+    restored.write_byte(0x0000, 0x0A);
+    restored.write_byte(0x4000, 0x03);
+    assert_eq!(restored.read_byte(0xA123), 0x77);
+
+    // Short saves only restore the start of RAM
+    restored.load_save_ram(&[0x11]);
+    restored.write_byte(0x4000, 0x00);
+    assert_eq!(restored.read_byte(0xA000), 0x11);
+    restored.write_byte(0x4000, 0x03);
+    assert_eq!(restored.read_byte(0xA123), 0x77);
+}
+
+#[test]
+fn test_mbc2_save_ram() {
+    let mut cartridge = Mbc2::new(banked_rom(2), true);
+    cartridge.load_save_ram(&[0xAB; MBC2_RAM_SIZE]);
+    cartridge.write_byte(0x0000, 0x0A);
+    assert_eq!(cartridge.read_byte(0xA000), 0xFB);
+    assert_eq!(cartridge.save_ram(), vec![0x0B; MBC2_RAM_SIZE]);
+}
+
+#[test]
+fn test_mbc6_save_ram_includes_flash() {
+    let mut cartridge = Mbc6::new(mbc6_rom());
+    cartridge.load_flash(&[0x5A]);
+    let save = cartridge.save_ram();
+    assert_eq!(save.len(), MBC6_RAM_SIZE_BYTES + MBC6_FLASH_SIZE_BYTES);
+    assert_eq!(save[MBC6_RAM_SIZE_BYTES], 0x5A);
+
+    let mut restored = Mbc6::new(mbc6_rom());
+    restored.load_save_ram(&save);
+    assert_eq!(restored.flash()[0], 0x5A);
+}
+
+#[test]
+fn test_mbc7_save_ram_is_eeprom() {
+    let mut cartridge = Mbc7::new(banked_rom(2));
+    cartridge.load_save_ram(&[0x12, 0x34]);
+    assert_eq!(&cartridge.eeprom()[..2], &[0x12, 0x34]);
+    assert_eq!(cartridge.save_ram().len(), MBC7_EEPROM_SIZE_BYTES);
+}
+
+#[test]
+fn test_rtc_footer_legacy_timestamp() {
+    let mut data = vec![0; RTC_FOOTER_LEGACY_SIZE_BYTES];
+    data[0] = 12;
+    data[20] = 34;
+    data[40..44].copy_from_slice(&0x5F00_0000u32.to_le_bytes());
+    let footer = RtcFooter::from_bytes(&data).unwrap();
+    assert_eq!(footer.live[0], 12);
+    assert_eq!(footer.latched[0], 34);
+    assert_eq!(footer.timestamp, 0x5F00_0000);
+    assert_eq!(RtcFooter::from_bytes(&data[..40]), None);
+}
+
+#[test]
+fn test_mbc3_rtc_footer() {
+    let mut cartridge = Mbc3::new(banked_rom(4), 0x2000, true, true);
+    cartridge.write_byte(0x0000, 0x0A);
+    cartridge.write_byte(0x4000, RTC_HOURS);
+    cartridge.write_byte(0xA000, 5);
+    read_rtc(&mut cartridge, RTC_SECONDS);
+    tick_seconds(&mut cartridge, 3);
+
+    let footer = RtcFooter::from_bytes(&cartridge.save_rtc(1000)).unwrap();
+    assert_eq!(footer.live, [3, 0, 5, 0, 0]);
+    assert_eq!(footer.latched, [0, 0, 5, 0, 0]);
+    assert_eq!(footer.timestamp, 1000);
+    assert!(Mbc3::new(banked_rom(4), 0x2000, false, true).save_rtc(1000).is_empty());
+}
+
+#[test]
+fn test_mbc3_rtc_footer_advances_clock() {
+    let mut cartridge = Mbc3::new(banked_rom(4), 0, true, true);
+    // 23:59:30 on day 511, then closed for 2 days and 45 seconds
+    let footer = RtcFooter { live: [30, 59, 23, 0xFF, 0x01], latched: [0; 5], timestamp: 1000 };
+    cartridge.load_rtc(&footer.to_bytes(), 1000 + 2 * 24 * 60 * 60 + 45);
+    cartridge.write_byte(0x0000, 0x0A);
+    assert_eq!(read_rtc(&mut cartridge, RTC_SECONDS), 15);
+    assert_eq!(read_rtc(&mut cartridge, RTC_MINUTES), 0);
+    assert_eq!(read_rtc(&mut cartridge, RTC_HOURS), 0);
+    assert_eq!(read_rtc(&mut cartridge, RTC_DAYS_LOW), 2);
+    assert_eq!(read_rtc(&mut cartridge, RTC_DAYS_HIGH), 0x80);
+}
+
+#[test]
+fn test_mbc3_rtc_footer_halted_clock() {
+    let mut cartridge = Mbc3::new(banked_rom(4), 0, true, true);
+    let footer = RtcFooter { live: [10, 0, 0, 0, 0x40], latched: [0; 5], timestamp: 1000 };
+    cartridge.load_rtc(&footer.to_bytes(), 5000);
+    cartridge.write_byte(0x0000, 0x0A);
+    assert_eq!(read_rtc(&mut cartridge, RTC_SECONDS), 10);
+}
@@ -1,2 +1,31 @@
 extern crate gameboy;
 use gameboy::cpu::*;
+use gameboy::operations::Reg16;
+
+#[test]
+fn test_initial_state() {
+    let cpu = Cpu::new();
+
+    // State after the DMG boot ROM hands control to the cartridge
+    assert_eq!(cpu.pc, 0x0100);
+    assert_eq!(cpu.sp, 0xFFFE);
+    assert_eq!(cpu.r.a, 0x01);
+    assert_eq!(cpu.r.f(), 0xB0);
+    assert!(!cpu.ime);
+}
+
+#[test]
+fn test_reg16() {
+    let mut cpu = Cpu::new();
+
+    cpu.set_reg16(Reg16::SP, 0xC000);
+    assert_eq!(cpu.sp, 0xC000);
+    assert_eq!(cpu.reg16(Reg16::SP), 0xC000);
+
+    cpu.set_reg16(Reg16::BC, 0x1234);
+    assert_eq!(cpu.r.bc(), 0x1234);
+    assert_eq!(cpu.reg16(Reg16::BC), 0x1234);
+
+    cpu.set_reg16(Reg16::AF, 0xABCD);
+    assert_eq!(cpu.reg16(Reg16::AF), 0xABC0);
+}
@@ -0,0 +1,195 @@
+extern crate gameboy;
+use gameboy::{Configuration, GameboyType};
+use gameboy::cartridge::{Cartridge, RomOnly};
+use gameboy::dma::*;
+use gameboy::memory::Memory;
+use gameboy::ppu::{LCDC_ADDRESS, CYCLES_PER_LINE};
+
+// Creates CGB memory with the LCD switched off and recognisable data at the
+// start of WRAM
+fn new_cgb_memory() -> Memory {
+    let cartridge: Box<dyn Cartridge> = Box::new(RomOnly::new(vec![0x12; 0x10000]));
+    let configuration = Configuration {
+        gameboy_type: GameboyType::ColorGameboy,
+        ..Configuration::default()
+    };
+    let mut memory = Memory::with_configuration(cartridge, &configuration);
+    memory.write_byte(LCDC_ADDRESS, 0x00);
+    for offset in 0..0x100 {
+        memory.write_byte(0xC000 + offset, offset as u8);
+    }
+    memory
+}
+
+// Points the transfer at 0xC000 -> 0x8100
+fn set_addresses(memory: &mut Memory) {
+    memory.write_byte(HDMA1_ADDRESS, 0xC0);
+    memory.write_byte(HDMA2_ADDRESS, 0x0F);
+    memory.write_byte(HDMA3_ADDRESS, 0xE1);
+    memory.write_byte(HDMA4_ADDRESS, 0x0F);
+}
+
+#[test]
+fn test_general_purpose_dma() {
+    let mut memory = new_cgb_memory();
+    set_addresses(&mut memory);
+    memory.write_byte(HDMA5_ADDRESS, 0x02);
+
+    for offset in 0..0x30 {
+        assert_eq!(memory.read_byte(0x8100 + offset), offset as u8);
+    }
+    assert_eq!(memory.read_byte(0x8130), 0x00);
+    assert_eq!(memory.read_byte(HDMA5_ADDRESS), 0xFF);
+    assert_eq!(memory.take_stall_cycles(), 3 * 32);
+    assert_eq!(memory.take_stall_cycles(), 0);
+}
+
+#[test]
+fn test_hdma_registers_unreadable() {
+    let mut memory = new_cgb_memory();
+    set_addresses(&mut memory);
+    for address in HDMA1_ADDRESS..=HDMA4_ADDRESS {
+        assert_eq!(memory.read_byte(address), 0xFF);
+    }
+    assert_eq!(memory.read_byte(HDMA5_ADDRESS), 0xFF);
+}
+
+#[test]
+fn test_hblank_dma() {
+    let mut memory = new_cgb_memory();
+    memory.write_byte(LCDC_ADDRESS, 0x91);
+    set_addresses(&mut memory);
+    memory.write_byte(HDMA5_ADDRESS, 0x81);
+    assert_eq!(memory.read_byte(HDMA5_ADDRESS), 0x01);
+    assert_eq!(memory.read_byte(0x8100), 0x00);
+
+    // First H-Blank copies one block
+    memory.tick(CYCLES_PER_LINE);
+    assert_eq!(memory.read_byte(HDMA5_ADDRESS), 0x00);
+    for offset in 0..0x10 {
+        assert_eq!(memory.read_byte(0x8100 + offset), offset as u8);
+    }
+    assert_eq!(memory.read_byte(0x8110), 0x00);
+    assert_eq!(memory.take_stall_cycles(), 32);
+
+    // Second H-Blank copies the last block and ends the transfer
+    memory.tick(CYCLES_PER_LINE);
+    assert_eq!(memory.read_byte(HDMA5_ADDRESS), 0xFF);
+    for offset in 0x10..0x20 {
+        assert_eq!(memory.read_byte(0x8100 + offset), offset as u8);
+    }
+
+    memory.tick(CYCLES_PER_LINE);
+    assert_eq!(memory.read_byte(0x8120), 0x00);
+}
+
+#[test]
+fn test_hblank_dma_cancel() {
+    let mut memory = new_cgb_memory();
+    memory.write_byte(LCDC_ADDRESS, 0x91);
+    set_addresses(&mut memory);
+    memory.write_byte(HDMA5_ADDRESS, 0x83);
+    memory.tick(CYCLES_PER_LINE);
+
+    memory.write_byte(HDMA5_ADDRESS, 0x00);
+    assert_eq!(memory.read_byte(HDMA5_ADDRESS), 0x82);
+
+    memory.tick(CYCLES_PER_LINE);
+    assert_eq!(memory.read_byte(0x810F), 0x0F);
+    assert_eq!(memory.read_byte(0x8110), 0x00);
+}
+
+#[test]
+fn test_hblank_dma_lcd_off() {
+    let mut memory = new_cgb_memory();
+    set_addresses(&mut memory);
+    memory.write_byte(HDMA5_ADDRESS, 0x81);
+
+    // A block is copied straight away with the LCD off
+    assert_eq!(memory.read_byte(0x810F), 0x0F);
+    assert_eq!(memory.read_byte(0x8110), 0x00);
+    assert_eq!(memory.read_byte(HDMA5_ADDRESS), 0x00);
+}
+
+#[test]
+fn test_hdma_ignored_on_dmg() {
+    let cartridge: Box<dyn Cartridge> = Box::new(RomOnly::new(vec![0x12; 0x10000]));
+    let mut memory = Memory::new(cartridge);
+    memory.write_byte(LCDC_ADDRESS, 0x00);
+    memory.write_byte(0xC000, 0xAB);
+    set_addresses(&mut memory);
+    memory.write_byte(HDMA5_ADDRESS, 0x00);
+
+    assert_eq!(memory.read_byte(0x8100), 0x00);
+    assert_eq!(memory.read_byte(HDMA5_ADDRESS), 0xFF);
+    assert_eq!(memory.take_stall_cycles(), 0);
+}
+
+// Creates DMG memory with recognisable data at 0xC000 - 0xC1FF
+fn new_oam_dma_memory() -> Memory {
+    let cartridge: Box<dyn Cartridge> = Box::new(RomOnly::new(vec![0x12; 0x10000]));
+    let mut memory = Memory::new(cartridge);
+    for offset in 0..0x200 {
+        memory.write_byte(0xC000 + offset, (offset as u8).wrapping_add((offset >> 8) as u8));
+    }
+    memory
+}
+
+#[test]
+fn test_oam_dma() {
+    let mut memory = new_oam_dma_memory();
+    memory.write_byte(DMA_ADDRESS, 0xC0);
+    assert_eq!(memory.read_byte(DMA_ADDRESS), 0xC0);
+
+    memory.tick(OAM_DMA_CYCLES_PER_BYTE * (OAM_DMA_LENGTH_BYTES - 1));
+    assert!(memory.oam_dma.active());
+    assert_eq!(memory.ppu.oam[0x9E], 0x9E);
+    assert_eq!(memory.ppu.oam[0x9F], 0x00);
+
+    memory.tick(OAM_DMA_CYCLES_PER_BYTE);
+    assert!(!memory.oam_dma.active());
+    for (index, value) in memory.ppu.oam.iter().enumerate() {
+        assert_eq!(*value, index as u8);
+    }
+}
+
+#[test]
+fn test_oam_dma_bus_conflicts() {
+    let mut memory = new_oam_dma_memory();
+    memory.write_byte(0xFF80, 0xAB);
+    memory.write_byte(DMA_ADDRESS, 0xC0);
+
+    // Only HRAM and the I/O registers are reachable during the transfer
+    assert_eq!(memory.read_byte(0x0000), 0xFF);
+    assert_eq!(memory.read_byte(0xC001), 0xFF);
+    assert_eq!(memory.read_word(0x0000), 0xFFFF);
+    assert_eq!(memory.read_byte(0xFF80), 0xAB);
+    assert_eq!(memory.read_byte(LCDC_ADDRESS), 0x91);
+    memory.write_byte(0xC001, 0x55);
+    memory.write_byte(0xFF81, 0xCD);
+    assert_eq!(memory.read_byte(0xFF81), 0xCD);
+
+    memory.tick(OAM_DMA_CYCLES_PER_BYTE * OAM_DMA_LENGTH_BYTES);
+    assert_eq!(memory.read_byte(0x0000), 0x12);
+    assert_eq!(memory.read_byte(0xC001), 0x01);
+}
+
+#[test]
+fn test_oam_dma_restart() {
+    let mut memory = new_oam_dma_memory();
+    memory.write_byte(DMA_ADDRESS, 0xC0);
+    memory.tick(OAM_DMA_CYCLES_PER_BYTE * 0x50);
+    assert_eq!(memory.ppu.oam[0x4F], 0x4F);
+
+    // Writing DMA again starts over from the new source
+    memory.write_byte(DMA_ADDRESS, 0xC1);
+    memory.tick(OAM_DMA_CYCLES_PER_BYTE * 0x50);
+    assert!(memory.oam_dma.active());
+    assert_eq!(memory.ppu.oam[0x00], 0x01);
+    assert_eq!(memory.ppu.oam[0x4F], 0x50);
+    assert_eq!(memory.ppu.oam[0x50], 0x00);
+
+    memory.tick(OAM_DMA_CYCLES_PER_BYTE * 0x50);
+    assert!(!memory.oam_dma.active());
+    assert_eq!(memory.ppu.oam[0x9F], 0xA0);
+}
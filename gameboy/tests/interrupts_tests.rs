@@ -0,0 +1,51 @@
+extern crate gameboy;
+use gameboy::interrupts::*;
+
+#[test]
+fn test_vectors() {
+    assert_eq!(Interrupt::VBlank.vector(), 0x0040);
+    assert_eq!(Interrupt::LcdStat.vector(), 0x0048);
+    assert_eq!(Interrupt::Timer.vector(), 0x0050);
+    assert_eq!(Interrupt::Serial.vector(), 0x0058);
+    assert_eq!(Interrupt::Joypad.vector(), 0x0060);
+}
+
+#[test]
+fn test_priority() {
+    assert_eq!(Interrupt::highest_priority(0x1F), Some(Interrupt::VBlank));
+    assert_eq!(Interrupt::highest_priority(0x1E), Some(Interrupt::LcdStat));
+    assert_eq!(Interrupt::highest_priority(0x1C), Some(Interrupt::Timer));
+    assert_eq!(Interrupt::highest_priority(0x18), Some(Interrupt::Serial));
+    assert_eq!(Interrupt::highest_priority(0x10), Some(Interrupt::Joypad));
+    assert_eq!(Interrupt::highest_priority(0xE0), None);
+}
+
+#[test]
+fn test_request_acknowledge() {
+    let mut interrupts = Interrupts::new();
+    interrupts.write_enable(0x05);
+
+    interrupts.request(Interrupt::Timer);
+    interrupts.request(Interrupt::LcdStat);
+    assert_eq!(interrupts.read_flags(), 0xE6);
+    assert_eq!(interrupts.pending(), 0x04);
+    assert_eq!(interrupts.next_pending(), Some(Interrupt::Timer));
+
+    interrupts.acknowledge(Interrupt::Timer);
+    assert_eq!(interrupts.read_flags(), 0xE2);
+    assert_eq!(interrupts.next_pending(), None);
+}
+
+#[test]
+fn test_register_readwrite() {
+    let mut interrupts = Interrupts::new();
+
+    interrupts.write_flags(0xFF);
+    assert_eq!(interrupts.read_flags(), 0xFF);
+    interrupts.write_flags(0x00);
+    assert_eq!(interrupts.read_flags(), 0xE0);
+
+    interrupts.write_enable(0xFF);
+    assert_eq!(interrupts.read_enable(), 0xFF);
+    assert_eq!(interrupts.pending(), 0x00);
+}
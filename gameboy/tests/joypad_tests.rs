@@ -0,0 +1,54 @@
+extern crate gameboy;
+use gameboy::cartridge::{Cartridge, RomOnly};
+use gameboy::joypad::*;
+use gameboy::memory::Memory;
+
+#[test]
+fn test_unselected() {
+    let mut joypad = Joypad::new();
+    assert_eq!(joypad.read(), 0xFF);
+
+    joypad.press(Button::A);
+    joypad.press(Button::Up);
+    assert_eq!(joypad.read(), 0xFF);
+    assert!(!joypad.any_line_low());
+}
+
+#[test]
+fn test_select_groups() {
+    let mut joypad = Joypad::new();
+    joypad.press(Button::Up);
+    joypad.press(Button::B);
+
+    joypad.write(0x20);
+    assert_eq!(joypad.read(), 0xEB);
+    joypad.write(0x10);
+    assert_eq!(joypad.read(), 0xDD);
+    joypad.write(0x00);
+    assert_eq!(joypad.read(), 0xC9);
+
+    joypad.release(Button::Up);
+    assert_eq!(joypad.read(), 0xCD);
+}
+
+#[test]
+fn test_press_line_transition() {
+    let mut joypad = Joypad::new();
+    joypad.write(0x10);
+
+    assert!(!joypad.press(Button::Right));
+    assert!(joypad.press(Button::A));
+    // Already low, no new transition
+    assert!(!joypad.press(Button::A));
+}
+
+#[test]
+fn test_memory_interrupt() {
+    let cartridge: Box<dyn Cartridge> = Box::new(RomOnly::new(vec![0; 0x8000]));
+    let mut memory = Memory::new(cartridge);
+
+    memory.write_byte(0xFF00, 0x10);
+    memory.press_button(Button::Start);
+    assert_eq!(memory.read_byte(0xFF00), 0xD7);
+    assert_eq!(memory.read_byte(0xFF0F), 0xF0);
+}
@@ -0,0 +1,541 @@
+extern crate gameboy;
+
+use gameboy::{Configuration, GameboyType};
+use gameboy::interrupts::Interrupt;
+use gameboy::joypad::Button;
+use gameboy::machine::Machine;
+use gameboy::memory::Memory;
+use gameboy::rom::{CartridgeKind, Rom};
+
+// Creates a machine with a RomOnly cartridge containing the given program at
+// the execution start address (0x0100).
+fn create_machine(program: &[u8]) -> Machine {
+    create_machine_with_configuration(program, &Configuration::default())
+}
+
+fn create_machine_with_configuration(program: &[u8], configuration: &Configuration) -> Machine {
+    let mut cartridge_data = vec![0x00; 0x8000];
+    cartridge_data[0x0100..(0x100 + program.len())].copy_from_slice(program);
+    cartridge_data[0x0147] = CartridgeKind::RomOnly as u8;
+    let rom = Rom::new(cartridge_data).unwrap();
+    Machine::new(Memory::with_configuration(rom.into_cartridge().unwrap(), configuration))
+}
+
+// Steps the machine once for every expected cycle count, checking each.
+macro_rules! assert_cycles {
+    ( $machine:expr, [ $( $cycles:expr ),* ] ) => {
+        $(
+            assert_eq!($machine.step().unwrap(), $cycles);
+        )*
+    };
+}
+
+#[test]
+fn test_nop() {
+    let mut machine = create_machine(&[0x00]);
+    assert_cycles!(machine, [4]);
+    assert_eq!(machine.cpu.pc, 0x0101);
+}
+
+#[test]
+fn test_ld_immediate() {
+    // LD B, $12; LD HL, $C000; LD (HL), $34; LD A, (HL)
+    let mut machine = create_machine(&[0x06, 0x12, 0x21, 0x00, 0xC0, 0x36, 0x34, 0x7E]);
+    assert_cycles!(machine, [8, 12, 12, 8]);
+    assert_eq!(machine.cpu.r.b, 0x12);
+    assert_eq!(machine.cpu.r.h, 0xC0);
+    assert_eq!(machine.cpu.r.l, 0x00);
+    assert_eq!(machine.memory.read_byte(0xC000), 0x34);
+    assert_eq!(machine.cpu.r.a, 0x34);
+    assert_eq!(machine.cpu.pc, 0x0108);
+}
+
+#[test]
+fn test_ld_hl_increment_decrement() {
+    // LD HL, $C000; LD (HL+), A; LD (HL-), A; LD A, (HL+)
+    let mut machine = create_machine(&[0x21, 0x00, 0xC0, 0x22, 0x32, 0x2A]);
+    machine.cpu.r.a = 0x42;
+    assert_cycles!(machine, [12, 8, 8, 8]);
+    assert_eq!(machine.memory.read_byte(0xC000), 0x42);
+    assert_eq!(machine.memory.read_byte(0xC001), 0x42);
+    assert_eq!(machine.cpu.r.h, 0xC0);
+    assert_eq!(machine.cpu.r.l, 0x01);
+}
+
+#[test]
+fn test_ldh() {
+    // LDH ($80), A; LD C, $80; LD A, ($FF00 + C)
+    let mut machine = create_machine(&[0xE0, 0x80, 0x0E, 0x80, 0xF2]);
+    machine.cpu.r.a = 0x99;
+    assert_cycles!(machine, [12]);
+    machine.cpu.r.a = 0x00;
+    assert_cycles!(machine, [8, 8]);
+    assert_eq!(machine.memory.read_byte(0xFF80), 0x99);
+    assert_eq!(machine.cpu.r.a, 0x99);
+}
+
+#[test]
+fn test_st_sp() {
+    // LD ($C000), SP
+    let mut machine = create_machine(&[0x08, 0x00, 0xC0]);
+    machine.cpu.sp = 0xBEEF;
+    assert_cycles!(machine, [20]);
+    assert_eq!(machine.memory.read_byte(0xC000), 0xEF);
+    assert_eq!(machine.memory.read_byte(0xC001), 0xBE);
+}
+
+#[test]
+fn test_add_flags() {
+    // ADD A, $0F; ADD A, $F0; ADC A, $00
+    let mut machine = create_machine(&[0xC6, 0x0F, 0xC6, 0xF2, 0xCE, 0x00]);
+    machine.cpu.r.a = 0x01;
+    assert_cycles!(machine, [8]);
+    assert_eq!(machine.cpu.r.a, 0x10);
+    assert_eq!(machine.cpu.r.f(), 0x20);
+    assert_cycles!(machine, [8]);
+    assert_eq!(machine.cpu.r.a, 0x02);
+    assert_eq!(machine.cpu.r.f(), 0x10);
+    assert_cycles!(machine, [8]);
+    assert_eq!(machine.cpu.r.a, 0x03);
+    assert_eq!(machine.cpu.r.f(), 0x00);
+}
+
+#[test]
+fn test_sub_flags() {
+    // SUB B; SBC A, $00; CP $FF
+    let mut machine = create_machine(&[0x90, 0xDE, 0x00, 0xFE, 0xFF]);
+    machine.cpu.r.a = 0x10;
+    machine.cpu.r.b = 0x11;
+    assert_cycles!(machine, [4]);
+    assert_eq!(machine.cpu.r.a, 0xFF);
+    assert_eq!(machine.cpu.r.f(), 0x70);
+    assert_cycles!(machine, [8]);
+    assert_eq!(machine.cpu.r.a, 0xFE);
+    assert_eq!(machine.cpu.r.f(), 0x40);
+    assert_cycles!(machine, [8]);
+    assert_eq!(machine.cpu.r.a, 0xFE);
+    assert_eq!(machine.cpu.r.f(), 0x70);
+}
+
+#[test]
+fn test_logic_flags() {
+    // AND $0F; XOR A; OR $80
+    let mut machine = create_machine(&[0xE6, 0x0F, 0xAF, 0xF6, 0x80]);
+    machine.cpu.r.a = 0xF0;
+    assert_cycles!(machine, [8]);
+    assert_eq!(machine.cpu.r.f(), 0xA0);
+    assert_cycles!(machine, [4]);
+    assert_eq!(machine.cpu.r.f(), 0x80);
+    assert_cycles!(machine, [8]);
+    assert_eq!(machine.cpu.r.a, 0x80);
+    assert_eq!(machine.cpu.r.f(), 0x00);
+}
+
+#[test]
+fn test_inc_dec() {
+    // INC B; DEC C; LD HL, $C000; INC (HL); DEC BC
+    let mut machine = create_machine(&[0x04, 0x0D, 0x21, 0x00, 0xC0, 0x34, 0x0B]);
+    machine.cpu.r.set_f(0x10);
+    machine.cpu.r.b = 0x0F;
+    machine.cpu.r.c = 0x01;
+    assert_cycles!(machine, [4]);
+    assert_eq!(machine.cpu.r.b, 0x10);
+    assert_eq!(machine.cpu.r.f(), 0x30);
+    assert_cycles!(machine, [4]);
+    assert_eq!(machine.cpu.r.c, 0x00);
+    assert_eq!(machine.cpu.r.f(), 0xD0);
+    assert_cycles!(machine, [12, 12, 8]);
+    assert_eq!(machine.memory.read_byte(0xC000), 0x01);
+    assert_eq!(machine.cpu.r.b, 0x0F);
+    assert_eq!(machine.cpu.r.c, 0xFF);
+}
+
+#[test]
+fn test_add16() {
+    // ADD HL, BC
+    let mut machine = create_machine(&[0x09]);
+    machine.cpu.r.set_f(0x80);
+    machine.cpu.r.h = 0x8F;
+    machine.cpu.r.l = 0xFF;
+    machine.cpu.r.b = 0x80;
+    machine.cpu.r.c = 0x01;
+    assert_cycles!(machine, [8]);
+    assert_eq!(machine.cpu.r.h, 0x10);
+    assert_eq!(machine.cpu.r.l, 0x00);
+    assert_eq!(machine.cpu.r.f(), 0xB0);
+}
+
+#[test]
+fn test_add_sp() {
+    // ADD SP, -1; LD HL, SP + 1
+    let mut machine = create_machine(&[0xE8, 0xFF, 0xF8, 0x01]);
+    machine.cpu.sp = 0x0001;
+    assert_cycles!(machine, [16]);
+    assert_eq!(machine.cpu.sp, 0x0000);
+    assert_eq!(machine.cpu.r.f(), 0x30);
+    assert_cycles!(machine, [12]);
+    assert_eq!(machine.cpu.r.h, 0x00);
+    assert_eq!(machine.cpu.r.l, 0x01);
+    assert_eq!(machine.cpu.r.f(), 0x00);
+}
+
+#[test]
+fn test_daa() {
+    // ADD A, $27; DAA; SUB $08; DAA
+    let mut machine = create_machine(&[0xC6, 0x27, 0x27, 0xD6, 0x08, 0x27]);
+    machine.cpu.r.a = 0x15;
+    assert_cycles!(machine, [8, 4]);
+    assert_eq!(machine.cpu.r.a, 0x42);
+    assert_cycles!(machine, [8, 4]);
+    assert_eq!(machine.cpu.r.a, 0x34);
+}
+
+#[test]
+fn test_rotate_accumulator() {
+    // RLCA; RRA; RLA; RRCA
+    let mut machine = create_machine(&[0x07, 0x1F, 0x17, 0x0F]);
+    machine.cpu.r.a = 0x80;
+    assert_cycles!(machine, [4]);
+    assert_eq!(machine.cpu.r.a, 0x01);
+    assert_eq!(machine.cpu.r.f(), 0x10);
+    assert_cycles!(machine, [4]);
+    assert_eq!(machine.cpu.r.a, 0x80);
+    assert_eq!(machine.cpu.r.f(), 0x10);
+    assert_cycles!(machine, [4]);
+    assert_eq!(machine.cpu.r.a, 0x01);
+    assert_eq!(machine.cpu.r.f(), 0x10);
+    assert_cycles!(machine, [4]);
+    assert_eq!(machine.cpu.r.a, 0x80);
+    assert_eq!(machine.cpu.r.f(), 0x10);
+}
+
+#[test]
+fn test_flag_operations() {
+    // SCF; CCF; CPL
+    let mut machine = create_machine(&[0x37, 0x3F, 0x2F]);
+    machine.cpu.r.set_f(0x80);
+    machine.cpu.r.a = 0x0F;
+    assert_cycles!(machine, [4]);
+    assert_eq!(machine.cpu.r.f(), 0x90);
+    assert_cycles!(machine, [4]);
+    assert_eq!(machine.cpu.r.f(), 0x80);
+    assert_cycles!(machine, [4]);
+    assert_eq!(machine.cpu.r.a, 0xF0);
+    assert_eq!(machine.cpu.r.f(), 0xE0);
+}
+
+#[test]
+fn test_jr() {
+    // JR NZ, +2 (not taken); JR Z, +2 (taken); 2 x NOP; JR -2
+    let mut machine = create_machine(&[0x20, 0x02, 0x28, 0x02, 0x00, 0x00, 0x18, 0xFE]);
+    machine.cpu.r.set_f(0x80);
+    assert_cycles!(machine, [8]);
+    assert_eq!(machine.cpu.pc, 0x0102);
+    assert_cycles!(machine, [12]);
+    assert_eq!(machine.cpu.pc, 0x0106);
+    assert_cycles!(machine, [12]);
+    assert_eq!(machine.cpu.pc, 0x0106);
+}
+
+#[test]
+fn test_jp() {
+    // JP C, $0200 (not taken); JP NC, $0107 (taken); JP HL
+    let mut machine = create_machine(&[0xDA, 0x00, 0x02, 0xD2, 0x07, 0x01, 0x00, 0xE9]);
+    machine.cpu.r.set_f(0x00);
+    machine.cpu.r.h = 0x12;
+    machine.cpu.r.l = 0x34;
+    assert_cycles!(machine, [12]);
+    assert_eq!(machine.cpu.pc, 0x0103);
+    assert_cycles!(machine, [16]);
+    assert_eq!(machine.cpu.pc, 0x0107);
+    assert_cycles!(machine, [4]);
+    assert_eq!(machine.cpu.pc, 0x1234);
+}
+
+#[test]
+fn test_call_ret() {
+    // CALL $0108; NOP...; RET NZ (not taken); RET Z (taken)
+    let mut machine = create_machine(&[0xCD, 0x08, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0, 0xC8]);
+    machine.cpu.r.set_f(0x80);
+    assert_cycles!(machine, [24]);
+    assert_eq!(machine.cpu.pc, 0x0108);
+    assert_eq!(machine.cpu.sp, 0xFFFC);
+    assert_eq!(machine.memory.read_word(0xFFFC), 0x0103);
+    assert_cycles!(machine, [8, 20]);
+    assert_eq!(machine.cpu.pc, 0x0103);
+    assert_eq!(machine.cpu.sp, 0xFFFE);
+}
+
+#[test]
+fn test_call_cond() {
+    // CALL C, $0200 (not taken); CALL NC, $0200 (taken)
+    let mut machine = create_machine(&[0xDC, 0x00, 0x02, 0xD4, 0x00, 0x02]);
+    machine.cpu.r.set_f(0x00);
+    assert_cycles!(machine, [12, 24]);
+    assert_eq!(machine.cpu.pc, 0x0200);
+    assert_eq!(machine.memory.read_word(0xFFFC), 0x0106);
+}
+
+#[test]
+fn test_rst() {
+    // RST $28
+    let mut machine = create_machine(&[0xEF]);
+    assert_cycles!(machine, [16]);
+    assert_eq!(machine.cpu.pc, 0x0028);
+    assert_eq!(machine.memory.read_word(0xFFFC), 0x0101);
+}
+
+#[test]
+fn test_push_pop() {
+    // PUSH BC; POP AF
+    let mut machine = create_machine(&[0xC5, 0xF1]);
+    machine.cpu.r.b = 0x12;
+    machine.cpu.r.c = 0xFF;
+    assert_cycles!(machine, [16, 12]);
+    assert_eq!(machine.cpu.r.a, 0x12);
+    // The low nibble of F is always zero
+    assert_eq!(machine.cpu.r.f(), 0xF0);
+    assert_eq!(machine.cpu.sp, 0xFFFE);
+}
+
+#[test]
+fn test_di_ei_reti() {
+    // EI; NOP; DI; RETI
+    let mut machine = create_machine(&[0xFB, 0x00, 0xF3, 0xD9]);
+    assert_cycles!(machine, [4]);
+    assert!(!machine.cpu.ime);
+    assert_cycles!(machine, [4]);
+    assert!(machine.cpu.ime);
+    assert_cycles!(machine, [4]);
+    assert!(!machine.cpu.ime);
+    machine.cpu.sp = 0xFFFC;
+    machine.memory.write_byte(0xFFFC, 0x00);
+    machine.memory.write_byte(0xFFFD, 0x02);
+    assert_cycles!(machine, [16]);
+    assert!(machine.cpu.ime);
+    assert_eq!(machine.cpu.pc, 0x0200);
+}
+
+#[test]
+fn test_invalid_opcode() {
+    let mut machine = create_machine(&[0xD3]);
+    assert!(machine.step().is_err());
+}
+
+#[test]
+fn test_cb_rotate() {
+    // RLC B; RR B; RL (HL)
+    let mut machine = create_machine(&[0xCB, 0x00, 0xCB, 0x18, 0x21, 0x00, 0xC0, 0xCB, 0x16]);
+    machine.cpu.r.set_f(0x00);
+    machine.cpu.r.b = 0x80;
+    assert_cycles!(machine, [8]);
+    assert_eq!(machine.cpu.r.b, 0x01);
+    assert_eq!(machine.cpu.r.f(), 0x10);
+    assert_cycles!(machine, [8]);
+    assert_eq!(machine.cpu.r.b, 0x80);
+    assert_eq!(machine.cpu.r.f(), 0x10);
+    assert_cycles!(machine, [12, 16]);
+    assert_eq!(machine.memory.read_byte(0xC000), 0x01);
+    assert_eq!(machine.cpu.r.f(), 0x00);
+    assert_eq!(machine.cpu.pc, 0x0109);
+}
+
+#[test]
+fn test_cb_shift() {
+    // SLA A; SRA B; SRL C; SWAP D
+    let mut machine = create_machine(&[0xCB, 0x27, 0xCB, 0x28, 0xCB, 0x39, 0xCB, 0x32]);
+    machine.cpu.r.a = 0x80;
+    machine.cpu.r.b = 0x81;
+    machine.cpu.r.c = 0x01;
+    machine.cpu.r.d = 0xF1;
+    assert_cycles!(machine, [8]);
+    assert_eq!(machine.cpu.r.a, 0x00);
+    assert_eq!(machine.cpu.r.f(), 0x90);
+    assert_cycles!(machine, [8]);
+    assert_eq!(machine.cpu.r.b, 0xC0);
+    assert_eq!(machine.cpu.r.f(), 0x10);
+    assert_cycles!(machine, [8]);
+    assert_eq!(machine.cpu.r.c, 0x00);
+    assert_eq!(machine.cpu.r.f(), 0x90);
+    assert_cycles!(machine, [8]);
+    assert_eq!(machine.cpu.r.d, 0x1F);
+    assert_eq!(machine.cpu.r.f(), 0x00);
+}
+
+#[test]
+fn test_cb_bit() {
+    // BIT 7, H; BIT 0, (HL); RES 0, (HL); SET 7, L
+    let mut machine = create_machine(&[0xCB, 0x7C, 0xCB, 0x46, 0xCB, 0x86, 0xCB, 0xFD]);
+    machine.cpu.r.set_f(0x10);
+    machine.cpu.r.h = 0xC0;
+    machine.cpu.r.l = 0x00;
+    machine.memory.write_byte(0xC000, 0x01);
+    assert_cycles!(machine, [8]);
+    assert_eq!(machine.cpu.r.f(), 0x30);
+    assert_cycles!(machine, [12]);
+    assert_eq!(machine.cpu.r.f(), 0x30);
+    assert_cycles!(machine, [16]);
+    assert_eq!(machine.memory.read_byte(0xC000), 0x00);
+    assert_cycles!(machine, [8]);
+    assert_eq!(machine.cpu.r.l, 0x80);
+}
+
+#[test]
+fn test_interrupt_dispatch() {
+    let mut machine = create_machine(&[0x00, 0x00]);
+    machine.cpu.ime = true;
+    machine.memory.write_byte(0xFFFF, 0x1F);
+    machine.memory.request_interrupt(Interrupt::Serial);
+    machine.memory.request_interrupt(Interrupt::Timer);
+
+    // Timer has the higher priority
+    assert_cycles!(machine, [20]);
+    assert_eq!(machine.cpu.pc, 0x0050);
+    assert!(!machine.cpu.ime);
+    assert_eq!(machine.memory.read_word(0xFFFC), 0x0100);
+    assert_eq!(machine.memory.read_byte(0xFF0F), 0xE8);
+}
+
+#[test]
+fn test_interrupt_disabled() {
+    let mut machine = create_machine(&[0x00, 0x00]);
+
+    // Requested but not enabled in IE
+    machine.cpu.ime = true;
+    machine.memory.request_interrupt(Interrupt::VBlank);
+    assert_cycles!(machine, [4]);
+    assert_eq!(machine.cpu.pc, 0x0101);
+
+    // Enabled in IE but IME is off
+    machine.cpu.ime = false;
+    machine.memory.write_byte(0xFFFF, 0x01);
+    assert_cycles!(machine, [4]);
+    assert_eq!(machine.cpu.pc, 0x0102);
+    assert_eq!(machine.memory.read_byte(0xFF0F), 0xE1);
+}
+
+#[test]
+fn test_ei_delay() {
+    // EI; NOP; NOP
+    let mut machine = create_machine(&[0xFB, 0x00, 0x00]);
+    machine.memory.write_byte(0xFFFF, 0x01);
+    machine.memory.request_interrupt(Interrupt::VBlank);
+
+    // The instruction following EI always executes before the interrupt
+    assert_cycles!(machine, [4, 4, 20]);
+    assert_eq!(machine.cpu.pc, 0x0040);
+    assert_eq!(machine.memory.read_word(0xFFFC), 0x0102);
+}
+
+#[test]
+fn test_ei_di_cancels() {
+    // EI; DI; NOP
+    let mut machine = create_machine(&[0xFB, 0xF3, 0x00]);
+    machine.memory.write_byte(0xFFFF, 0x01);
+    machine.memory.request_interrupt(Interrupt::VBlank);
+    assert_cycles!(machine, [4, 4, 4]);
+    assert_eq!(machine.cpu.pc, 0x0103);
+}
+
+#[test]
+fn test_halt_until_interrupt() {
+    // HALT; NOP
+    let mut machine = create_machine(&[0x76, 0x00]);
+    machine.cpu.ime = true;
+    machine.memory.write_byte(0xFFFF, 0x04);
+    assert_cycles!(machine, [4, 4, 4]);
+    assert!(machine.cpu.halted);
+    assert_eq!(machine.cpu.pc, 0x0101);
+
+    // Interrupt is serviced with the return address after the HALT
+    machine.memory.request_interrupt(Interrupt::Timer);
+    assert_cycles!(machine, [20]);
+    assert!(!machine.cpu.halted);
+    assert_eq!(machine.cpu.pc, 0x0050);
+    assert_eq!(machine.memory.read_word(0xFFFC), 0x0101);
+}
+
+#[test]
+fn test_halt_without_ime() {
+    // HALT; INC A
+    let mut machine = create_machine(&[0x76, 0x3C]);
+    machine.memory.write_byte(0xFFFF, 0x01);
+    assert_cycles!(machine, [4, 4]);
+    assert!(machine.cpu.halted);
+
+    // Wakes up without servicing the interrupt
+    machine.memory.request_interrupt(Interrupt::VBlank);
+    assert_cycles!(machine, [4]);
+    assert!(!machine.cpu.halted);
+    assert_eq!(machine.cpu.pc, 0x0102);
+    assert_eq!(machine.cpu.r.a, 0x02);
+    assert_eq!(machine.memory.read_byte(0xFF0F), 0xE1);
+}
+
+#[test]
+fn test_halt_bug() {
+    // HALT; LD A, $3C -> executes LD A, $3E; INC A
+    let mut machine = create_machine(&[0x76, 0x3E, 0x3C]);
+    machine.memory.write_byte(0xFFFF, 0x01);
+    machine.memory.request_interrupt(Interrupt::VBlank);
+    assert_cycles!(machine, [4]);
+    assert!(!machine.cpu.halted);
+    assert_cycles!(machine, [8]);
+    assert_eq!(machine.cpu.r.a, 0x3E);
+    assert_eq!(machine.cpu.pc, 0x0102);
+    assert_cycles!(machine, [4]);
+    assert_eq!(machine.cpu.r.a, 0x3F);
+    assert_eq!(machine.cpu.pc, 0x0103);
+}
+
+#[test]
+fn test_stop() {
+    // STOP; INC A
+    let mut machine = create_machine(&[0x10, 0x00, 0x3C]);
+    machine.memory.write_byte(0xFF00, 0x20);
+    assert_cycles!(machine, [4, 4, 4]);
+    assert!(machine.cpu.stopped);
+    assert_eq!(machine.cpu.pc, 0x0102);
+
+    // Button in a deselected group does not wake the CPU
+    machine.memory.press_button(Button::A);
+    assert_cycles!(machine, [4]);
+    assert!(machine.cpu.stopped);
+
+    machine.memory.press_button(Button::Down);
+    assert_cycles!(machine, [4]);
+    assert!(!machine.cpu.stopped);
+    assert_eq!(machine.cpu.r.a, 0x02);
+}
+
+#[test]
+fn test_stop_speed_switch() {
+    // LD A, $01; LDH ($4D), A; STOP; INC A
+    let configuration = Configuration {
+        gameboy_type: GameboyType::ColorGameboy,
+        ..Configuration::default()
+    };
+    let mut machine = create_machine_with_configuration(
+        &[0x3E, 0x01, 0xE0, 0x4D, 0x10, 0x00, 0x3C], &configuration
+    );
+    assert_cycles!(machine, [8, 12, 4]);
+    assert!(!machine.cpu.stopped);
+    assert!(machine.memory.double_speed());
+    assert_eq!(machine.memory.read_byte(0xFF4D), 0xFE);
+    assert_cycles!(machine, [4]);
+    assert_eq!(machine.cpu.r.a, 0x02);
+}
+
+#[test]
+fn test_general_purpose_dma_stalls_cpu() {
+    // LD A, $01; LDH ($55), A; INC A
+    let configuration = Configuration {
+        gameboy_type: GameboyType::ColorGameboy,
+        ..Configuration::default()
+    };
+    let mut machine = create_machine_with_configuration(
+        &[0x3E, 0x01, 0xE0, 0x55, 0x3C], &configuration
+    );
+    assert_cycles!(machine, [8, 12 + 2 * 32, 4]);
+    assert_eq!(machine.memory.read_byte(0xFF55), 0xFF);
+}
@@ -1,13 +1,25 @@
 extern crate gameboy;
-use gameboy::memory::{Memory};
+use gameboy::{Configuration, GameboyType};
+use gameboy::memory::*;
 use gameboy::cartridge::{Cartridge, RomOnly};
+use gameboy::ppu::LY_ADDRESS;
 
 // Helper method that creates a new memory instance with ROM-only cartridge
 fn new_memory() -> Memory {
-    let cartridge: Box<Cartridge> = Box::new(RomOnly::new(vec![0x12; 0x10000]));
+    let cartridge: Box<dyn Cartridge> = Box::new(RomOnly::new(vec![0x12; 0x10000]));
     Memory::new(cartridge)
 }
 
+// Same as new_memory but with the CGB features enabled
+fn new_cgb_memory() -> Memory {
+    let cartridge: Box<dyn Cartridge> = Box::new(RomOnly::new(vec![0x12; 0x10000]));
+    let configuration = Configuration {
+        gameboy_type: GameboyType::ColorGameboy,
+        ..Configuration::default()
+    };
+    Memory::with_configuration(cartridge, &configuration)
+}
+
 #[test]
 fn test_wram_mirror_readwrite() {
     let mut memory = new_memory();
@@ -60,3 +72,100 @@ fn test_cartridge_rom_readwrite() {
     memory.write_byte(0x0000, 0x34);
     assert_eq!(memory.read_byte(0x0000), 0x12);
 }
+
+#[test]
+fn test_read_word_across_end_of_rom() {
+    let mut memory = new_memory();
+    memory.write_byte(0x8000, 0x34);
+    let vram = memory.read_byte(0x8000);
+    assert_eq!(memory.read_word(0x7FFE), 0x1212);
+    assert_eq!(memory.read_word(0x7FFF), (vram as u16) << 8 | 0x12);
+}
+
+#[test]
+fn test_cartridge_ram_absent() {
+    let mut memory = new_memory();
+    memory.write_byte(0xA000, 0x34);
+    assert_eq!(memory.read_byte(0xA000), 0xFF);
+    assert_eq!(memory.read_byte(0xBFFF), 0xFF);
+    assert_eq!(memory.read_word(0xA000), 0xFFFF);
+    assert_eq!(memory.read_word(0xBFFF) & 0x00FF, 0xFF);
+}
+
+#[test]
+fn test_wram_banks() {
+    let mut memory = new_cgb_memory();
+    assert_eq!(memory.read_byte(SVBK_ADDRESS), 0xF8);
+    for bank in 1..8 {
+        memory.write_byte(SVBK_ADDRESS, bank);
+        memory.write_byte(0xC000, 0x10);
+        memory.write_byte(0xD000, bank);
+        memory.write_byte(0xDFFF, bank + 0x10);
+    }
+
+    for bank in 1..8 {
+        memory.write_byte(SVBK_ADDRESS, bank);
+        assert_eq!(memory.read_byte(SVBK_ADDRESS), 0xF8 | bank);
+        assert_eq!(memory.read_byte(0xD000), bank);
+        assert_eq!(memory.read_byte(0xDFFF), bank + 0x10);
+        // Bank 0 is always mapped and the mirror follows the selected bank
+        assert_eq!(memory.read_byte(0xC000), 0x10);
+        assert_eq!(memory.read_byte(0xF000), bank);
+    }
+
+    // Writes through the mirror land in the selected bank
+    memory.write_byte(SVBK_ADDRESS, 0x03);
+    memory.write_byte(0xF123, 0x45);
+    assert_eq!(memory.read_byte(0xD123), 0x45);
+
+    // Selecting bank 0 selects bank 1
+    memory.write_byte(SVBK_ADDRESS, 0x00);
+    assert_eq!(memory.read_byte(SVBK_ADDRESS), 0xF8);
+    assert_eq!(memory.read_byte(0xD000), 0x01);
+}
+
+#[test]
+fn test_wram_banks_dmg() {
+    let mut memory = new_memory();
+    memory.write_byte(0xD000, 0x01);
+    memory.write_byte(SVBK_ADDRESS, 0x02);
+    memory.write_byte(0xD000, 0x02);
+    memory.write_byte(SVBK_ADDRESS, 0x01);
+    assert_eq!(memory.read_byte(0xD000), 0x02);
+    assert_eq!(memory.read_byte(SVBK_ADDRESS), 0xFF);
+}
+
+#[test]
+fn test_speed_switch() {
+    let mut memory = new_memory();
+    memory.write_byte(KEY1_ADDRESS, 0x01);
+    assert_eq!(memory.read_byte(KEY1_ADDRESS), 0xFF);
+    assert!(!memory.switch_speed());
+
+    let mut memory = new_cgb_memory();
+    assert_eq!(memory.read_byte(KEY1_ADDRESS), 0x7E);
+    assert!(!memory.switch_speed());
+    memory.write_byte(KEY1_ADDRESS, 0xFF);
+    assert_eq!(memory.read_byte(KEY1_ADDRESS), 0x7F);
+    assert!(memory.switch_speed());
+    assert!(memory.double_speed());
+    assert_eq!(memory.read_byte(KEY1_ADDRESS), 0xFE);
+
+    memory.write_byte(KEY1_ADDRESS, 0x01);
+    assert!(memory.switch_speed());
+    assert!(!memory.double_speed());
+}
+
+#[test]
+fn test_double_speed_clocking() {
+    let mut memory = new_cgb_memory();
+    memory.write_byte(KEY1_ADDRESS, 0x01);
+    memory.switch_speed();
+
+    // The divider counts CPU cycles while the PPU takes twice as many CPU
+    // cycles per line
+    memory.tick(456);
+    assert_eq!(memory.read_byte(LY_ADDRESS), 0);
+    memory.tick(456);
+    assert_eq!(memory.read_byte(LY_ADDRESS), 1);
+}
@@ -2,7 +2,6 @@ extern crate gameboy;
 
 use gameboy::*;
 use gameboy::memory::Memory;
-use gameboy::cpu::*;
 use gameboy::operations::*;
 use gameboy::rom::{CartridgeKind, Rom};
 
@@ -55,18 +54,19 @@ fn test_misc() {
     assert_op!("RRA", &[0x1F], Rra);
     assert_op!("DAA", &[0x27], Daa);
     assert_op!("CPL", &[0x2F], Cpl);
-    assert_op!("SCF", &[0x36], Scf);
+    assert_op!("SCF", &[0x37], Scf);
     assert_op!("CCF", &[0x3F], Ccf);
     assert_op!("DI", &[0xF3], Di);
     assert_op!("EI", &[0xFB], Ei);
+    assert_op!("HALT", &[0x76], Halt);
 }
 
 #[test]
 fn test_ld() {
-    assert_op!("LD BC, d16", &[0x01, 0x12, 0x34], Ld16RegImm(Reg16::BC, 0x1234));
-    assert_op!("LD DE, d16", &[0x11, 0x12, 0x34], Ld16RegImm(Reg16::DE, 0x1234));
-    assert_op!("LD HL, d16", &[0x21, 0x12, 0x34], Ld16RegImm(Reg16::HL, 0x1234));
-    assert_op!("LD SP, d16", &[0x31, 0x12, 0x34], Ld16RegImm(Reg16::SP, 0x1234));
+    assert_op!("LD BC, d16", &[0x01, 0x34, 0x12], Ld16RegImm(Reg16::BC, 0x1234));
+    assert_op!("LD DE, d16", &[0x11, 0x34, 0x12], Ld16RegImm(Reg16::DE, 0x1234));
+    assert_op!("LD HL, d16", &[0x21, 0x34, 0x12], Ld16RegImm(Reg16::HL, 0x1234));
+    assert_op!("LD SP, d16", &[0x31, 0x34, 0x12], Ld16RegImm(Reg16::SP, 0x1234));
 
     assert_op!("LD B, d8", &[0x06, 0x12], Ld8RegImm(Reg8::B, 0x12));
     assert_op!("LD C, d8", &[0x0E, 0x12], Ld8RegImm(Reg8::C, 0x12));
@@ -129,6 +129,14 @@ fn test_ld() {
     assert_op!("LD L, L", &[0x6D], Ld8RegReg(Reg8::L, Reg8::L));
     assert_op!("LD L, L", &[0x6F], Ld8RegReg(Reg8::L, Reg8::A));
 
+    assert_op!("LD B, (HL)", &[0x46], Ld8RegMemHl(Reg8::B));
+    assert_op!("LD C, (HL)", &[0x4E], Ld8RegMemHl(Reg8::C));
+    assert_op!("LD D, (HL)", &[0x56], Ld8RegMemHl(Reg8::D));
+    assert_op!("LD E, (HL)", &[0x5E], Ld8RegMemHl(Reg8::E));
+    assert_op!("LD H, (HL)", &[0x66], Ld8RegMemHl(Reg8::H));
+    assert_op!("LD L, (HL)", &[0x6E], Ld8RegMemHl(Reg8::L));
+    assert_op!("LD A, (HL)", &[0x7E], Ld8RegMemHl(Reg8::A));
+
     assert_op!("LD A, B", &[0x78], Ld8RegReg(Reg8::A, Reg8::B));
     assert_op!("LD A, C", &[0x79], Ld8RegReg(Reg8::A, Reg8::C));
     assert_op!("LD A, D", &[0x7A], Ld8RegReg(Reg8::A, Reg8::D));
@@ -143,7 +151,7 @@ fn test_ld() {
     assert_op!("LD HL, SP + r8", &[0xF8, 0x01], LdHlSp(1));
     assert_op!("LD HL, SP + r8", &[0xF8, !0x01], LdHlSp(-2));
     assert_op!("LD SP, HL", &[0xF9], LdSpHl);
-    assert_op!("LD A, (a16)", &[0xFA, 0x12, 0x34], Ld8AccMemImm(0x1234));
+    assert_op!("LD A, (a16)", &[0xFA, 0x34, 0x12], Ld8AccMemImm(0x1234));
 }
 
 #[test]
@@ -153,10 +161,19 @@ fn test_st() {
     assert_op!("LD (HL+), A", &[0x22], St8MemRegAcc(Reg16::HL));
     assert_op!("LD (HL-), A", &[0x32], St8MemRegAcc(Reg16::HL));
 
-    assert_op!("LD (a16), SP", &[0x08, 0x12, 0x34], St16MemSp(0x1234));
+    assert_op!("LD (HL), B", &[0x70], St8MemHlReg(Reg8::B));
+    assert_op!("LD (HL), C", &[0x71], St8MemHlReg(Reg8::C));
+    assert_op!("LD (HL), D", &[0x72], St8MemHlReg(Reg8::D));
+    assert_op!("LD (HL), E", &[0x73], St8MemHlReg(Reg8::E));
+    assert_op!("LD (HL), H", &[0x74], St8MemHlReg(Reg8::H));
+    assert_op!("LD (HL), L", &[0x75], St8MemHlReg(Reg8::L));
+    assert_op!("LD (HL), A", &[0x77], St8MemHlReg(Reg8::A));
+    assert_op!("LD (HL), d8", &[0x36, 0x12], St8MemHlImm(0x12));
+
+    assert_op!("LD (a16), SP", &[0x08, 0x34, 0x12], St16MemSp(0x1234));
     assert_op!("LD ($FF00 + a8), A", &[0xE0, 0x12], LdhMemAcc(0x12));
     assert_op!("LD ($FF00 + C), A", &[0xE2], LdcMemAcc);
-    assert_op!("LD (a16), A", &[0xEA, 0x12, 0x34], St8MemImmAcc(0x1234));
+    assert_op!("LD (a16), A", &[0xEA, 0x34, 0x12], St8MemImmAcc(0x1234));
 }
 
 #[test]
@@ -286,6 +303,8 @@ fn test_bitwise_arithmetic() {
 
 #[test]
 fn test_jump() {
+    assert_op!("JR s8", &[0x18, 0x01], JrImm(1));
+    assert_op!("JR s8", &[0x18, !0x01], JrImm(-2));
     assert_op!("JR NZ, s8", &[0x20, 0x01], Jr(Condition::NZ, 1));
     assert_op!("JR NZ, s8", &[0x20, !0x01], Jr(Condition::NZ, -2));
     assert_op!("JR Z, s8", &[0x28, 0x01], Jr(Condition::Z, 1));
@@ -295,11 +314,11 @@ fn test_jump() {
     assert_op!("JR C, s8", &[0x38, 0x01], Jr(Condition::C, 1));
     assert_op!("JR C, s8", &[0x38, !0x01], Jr(Condition::C, -2));
 
-    assert_op!("JP NZ, a16", &[0xC2, 0x12, 0x34], Jp(Condition::NZ, 0x1234));
-    assert_op!("JP Z, a16", &[0xCA, 0x12, 0x34], Jp(Condition::Z, 0x1234));
-    assert_op!("JP NC, a16", &[0xD2, 0x12, 0x34], Jp(Condition::NC, 0x1234));
-    assert_op!("JP C, a16", &[0xDA, 0x12, 0x34], Jp(Condition::C, 0x1234));
-    assert_op!("JP a16", &[0xC3, 0x12, 0x34], JpImm(0x1234));
+    assert_op!("JP NZ, a16", &[0xC2, 0x34, 0x12], Jp(Condition::NZ, 0x1234));
+    assert_op!("JP Z, a16", &[0xCA, 0x34, 0x12], Jp(Condition::Z, 0x1234));
+    assert_op!("JP NC, a16", &[0xD2, 0x34, 0x12], Jp(Condition::NC, 0x1234));
+    assert_op!("JP C, a16", &[0xDA, 0x34, 0x12], Jp(Condition::C, 0x1234));
+    assert_op!("JP a16", &[0xC3, 0x34, 0x12], JpImm(0x1234));
     assert_op!("JP HL", &[0xE9], JpHl);
 }
 
@@ -312,11 +331,11 @@ fn test_call_ret() {
     assert_op!("RET", &[0xC9], Ret);
     assert_op!("RETI", &[0xD9], Reti);
 
-    assert_op!("CALL NZ, a16", &[0xC4, 0x12, 0x34], CallCond(Condition::NZ, 0x1234));
-    assert_op!("CALL Z, a16", &[0xCC, 0x12, 0x34], CallCond(Condition::Z, 0x1234));
-    assert_op!("CALL NC, a16", &[0xD4, 0x12, 0x34], CallCond(Condition::NC, 0x1234));
-    assert_op!("CALL C, a16", &[0xDC, 0x12, 0x34], CallCond(Condition::C, 0x1234));
-    assert_op!("CALL a16", &[0xCD, 0x12, 0x34], Call(0x1234));
+    assert_op!("CALL NZ, a16", &[0xC4, 0x34, 0x12], CallCond(Condition::NZ, 0x1234));
+    assert_op!("CALL Z, a16", &[0xCC, 0x34, 0x12], CallCond(Condition::Z, 0x1234));
+    assert_op!("CALL NC, a16", &[0xD4, 0x34, 0x12], CallCond(Condition::NC, 0x1234));
+    assert_op!("CALL C, a16", &[0xDC, 0x34, 0x12], CallCond(Condition::C, 0x1234));
+    assert_op!("CALL a16", &[0xCD, 0x34, 0x12], Call(0x1234));
 }
 
 #[test]
@@ -357,3 +376,55 @@ fn test_invalid_opcodes() {
     assert_err!(&[0xFC]);
     assert_err!(&[0xFD]);
 }
+
+#[test]
+fn test_operation_size() {
+    assert_eq!(operation_from_memory_fragment(&[0x00]).unwrap().size(), 1);
+    assert_eq!(operation_from_memory_fragment(&[0x06, 0x12]).unwrap().size(), 2);
+    assert_eq!(operation_from_memory_fragment(&[0x10, 0x00]).unwrap().size(), 2);
+    assert_eq!(operation_from_memory_fragment(&[0x18, 0x12]).unwrap().size(), 2);
+    assert_eq!(operation_from_memory_fragment(&[0x36, 0x12]).unwrap().size(), 2);
+    assert_eq!(operation_from_memory_fragment(&[0xE0, 0x12]).unwrap().size(), 2);
+    assert_eq!(operation_from_memory_fragment(&[0x01, 0x34, 0x12]).unwrap().size(), 3);
+    assert_eq!(operation_from_memory_fragment(&[0xC3, 0x34, 0x12]).unwrap().size(), 3);
+    assert_eq!(operation_from_memory_fragment(&[0xCD, 0x34, 0x12]).unwrap().size(), 3);
+}
+
+#[test]
+fn test_cb_rotate_shift() {
+    assert_op!("RLC B", &[0xCB, 0x00], Rlc8Reg(Reg8::B));
+    assert_op!("RLC (HL)", &[0xCB, 0x06], Rlc8MemHl);
+    assert_op!("RRC A", &[0xCB, 0x0F], Rrc8Reg(Reg8::A));
+    assert_op!("RRC (HL)", &[0xCB, 0x0E], Rrc8MemHl);
+    assert_op!("RL C", &[0xCB, 0x11], Rl8Reg(Reg8::C));
+    assert_op!("RL (HL)", &[0xCB, 0x16], Rl8MemHl);
+    assert_op!("RR D", &[0xCB, 0x1A], Rr8Reg(Reg8::D));
+    assert_op!("RR (HL)", &[0xCB, 0x1E], Rr8MemHl);
+    assert_op!("SLA E", &[0xCB, 0x23], Sla8Reg(Reg8::E));
+    assert_op!("SLA (HL)", &[0xCB, 0x26], Sla8MemHl);
+    assert_op!("SRA H", &[0xCB, 0x2C], Sra8Reg(Reg8::H));
+    assert_op!("SRA (HL)", &[0xCB, 0x2E], Sra8MemHl);
+    assert_op!("SWAP L", &[0xCB, 0x35], Swap8Reg(Reg8::L));
+    assert_op!("SWAP (HL)", &[0xCB, 0x36], Swap8MemHl);
+    assert_op!("SRL A", &[0xCB, 0x3F], Srl8Reg(Reg8::A));
+    assert_op!("SRL (HL)", &[0xCB, 0x3E], Srl8MemHl);
+}
+
+#[test]
+fn test_cb_bit_operations() {
+    assert_op!("BIT 0, B", &[0xCB, 0x40], Bit8Reg(0, Reg8::B));
+    assert_op!("BIT 7, A", &[0xCB, 0x7F], Bit8Reg(7, Reg8::A));
+    assert_op!("BIT 3, (HL)", &[0xCB, 0x5E], Bit8MemHl(3));
+    assert_op!("RES 1, C", &[0xCB, 0x89], Res8Reg(1, Reg8::C));
+    assert_op!("RES 7, (HL)", &[0xCB, 0xBE], Res8MemHl(7));
+    assert_op!("SET 4, H", &[0xCB, 0xE4], Set8Reg(4, Reg8::H));
+    assert_op!("SET 0, (HL)", &[0xCB, 0xC6], Set8MemHl(0));
+}
+
+#[test]
+fn test_cb_complete() {
+    for prefix in 0x00..=0xFF {
+        let result = operation_from_memory_fragment(&[0xCB, prefix]).unwrap();
+        assert_eq!(result.size(), 2, "CB {:02X} size mismatch", prefix);
+    }
+}
@@ -0,0 +1,655 @@
+extern crate gameboy;
+
+use gameboy::{Configuration, GameboyType, PpuRenderer};
+use gameboy::interrupts::{Interrupt, Interrupts};
+use gameboy::ppu::*;
+
+// Creates a PPU with the LCD switched off, ready for VRAM and OAM to be
+// filled before the LCD is switched on.
+fn create_ppu() -> (Ppu, Interrupts) {
+    create_ppu_with_renderer(PpuRenderer::Scanline)
+}
+
+fn create_fifo_ppu() -> (Ppu, Interrupts) {
+    create_ppu_with_renderer(PpuRenderer::PixelFifo)
+}
+
+fn create_ppu_with_renderer(renderer: PpuRenderer) -> (Ppu, Interrupts) {
+    create_ppu_with_configuration(Configuration { ppu_renderer: renderer, ..Configuration::default() })
+}
+
+fn create_cgb_ppu(renderer: PpuRenderer) -> (Ppu, Interrupts) {
+    create_ppu_with_configuration(Configuration {
+        gameboy_type: GameboyType::ColorGameboy,
+        ppu_renderer: renderer
+    })
+}
+
+fn create_ppu_with_configuration(configuration: Configuration) -> (Ppu, Interrupts) {
+    let mut ppu = Ppu::with_configuration(&configuration);
+    let mut interrupts = Interrupts::new();
+    interrupts.write_enable(0xFF);
+    ppu.write(LCDC_ADDRESS, 0x00, &mut interrupts);
+    ppu.write(BGP_ADDRESS, 0xE4, &mut interrupts);
+    ppu.write(OBP0_ADDRESS, 0xE4, &mut interrupts);
+    ppu.write(OBP1_ADDRESS, 0x1B, &mut interrupts);
+    ppu.frame_ready();
+    (ppu, interrupts)
+}
+
+// Fills every row of a tile with the given colour index.
+fn fill_tile(ppu: &mut Ppu, address: usize, colour: u8) {
+    let lo = if colour & 0x01 != 0 { 0xFF } else { 0x00 };
+    let hi = if colour & 0x02 != 0 { 0xFF } else { 0x00 };
+    for row in 0..8 {
+        ppu.vram[address - 0x8000 + row * 2] = lo;
+        ppu.vram[address - 0x8000 + row * 2 + 1] = hi;
+    }
+}
+
+fn set_sprite(ppu: &mut Ppu, index: usize, y: u8, x: u8, tile: u8, attributes: u8) {
+    ppu.oam[index * 4..index * 4 + 4].copy_from_slice(&[y, x, tile, attributes]);
+}
+
+// Switches the LCD on with the given LCDC value and renders a full frame.
+fn render_frame(ppu: &mut Ppu, interrupts: &mut Interrupts, lcdc: u8) {
+    ppu.write(LCDC_ADDRESS, lcdc, interrupts);
+    ppu.tick(CYCLES_PER_LINE * 72, interrupts);
+    ppu.tick(CYCLES_PER_LINE * 72, interrupts);
+    assert!(ppu.frame_ready());
+}
+
+#[test]
+fn test_mode_timing() {
+    let (mut ppu, mut interrupts) = create_ppu();
+    ppu.write(LCDC_ADDRESS, 0x91, &mut interrupts);
+    assert_eq!(ppu.mode(), Mode::OamSearch);
+    ppu.tick(79, &mut interrupts);
+    assert_eq!(ppu.mode(), Mode::OamSearch);
+    ppu.tick(1, &mut interrupts);
+    assert_eq!(ppu.mode(), Mode::PixelTransfer);
+    ppu.tick(172, &mut interrupts);
+    assert_eq!(ppu.mode(), Mode::HBlank);
+    assert_eq!(ppu.read(STAT_ADDRESS) & 0x03, 0x00);
+    ppu.tick(204, &mut interrupts);
+    assert_eq!(ppu.mode(), Mode::OamSearch);
+    assert_eq!(ppu.read(LY_ADDRESS), 1);
+}
+
+#[test]
+fn test_vblank() {
+    let (mut ppu, mut interrupts) = create_ppu();
+    ppu.write(LCDC_ADDRESS, 0x91, &mut interrupts);
+    ppu.tick(CYCLES_PER_LINE * 72, &mut interrupts);
+    ppu.tick(CYCLES_PER_LINE * 72 - 1, &mut interrupts);
+    assert_eq!(interrupts.pending(), 0x00);
+    assert!(!ppu.frame_ready());
+    ppu.tick(1, &mut interrupts);
+    assert_eq!(ppu.read(LY_ADDRESS), 144);
+    assert_eq!(ppu.mode(), Mode::VBlank);
+    assert_eq!(interrupts.next_pending(), Some(Interrupt::VBlank));
+    assert!(ppu.frame_ready());
+    assert!(!ppu.frame_ready());
+
+    // LY wraps back to 0 after line 153
+    ppu.tick(CYCLES_PER_LINE * 10, &mut interrupts);
+    assert_eq!(ppu.read(LY_ADDRESS), 0);
+    assert_eq!(ppu.mode(), Mode::OamSearch);
+}
+
+#[test]
+fn test_lcd_off() {
+    let (mut ppu, mut interrupts) = create_ppu();
+    ppu.tick(1000, &mut interrupts);
+    assert_eq!(ppu.read(LY_ADDRESS), 0);
+    assert_eq!(ppu.read(STAT_ADDRESS), 0x84);
+    assert_eq!(interrupts.pending(), 0x00);
+}
+
+#[test]
+fn test_stat_lyc_interrupt() {
+    let (mut ppu, mut interrupts) = create_ppu();
+    ppu.write(LYC_ADDRESS, 2, &mut interrupts);
+    ppu.write(STAT_ADDRESS, 0x40, &mut interrupts);
+    ppu.write(LCDC_ADDRESS, 0x91, &mut interrupts);
+    ppu.tick(CYCLES_PER_LINE * 2 - 1, &mut interrupts);
+    assert_eq!(ppu.read(STAT_ADDRESS) & 0x04, 0x00);
+    assert_eq!(interrupts.pending(), 0x00);
+    ppu.tick(1, &mut interrupts);
+    assert_eq!(ppu.read(STAT_ADDRESS), 0xC6);
+    assert_eq!(interrupts.next_pending(), Some(Interrupt::LcdStat));
+}
+
+#[test]
+fn test_stat_interrupt_blocking() {
+    let (mut ppu, mut interrupts) = create_ppu();
+    ppu.write(STAT_ADDRESS, 0x28, &mut interrupts);
+    ppu.write(LCDC_ADDRESS, 0x91, &mut interrupts);
+    ppu.tick(CYCLES_PER_LINE - 1, &mut interrupts);
+    interrupts.acknowledge(Interrupt::LcdStat);
+
+    // HBlank runs straight into OAM search so the line never goes low
+    ppu.tick(1, &mut interrupts);
+    assert_eq!(ppu.mode(), Mode::OamSearch);
+    assert_eq!(interrupts.pending(), 0x00);
+}
+
+#[test]
+fn test_vram_oam_access() {
+    let (mut ppu, mut interrupts) = create_ppu();
+    ppu.write(LCDC_ADDRESS, 0x91, &mut interrupts);
+    ppu.write_vram(0x8000, 0x12);
+    assert_eq!(ppu.read_vram(0x8000), 0x12);
+    ppu.write_oam(0xFE00, 0x34);
+    assert_eq!(ppu.read_oam(0xFE00), 0xFF);
+
+    ppu.tick(80, &mut interrupts);
+    ppu.write_vram(0x8000, 0x56);
+    assert_eq!(ppu.read_vram(0x8000), 0xFF);
+
+    ppu.tick(172, &mut interrupts);
+    assert_eq!(ppu.read_vram(0x8000), 0x12);
+    ppu.write_oam(0xFE00, 0x34);
+    assert_eq!(ppu.read_oam(0xFE00), 0x34);
+}
+
+#[test]
+fn test_background() {
+    let (mut ppu, mut interrupts) = create_ppu();
+    fill_tile(&mut ppu, 0x8010, 1);
+    fill_tile(&mut ppu, 0x8020, 3);
+    ppu.vram[0x1800] = 0x01;
+    ppu.vram[0x1801] = 0x02;
+    render_frame(&mut ppu, &mut interrupts, 0x91);
+
+    let frame = ppu.frame_buffer();
+    assert_eq!(frame.pixel(0, 0), DMG_SHADES[1]);
+    assert_eq!(frame.pixel(7, 7), DMG_SHADES[1]);
+    assert_eq!(frame.pixel(8, 0), DMG_SHADES[3]);
+    assert_eq!(frame.pixel(16, 0), DMG_SHADES[0]);
+    assert_eq!(frame.pixel(0, 8), DMG_SHADES[0]);
+}
+
+#[test]
+fn test_background_scroll_and_palette() {
+    let (mut ppu, mut interrupts) = create_ppu();
+    fill_tile(&mut ppu, 0x8010, 1);
+    ppu.vram[0x1800 + 32 + 1] = 0x01;
+    ppu.write(SCX_ADDRESS, 4, &mut interrupts);
+    ppu.write(SCY_ADDRESS, 8, &mut interrupts);
+    ppu.write(BGP_ADDRESS, 0x0C, &mut interrupts);
+    render_frame(&mut ppu, &mut interrupts, 0x91);
+
+    let frame = ppu.frame_buffer();
+    assert_eq!(frame.pixel(3, 0), DMG_SHADES[0]);
+    assert_eq!(frame.pixel(4, 0), DMG_SHADES[3]);
+    assert_eq!(frame.pixel(11, 7), DMG_SHADES[3]);
+    assert_eq!(frame.pixel(12, 0), DMG_SHADES[0]);
+}
+
+#[test]
+fn test_signed_tile_data() {
+    let (mut ppu, mut interrupts) = create_ppu();
+    fill_tile(&mut ppu, 0x8FF0, 2);
+    ppu.vram[0x1800] = 0xFF;
+    render_frame(&mut ppu, &mut interrupts, 0x81);
+    assert_eq!(ppu.frame_buffer().pixel(0, 0), DMG_SHADES[2]);
+}
+
+#[test]
+fn test_window_line_counter() {
+    let (mut ppu, mut interrupts) = create_ppu();
+    // Window map at 0x9C00 uses tile 1 for its first row and tile 2 below
+    fill_tile(&mut ppu, 0x8010, 1);
+    fill_tile(&mut ppu, 0x8020, 2);
+    ppu.vram[0x1C00] = 0x01;
+    ppu.vram[0x1C20] = 0x02;
+    ppu.write(WY_ADDRESS, 0, &mut interrupts);
+    ppu.write(WX_ADDRESS, 7, &mut interrupts);
+    ppu.write(LCDC_ADDRESS, 0xF1, &mut interrupts);
+
+    // Move the window off screen for lines 4 to 7
+    ppu.tick(CYCLES_PER_LINE * 4, &mut interrupts);
+    ppu.write(WX_ADDRESS, 200, &mut interrupts);
+    ppu.tick(CYCLES_PER_LINE * 4, &mut interrupts);
+    ppu.write(WX_ADDRESS, 7, &mut interrupts);
+    ppu.tick(CYCLES_PER_LINE * 68, &mut interrupts);
+    ppu.tick(CYCLES_PER_LINE * 68, &mut interrupts);
+
+    let frame = ppu.frame_buffer();
+    assert_eq!(frame.pixel(0, 3), DMG_SHADES[1]);
+    assert_eq!(frame.pixel(0, 4), DMG_SHADES[0]);
+    // The window continues from its 5th line rather than following LY
+    assert_eq!(frame.pixel(0, 8), DMG_SHADES[1]);
+    assert_eq!(frame.pixel(0, 11), DMG_SHADES[1]);
+    assert_eq!(frame.pixel(0, 12), DMG_SHADES[2]);
+}
+
+#[test]
+fn test_window_position() {
+    let (mut ppu, mut interrupts) = create_ppu();
+    fill_tile(&mut ppu, 0x8010, 3);
+    for tile in 0..0x400 {
+        ppu.vram[0x1C00 + tile] = 0x01;
+    }
+    ppu.write(WY_ADDRESS, 10, &mut interrupts);
+    ppu.write(WX_ADDRESS, 27, &mut interrupts);
+    render_frame(&mut ppu, &mut interrupts, 0xF1);
+
+    let frame = ppu.frame_buffer();
+    assert_eq!(frame.pixel(20, 9), DMG_SHADES[0]);
+    assert_eq!(frame.pixel(19, 10), DMG_SHADES[0]);
+    assert_eq!(frame.pixel(20, 10), DMG_SHADES[3]);
+    assert_eq!(frame.pixel(159, 143), DMG_SHADES[3]);
+}
+
+#[test]
+fn test_sprites() {
+    let (mut ppu, mut interrupts) = create_ppu();
+    // Tile 1 is colour 1 in its left half and transparent in its right half
+    for row in 0..8 {
+        ppu.vram[0x10 + row * 2] = 0xF0;
+    }
+    set_sprite(&mut ppu, 0, 16, 8, 0x01, 0x00);
+    set_sprite(&mut ppu, 1, 32, 8, 0x01, 0x20);
+    set_sprite(&mut ppu, 2, 48, 8, 0x01, 0x10);
+    render_frame(&mut ppu, &mut interrupts, 0x93);
+
+    let frame = ppu.frame_buffer();
+    assert_eq!(frame.pixel(0, 0), DMG_SHADES[1]);
+    assert_eq!(frame.pixel(4, 0), DMG_SHADES[0]);
+    assert_eq!(frame.pixel(0, 8), DMG_SHADES[0]);
+    // Flipped horizontally
+    assert_eq!(frame.pixel(0, 16), DMG_SHADES[0]);
+    assert_eq!(frame.pixel(7, 16), DMG_SHADES[1]);
+    // OBP1
+    assert_eq!(frame.pixel(0, 32), DMG_SHADES[2]);
+}
+
+#[test]
+fn test_sprites_disabled() {
+    let (mut ppu, mut interrupts) = create_ppu();
+    fill_tile(&mut ppu, 0x8010, 3);
+    set_sprite(&mut ppu, 0, 16, 8, 0x01, 0x00);
+    render_frame(&mut ppu, &mut interrupts, 0x91);
+    assert_eq!(ppu.frame_buffer().pixel(0, 0), DMG_SHADES[0]);
+}
+
+#[test]
+fn test_sprite_flip_y_and_tall_sprites() {
+    let (mut ppu, mut interrupts) = create_ppu();
+    // Tiles 2 and 3 only have their top row set
+    ppu.vram[0x20] = 0xFF;
+    ppu.vram[0x30] = 0xFF;
+    ppu.vram[0x31] = 0xFF;
+    set_sprite(&mut ppu, 0, 16, 8, 0x03, 0x00);
+    set_sprite(&mut ppu, 1, 16, 16, 0x02, 0x40);
+    render_frame(&mut ppu, &mut interrupts, 0x97);
+
+    let frame = ppu.frame_buffer();
+    // 8x16 ignores bit 0 of the tile index
+    assert_eq!(frame.pixel(0, 0), DMG_SHADES[1]);
+    assert_eq!(frame.pixel(0, 8), DMG_SHADES[3]);
+    // Flipping a 16 pixel sprite swaps its tiles
+    assert_eq!(frame.pixel(8, 7), DMG_SHADES[3]);
+    assert_eq!(frame.pixel(8, 15), DMG_SHADES[1]);
+}
+
+#[test]
+fn test_sprite_priority() {
+    let (mut ppu, mut interrupts) = create_ppu();
+    fill_tile(&mut ppu, 0x8010, 1);
+    fill_tile(&mut ppu, 0x8020, 2);
+    fill_tile(&mut ppu, 0x8030, 3);
+    // Lower X wins even if later in OAM
+    set_sprite(&mut ppu, 0, 16, 12, 0x01, 0x00);
+    set_sprite(&mut ppu, 1, 16, 8, 0x02, 0x00);
+    // Equal X goes to the first in OAM
+    set_sprite(&mut ppu, 2, 32, 8, 0x03, 0x00);
+    set_sprite(&mut ppu, 3, 32, 8, 0x01, 0x00);
+    render_frame(&mut ppu, &mut interrupts, 0x93);
+
+    let frame = ppu.frame_buffer();
+    assert_eq!(frame.pixel(4, 0), DMG_SHADES[2]);
+    assert_eq!(frame.pixel(8, 0), DMG_SHADES[1]);
+    assert_eq!(frame.pixel(0, 16), DMG_SHADES[3]);
+}
+
+#[test]
+fn test_sprite_behind_background() {
+    let (mut ppu, mut interrupts) = create_ppu();
+    fill_tile(&mut ppu, 0x8010, 1);
+    fill_tile(&mut ppu, 0x8020, 3);
+    ppu.vram[0x1800] = 0x01;
+    set_sprite(&mut ppu, 0, 16, 8, 0x02, 0x80);
+    set_sprite(&mut ppu, 1, 16, 16, 0x02, 0x80);
+    render_frame(&mut ppu, &mut interrupts, 0x93);
+
+    let frame = ppu.frame_buffer();
+    assert_eq!(frame.pixel(0, 0), DMG_SHADES[1]);
+    // Background colour 0 does not hide the sprite
+    assert_eq!(frame.pixel(8, 0), DMG_SHADES[3]);
+}
+
+#[test]
+fn test_sprites_per_line_limit() {
+    let (mut ppu, mut interrupts) = create_ppu();
+    fill_tile(&mut ppu, 0x8010, 3);
+    for index in 0..11 {
+        set_sprite(&mut ppu, index, 16, 8 + index as u8 * 8, 0x01, 0x00);
+    }
+    render_frame(&mut ppu, &mut interrupts, 0x93);
+
+    let frame = ppu.frame_buffer();
+    assert_eq!(frame.pixel(72, 0), DMG_SHADES[3]);
+    assert_eq!(frame.pixel(80, 0), DMG_SHADES[0]);
+}
+
+// Fills VRAM and OAM with a scene using scrolling, the window and sprites.
+fn build_scene(ppu: &mut Ppu, interrupts: &mut Interrupts) {
+    for tile in 0..4 {
+        for row in 0..8 {
+            ppu.vram[tile * 16 + row * 2] = (tile * 0x35 + row * 0x11) as u8;
+            ppu.vram[tile * 16 + row * 2 + 1] = (tile * 0x5A + row * 0x23) as u8;
+        }
+    }
+    for index in 0..0x800 {
+        ppu.vram[0x1800 + index] = (index % 7 % 4) as u8;
+    }
+    for index in 0..40 {
+        let attributes = [0x00, 0x20, 0x40, 0x80, 0x10][index % 5];
+        set_sprite(ppu, index, 10 + index as u8 * 4, 3 + index as u8 * 5, index as u8 % 4, attributes);
+    }
+    ppu.write(SCX_ADDRESS, 13, interrupts);
+    ppu.write(SCY_ADDRESS, 5, interrupts);
+    ppu.write(WY_ADDRESS, 90, interrupts);
+    ppu.write(WX_ADDRESS, 60, interrupts);
+}
+
+#[test]
+fn test_fifo_matches_scanline() {
+    let (mut scanline, mut interrupts) = create_ppu();
+    build_scene(&mut scanline, &mut interrupts);
+    render_frame(&mut scanline, &mut interrupts, 0xF3);
+
+    let (mut fifo, mut interrupts) = create_fifo_ppu();
+    build_scene(&mut fifo, &mut interrupts);
+    render_frame(&mut fifo, &mut interrupts, 0xF3);
+
+    for y in 0..SCREEN_HEIGHT {
+        for x in 0..SCREEN_WIDTH {
+            assert_eq!(
+                fifo.frame_buffer().pixel(x, y), scanline.frame_buffer().pixel(x, y),
+                "pixel ({}, {})", x, y
+            );
+        }
+    }
+}
+
+// Counts the dots spent in pixel transfer on the first line.
+fn pixel_transfer_cycles(ppu: &mut Ppu, interrupts: &mut Interrupts, lcdc: u8) -> u16 {
+    ppu.write(LCDC_ADDRESS, lcdc, interrupts);
+    ppu.tick(80, interrupts);
+    let mut cycles = 0;
+    while ppu.mode() == Mode::PixelTransfer {
+        ppu.tick(1, interrupts);
+        cycles += 1;
+    }
+    cycles
+}
+
+#[test]
+fn test_fifo_pixel_transfer_length() {
+    let (mut ppu, mut interrupts) = create_fifo_ppu();
+    assert_eq!(pixel_transfer_cycles(&mut ppu, &mut interrupts, 0x93), 172);
+
+    // Fine scrolling discards pixels at the start of the line
+    let (mut ppu, mut interrupts) = create_fifo_ppu();
+    ppu.write(SCX_ADDRESS, 5, &mut interrupts);
+    assert_eq!(pixel_transfer_cycles(&mut ppu, &mut interrupts, 0x93), 177);
+
+    // Each sprite stalls the FIFOs for 6 dots while it is fetched, after
+    // waiting up to 5 dots for the background fetcher to finish its tile
+    let (mut ppu, mut interrupts) = create_fifo_ppu();
+    set_sprite(&mut ppu, 0, 16, 48, 0x00, 0x00);
+    set_sprite(&mut ppu, 1, 16, 100, 0x00, 0x00);
+    set_sprite(&mut ppu, 2, 16, 100, 0x00, 0x00);
+    assert_eq!(pixel_transfer_cycles(&mut ppu, &mut interrupts, 0x93), 172 + 11 + 7 + 6);
+
+    // The window restarts the fetcher
+    let (mut ppu, mut interrupts) = create_fifo_ppu();
+    ppu.write(WX_ADDRESS, 87, &mut interrupts);
+    assert_eq!(pixel_transfer_cycles(&mut ppu, &mut interrupts, 0xB3), 172 + 6);
+
+    // The scanline renderer always takes the same time
+    let (mut ppu, mut interrupts) = create_ppu();
+    ppu.write(SCX_ADDRESS, 5, &mut interrupts);
+    set_sprite(&mut ppu, 0, 16, 48, 0x00, 0x00);
+    assert_eq!(pixel_transfer_cycles(&mut ppu, &mut interrupts, 0x93), 172);
+}
+
+#[test]
+fn test_fifo_mid_line_palette_change() {
+    let (mut ppu, mut interrupts) = create_fifo_ppu();
+    fill_tile(&mut ppu, 0x8000, 3);
+    ppu.write(BGP_ADDRESS, 0xFF, &mut interrupts);
+    ppu.write(LCDC_ADDRESS, 0x91, &mut interrupts);
+
+    // The first pixel is output 12 dots into pixel transfer
+    ppu.tick(80 + 12 + 50, &mut interrupts);
+    ppu.write(BGP_ADDRESS, 0x00, &mut interrupts);
+    ppu.tick(CYCLES_PER_LINE - 80 - 12 - 50, &mut interrupts);
+
+    let frame = ppu.frame_buffer();
+    assert_eq!(frame.pixel(49, 0), DMG_SHADES[3]);
+    assert_eq!(frame.pixel(50, 0), DMG_SHADES[0]);
+    assert_eq!(frame.pixel(159, 0), DMG_SHADES[0]);
+}
+
+#[test]
+fn test_fifo_mid_line_scroll_change() {
+    let (mut ppu, mut interrupts) = create_fifo_ppu();
+    fill_tile(&mut ppu, 0x8010, 3);
+    ppu.vram[0x1800 + 17] = 0x01;
+    ppu.write(LCDC_ADDRESS, 0x91, &mut interrupts);
+
+    // Coarse scrolling changes the tiles fetched after the write. Pixels 64
+    // to 71 were already fetched when it was made.
+    ppu.tick(80 + 12 + 64, &mut interrupts);
+    ppu.write(SCX_ADDRESS, 64, &mut interrupts);
+    ppu.tick(CYCLES_PER_LINE - 80 - 12 - 64, &mut interrupts);
+
+    let frame = ppu.frame_buffer();
+    assert_eq!(frame.pixel(63, 0), DMG_SHADES[0]);
+    assert_eq!(frame.pixel(72, 0), DMG_SHADES[3]);
+    assert_eq!(frame.pixel(79, 0), DMG_SHADES[3]);
+    assert_eq!(frame.pixel(80, 0), DMG_SHADES[0]);
+}
+
+// Colours used to fill CGB palettes, 4 per palette.
+fn cgb_colour(palette: u8, colour: u8) -> u16 {
+    (palette as u16 * 4 + colour as u16) * 0x0421 % 0x8000
+}
+
+// Fills every background and sprite palette with distinct colours.
+fn fill_cgb_palettes(ppu: &mut Ppu, interrupts: &mut Interrupts) {
+    ppu.write(BCPS_ADDRESS, 0x80, interrupts);
+    ppu.write(OCPS_ADDRESS, 0x80, interrupts);
+    for palette in 0..8 {
+        for colour in 0..4 {
+            let bg = cgb_colour(palette, colour);
+            let sprite = cgb_colour(palette, colour) ^ 0x7FFF;
+            ppu.write(BCPD_ADDRESS, bg as u8, interrupts);
+            ppu.write(BCPD_ADDRESS, (bg >> 8) as u8, interrupts);
+            ppu.write(OCPD_ADDRESS, sprite as u8, interrupts);
+            ppu.write(OCPD_ADDRESS, (sprite >> 8) as u8, interrupts);
+        }
+    }
+}
+
+#[test]
+fn test_cgb_registers_on_dmg() {
+    let (mut ppu, mut interrupts) = create_ppu();
+    ppu.write(VBK_ADDRESS, 0x01, &mut interrupts);
+    ppu.write(BCPS_ADDRESS, 0x80, &mut interrupts);
+    assert_eq!(ppu.read(VBK_ADDRESS), 0xFF);
+    assert_eq!(ppu.read(BCPS_ADDRESS), 0xFF);
+    assert_eq!(ppu.read(OCPD_ADDRESS), 0xFF);
+    ppu.write_vram(0x8000, 0x12);
+    assert_eq!(ppu.vram[0x0000], 0x12);
+}
+
+#[test]
+fn test_cgb_vram_banks() {
+    let (mut ppu, mut interrupts) = create_cgb_ppu(PpuRenderer::Scanline);
+    assert_eq!(ppu.read(VBK_ADDRESS), 0xFE);
+    ppu.write(VBK_ADDRESS, 0xFF, &mut interrupts);
+    assert_eq!(ppu.read(VBK_ADDRESS), 0xFF);
+    ppu.write_vram(0x9FFF, 0x12);
+    assert_eq!(ppu.vram[0x3FFF], 0x12);
+    assert_eq!(ppu.vram[0x1FFF], 0x00);
+}
+
+#[test]
+fn test_cgb_palette_auto_increment() {
+    let (mut ppu, mut interrupts) = create_cgb_ppu(PpuRenderer::Scanline);
+    ppu.write(BCPS_ADDRESS, 0xBE, &mut interrupts);
+    assert_eq!(ppu.read(BCPS_ADDRESS), 0xFE);
+    ppu.write(BCPD_ADDRESS, 0x12, &mut interrupts);
+    ppu.write(BCPD_ADDRESS, 0x34, &mut interrupts);
+    // The index wraps around after the last byte
+    ppu.write(BCPD_ADDRESS, 0x56, &mut interrupts);
+    assert_eq!(ppu.read(BCPS_ADDRESS), 0xC1);
+
+    ppu.write(BCPS_ADDRESS, 0x3E, &mut interrupts);
+    assert_eq!(ppu.read(BCPD_ADDRESS), 0x12);
+    // Reads and writes without auto increment leave the index alone
+    ppu.write(BCPD_ADDRESS, 0x78, &mut interrupts);
+    assert_eq!(ppu.read(BCPS_ADDRESS), 0x7E);
+    assert_eq!(ppu.read(BCPD_ADDRESS), 0x78);
+
+    ppu.write(BCPS_ADDRESS, 0x00, &mut interrupts);
+    assert_eq!(ppu.read(BCPD_ADDRESS), 0x56);
+    // Sprite palettes are separate
+    ppu.write(OCPS_ADDRESS, 0x00, &mut interrupts);
+    assert_eq!(ppu.read(OCPD_ADDRESS), 0xFF);
+}
+
+#[test]
+fn test_cgb_palette_locked_during_pixel_transfer() {
+    let (mut ppu, mut interrupts) = create_cgb_ppu(PpuRenderer::Scanline);
+    ppu.write(OCPS_ADDRESS, 0x80, &mut interrupts);
+    ppu.write(LCDC_ADDRESS, 0x91, &mut interrupts);
+    ppu.tick(80, &mut interrupts);
+    assert_eq!(ppu.read(OCPD_ADDRESS), 0xFF);
+    ppu.write(OCPD_ADDRESS, 0x00, &mut interrupts);
+    assert_eq!(ppu.read(OCPS_ADDRESS), 0xC1);
+
+    ppu.tick(172, &mut interrupts);
+    ppu.write(OCPS_ADDRESS, 0x00, &mut interrupts);
+    assert_eq!(ppu.read(OCPD_ADDRESS), 0xFF);
+}
+
+#[test]
+fn test_cgb_bg_attributes() {
+    let (mut ppu, mut interrupts) = create_cgb_ppu(PpuRenderer::Scanline);
+    fill_cgb_palettes(&mut ppu, &mut interrupts);
+    // Tile 1 in bank 0 is colour 1, tile 1 in bank 1 is colour 2 in its top
+    // left pixel only
+    fill_tile(&mut ppu, 0x8010, 1);
+    ppu.vram[0x2011] = 0x80;
+    ppu.vram[0x1800..0x1803].copy_from_slice(&[0x01, 0x01, 0x01]);
+    ppu.vram[0x3800..0x3803].copy_from_slice(&[0x03, 0x0D, 0x68]);
+    render_frame(&mut ppu, &mut interrupts, 0x91);
+
+    let frame = ppu.frame_buffer();
+    assert_eq!(frame.pixel(0, 0), cgb_colour(3, 1));
+    // Bank 1
+    assert_eq!(frame.pixel(8, 0), cgb_colour(5, 2));
+    assert_eq!(frame.pixel(9, 0), cgb_colour(5, 0));
+    // Flipped in both directions
+    assert_eq!(frame.pixel(23, 7), cgb_colour(0, 2));
+    assert_eq!(frame.pixel(16, 0), cgb_colour(0, 0));
+}
+
+#[test]
+fn test_cgb_sprites() {
+    let (mut ppu, mut interrupts) = create_cgb_ppu(PpuRenderer::Scanline);
+    fill_cgb_palettes(&mut ppu, &mut interrupts);
+    fill_tile(&mut ppu, 0x8010, 1);
+    fill_tile(&mut ppu, 0xA010, 3);
+    // The earlier sprite in OAM wins despite its higher X
+    set_sprite(&mut ppu, 0, 16, 12, 0x01, 0x0A);
+    set_sprite(&mut ppu, 1, 16, 8, 0x01, 0x17);
+    render_frame(&mut ppu, &mut interrupts, 0x93);
+
+    let sprite_colour = |palette, colour| cgb_colour(palette, colour) ^ 0x7FFF;
+    let frame = ppu.frame_buffer();
+    assert_eq!(frame.pixel(0, 0), sprite_colour(7, 1));
+    assert_eq!(frame.pixel(4, 0), sprite_colour(2, 3));
+    assert_eq!(frame.pixel(11, 0), sprite_colour(2, 3));
+}
+
+#[test]
+fn test_cgb_bg_priority() {
+    for &renderer in &[PpuRenderer::Scanline, PpuRenderer::PixelFifo] {
+        let (mut ppu, mut interrupts) = create_cgb_ppu(renderer);
+        fill_cgb_palettes(&mut ppu, &mut interrupts);
+        fill_tile(&mut ppu, 0x8010, 1);
+        fill_tile(&mut ppu, 0x8020, 2);
+        // Background tile 1 with priority, and tile 0 (colour 0) with priority
+        ppu.vram[0x1800] = 0x01;
+        ppu.vram[0x1840] = 0x01;
+        ppu.vram[0x3800] = 0x80;
+        ppu.vram[0x3801] = 0x80;
+        set_sprite(&mut ppu, 0, 16, 8, 0x02, 0x00);
+        set_sprite(&mut ppu, 1, 16, 16, 0x02, 0x00);
+        set_sprite(&mut ppu, 2, 24, 8, 0x02, 0x00);
+        render_frame(&mut ppu, &mut interrupts, 0x93);
+
+        let frame = ppu.frame_buffer();
+        assert_eq!(frame.pixel(0, 0), cgb_colour(0, 1));
+        assert_eq!(frame.pixel(8, 0), cgb_colour(0, 2) ^ 0x7FFF);
+        assert_eq!(frame.pixel(0, 8), cgb_colour(0, 2) ^ 0x7FFF);
+
+        // Clearing LCDC bit 0 puts sprites above everything but still shows
+        // the background
+        ppu.write(LCDC_ADDRESS, 0x00, &mut interrupts);
+        ppu.frame_ready();
+        render_frame(&mut ppu, &mut interrupts, 0x92);
+        let frame = ppu.frame_buffer();
+        assert_eq!(frame.pixel(0, 0), cgb_colour(0, 2) ^ 0x7FFF);
+        assert_eq!(frame.pixel(0, 16), cgb_colour(0, 1));
+    }
+}
+
+#[test]
+fn test_cgb_fifo_matches_scanline() {
+    let render = |renderer| {
+        let (mut ppu, mut interrupts) = create_cgb_ppu(renderer);
+        build_scene(&mut ppu, &mut interrupts);
+        fill_cgb_palettes(&mut ppu, &mut interrupts);
+        for index in 0..0x800 {
+            ppu.vram[0x3800 + index] = (index * 0x2B % 0x100) as u8;
+        }
+        for index in 0..0x40 {
+            ppu.vram[0x2000 + index] = ppu.vram[index] ^ 0xA5;
+        }
+        for index in 0..40 {
+            ppu.oam[index * 4 + 3] ^= (index % 16) as u8;
+        }
+        render_frame(&mut ppu, &mut interrupts, 0xF3);
+        ppu
+    };
+    let scanline = render(PpuRenderer::Scanline);
+    let fifo = render(PpuRenderer::PixelFifo);
+
+    for y in 0..SCREEN_HEIGHT {
+        for x in 0..SCREEN_WIDTH {
+            assert_eq!(
+                fifo.frame_buffer().pixel(x, y), scanline.frame_buffer().pixel(x, y),
+                "pixel ({}, {})", x, y
+            );
+        }
+    }
+}
@@ -0,0 +1,70 @@
+extern crate gameboy;
+use gameboy::operations::Reg8;
+use gameboy::registers::*;
+
+#[test]
+fn test_flags() {
+    let mut registers = Registers::new();
+    registers.set_f(0x00);
+
+    for flag in &[Flag::Z, Flag::N, Flag::H, Flag::C] {
+        assert!(!registers.flag(*flag));
+        registers.set_flag(*flag, true);
+        assert!(registers.flag(*flag));
+    }
+    assert_eq!(registers.f(), 0xF0);
+
+    registers.set_flag(Flag::H, false);
+    assert_eq!(registers.f(), 0xD0);
+
+    registers.set_flags(false, true, false, true);
+    assert_eq!(registers.f(), 0x50);
+}
+
+#[test]
+fn test_flags_low_nibble() {
+    let mut registers = Registers::new();
+
+    registers.set_f(0x0F);
+    assert_eq!(registers.f(), 0x00);
+    registers.set_af(0x12FF);
+    assert_eq!(registers.a, 0x12);
+    assert_eq!(registers.f(), 0xF0);
+    assert_eq!(registers.af(), 0x12F0);
+}
+
+#[test]
+fn test_register_pairs() {
+    let mut registers = Registers::new();
+
+    registers.set_bc(0x1234);
+    assert_eq!((registers.b, registers.c), (0x12, 0x34));
+    assert_eq!(registers.bc(), 0x1234);
+
+    registers.set_de(0x5678);
+    assert_eq!((registers.d, registers.e), (0x56, 0x78));
+    assert_eq!(registers.de(), 0x5678);
+
+    registers.set_hl(0x9ABC);
+    assert_eq!((registers.h, registers.l), (0x9A, 0xBC));
+    assert_eq!(registers.hl(), 0x9ABC);
+}
+
+#[test]
+fn test_index_reg8() {
+    let mut registers = Registers::new();
+    let all = [Reg8::A, Reg8::B, Reg8::C, Reg8::D, Reg8::E, Reg8::H, Reg8::L];
+
+    for (value, reg) in all.iter().enumerate() {
+        registers[*reg] = value as u8;
+    }
+
+    assert_eq!(registers.a, 0);
+    assert_eq!(registers.b, 1);
+    assert_eq!(registers.c, 2);
+    assert_eq!(registers.d, 3);
+    assert_eq!(registers.e, 4);
+    assert_eq!(registers.h, 5);
+    assert_eq!(registers.l, 6);
+    assert_eq!(registers[Reg8::H], 5);
+}
@@ -0,0 +1,152 @@
+extern crate gameboy;
+
+use gameboy::rom::*;
+use gameboy::GameboyErrorKind;
+
+// Creates a ROM with the logo, given title bytes and a valid header checksum
+fn rom_with_title(title: &[u8]) -> Vec<u8> {
+    let mut data = vec![0; MINIMUM_CARTRIDGE_SIZE_BYTES];
+    data[0x0100..0x0104].copy_from_slice(&[0x00, 0xC3, 0x50, 0x01]);
+    data[0x0104..0x0134].copy_from_slice(&NINTENDO_LOGO_BYTES);
+    data[0x0134..0x0134 + title.len()].copy_from_slice(title);
+    fix_header_checksum(&mut data);
+    data
+}
+
+fn fix_header_checksum(data: &mut [u8]) {
+    data[0x014D] = data[0x0134..=0x014C].iter()
+        .fold(0u8, |checksum, byte| checksum.wrapping_sub(*byte).wrapping_sub(1));
+}
+
+fn error_kind(data: &[u8]) -> GameboyErrorKind {
+    CartridgeHeader::parse(data).unwrap_err().kind
+}
+
+#[test]
+fn test_header_fields() {
+    let mut data = rom_with_title(b"POKEMON RED");
+    data[0x0146] = 0x03;
+    data[0x0147] = 0x13;
+    data[0x0148] = 0x05;
+    data[0x0149] = 0x03;
+    data[0x014A] = 0x01;
+    data[0x014B] = 0x01;
+    data[0x014C] = 0x02;
+    data[0x014E] = 0x91;
+    data[0x014F] = 0xE6;
+    fix_header_checksum(&mut data);
+
+    let header = CartridgeHeader::parse(&data).unwrap();
+    assert_eq!(header.entry_point, [0x00, 0xC3, 0x50, 0x01]);
+    assert_eq!(header.title, "POKEMON RED");
+    assert_eq!(header.manufacturer_code, None);
+    assert_eq!(header.cgb_support, CgbSupport::None);
+    assert_eq!(header.licensee, Licensee::Old(0x01));
+    assert!(header.sgb);
+    assert_eq!(header.kind(), Some(CartridgeKind::Mbc3RamBattery));
+    assert_eq!(header.rom_banks, 64);
+    assert_eq!(header.rom_size_bytes, 0x100000);
+    assert_eq!(header.ram_banks, 4);
+    assert_eq!(header.ram_size_bytes, 0x8000);
+    assert_eq!(header.destination, Destination::Overseas);
+    assert_eq!(header.mask_rom_version, 0x02);
+    assert_eq!(header.global_checksum, 0x91E6);
+    assert_eq!(header.validate(), Ok(()));
+}
+
+#[test]
+fn test_header_cgb_title_and_manufacturer_code() {
+    let data = rom_with_title(b"ZELDA DX\0\0\0AZ7E\xC0");
+    let header = CartridgeHeader::parse(&data).unwrap();
+    assert_eq!(header.title, "ZELDA DX");
+    assert_eq!(header.manufacturer_code, Some("AZ7E".to_string()));
+    assert_eq!(header.cgb_support, CgbSupport::Required);
+
+    // A 15 character title is not mistaken for a manufacturer code
+    let data = rom_with_title(b"MARIO tennis ok\x80");
+    let header = CartridgeHeader::parse(&data).unwrap();
+    assert_eq!(header.title, "MARIO tennis ok");
+    assert_eq!(header.manufacturer_code, None);
+    assert_eq!(header.cgb_support, CgbSupport::Enhanced);
+
+    // Without a CGB flag the title uses all 16 bytes
+    let data = rom_with_title(b"SIXTEEN CHARS!!!");
+    assert_eq!(CartridgeHeader::parse(&data).unwrap().title, "SIXTEEN CHARS!!!");
+}
+
+#[test]
+fn test_header_high_bytes_in_title() {
+    let data = rom_with_title(b"\xB0\xC3\xCCGAME");
+    let header = CartridgeHeader::parse(&data).unwrap();
+    assert_eq!(header.title, "\u{FFFD}\u{FFFD}\u{FFFD}GAME");
+    assert_eq!(Rom::new(data).unwrap().name(), "\u{FFFD}\u{FFFD}\u{FFFD}GAME");
+}
+
+#[test]
+fn test_header_new_licensee() {
+    let mut data = rom_with_title(b"GAME");
+    data[0x0144..0x0146].copy_from_slice(b"01");
+    data[0x014B] = 0x33;
+    let header = CartridgeHeader::parse(&data).unwrap();
+    assert_eq!(header.licensee, Licensee::New("01".to_string()));
+    assert_eq!(header.licensee.publisher(), Some("Nintendo Research & Development 1"));
+}
+
+#[test]
+fn test_header_unofficial_sizes() {
+    let mut data = rom_with_title(b"GAME");
+    data[0x0148] = 0x52;
+    data[0x0149] = 0x01;
+    let header = CartridgeHeader::parse(&data).unwrap();
+    assert_eq!(header.rom_banks, 72);
+    assert_eq!(header.ram_banks, 1);
+    assert_eq!(header.ram_size_bytes, 0x0800);
+}
+
+#[test]
+fn test_header_parse_errors() {
+    assert_eq!(error_kind(&[0; 0x014F]), GameboyErrorKind::CartridgeTooSmall(0x014F));
+
+    let mut data = rom_with_title(b"GAME");
+    data[0x0148] = 0x09;
+    assert_eq!(error_kind(&data), GameboyErrorKind::UnknownRomSize(0x09));
+
+    let mut data = rom_with_title(b"GAME");
+    data[0x0149] = 0x06;
+    assert_eq!(error_kind(&data), GameboyErrorKind::UnknownRamSize(0x06));
+
+    let mut data = rom_with_title(b"GAME");
+    data[0x014A] = 0x02;
+    assert_eq!(error_kind(&data), GameboyErrorKind::UnknownDestination(0x02));
+}
+
+#[test]
+fn test_header_validation_errors() {
+    let mut data = rom_with_title(b"GAME");
+    data[0x0104] = 0x00;
+    let header = CartridgeHeader::parse(&data).unwrap();
+    assert_eq!(header.validate().unwrap_err().kind, GameboyErrorKind::InvalidLogo);
+
+    let mut data = rom_with_title(b"GAME");
+    let checksum = data[0x014D];
+    data[0x014D] = checksum.wrapping_add(1);
+    let header = CartridgeHeader::parse(&data).unwrap();
+    assert_eq!(
+        header.validate().unwrap_err().kind,
+        GameboyErrorKind::HeaderChecksumMismatch(checksum.wrapping_add(1), checksum)
+    );
+    assert!(!Rom::new(data).unwrap().has_valid_header_checksum());
+}
+
+#[test]
+fn test_rom_cartridge_header_agrees_with_rom() {
+    let mut data = rom_with_title(b"GAME");
+    data[0x0147] = 0x1B;
+    data[0x0149] = 0x04;
+    fix_header_checksum(&mut data);
+    let rom = Rom::new(data).unwrap();
+    let header = rom.cartridge_header().unwrap();
+    assert_eq!(header.kind(), rom.kind());
+    assert_eq!(header.ram_size_bytes, rom.ram_size_bytes());
+    assert_eq!(header.validate().is_ok(), rom.has_valid_header_checksum() && rom.has_valid_logo());
+}
@@ -0,0 +1,115 @@
+extern crate gameboy;
+
+use gameboy::cartridge::*;
+use gameboy::rom::Rom;
+use gameboy::save::*;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Creates an empty directory for a test's save files
+fn test_directory(name: &str) -> PathBuf {
+    let directory = env::temp_dir().join(format!("gameboy-save-tests-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+fn cartridge_with_ram(battery: bool) -> RomRam {
+    RomRam::new(vec![0; 0x8000], 0x2000, battery)
+}
+
+#[test]
+fn test_save_file_for_rom() {
+    let save_file = SaveFile::for_rom("games/pokemon.gbc");
+    assert_eq!(save_file.path(), PathBuf::from("games/pokemon.sav").as_path());
+}
+
+#[test]
+fn test_load_missing_save_file() {
+    let directory = test_directory("missing");
+    let mut save_file = SaveFile::for_rom(directory.join("game.gb"));
+    let mut cartridge = cartridge_with_ram(true);
+    assert!(!save_file.load(&mut cartridge).unwrap());
+}
+
+#[test]
+fn test_flush_and_load_save_file() {
+    let directory = test_directory("flush");
+    let mut save_file = SaveFile::for_rom(directory.join("game.gb"));
+    let mut cartridge = cartridge_with_ram(true);
+    cartridge.write_byte(0xA010, 0x42);
+    assert!(save_file.flush(&cartridge).unwrap());
+    assert_eq!(fs::read(directory.join("game.sav")).unwrap()[0x10], 0x42);
+    assert!(!directory.join("game.sav.tmp").exists());
+
+    // Unchanged RAM is not written again
+    assert!(!save_file.flush(&cartridge).unwrap());
+
+    let mut restored = cartridge_with_ram(true);
+    let mut save_file = SaveFile::for_rom(directory.join("game.gb"));
+    assert!(save_file.load(&mut restored).unwrap());
+    assert_eq!(restored.read_byte(0xA010), 0x42);
+    assert!(!save_file.flush(&restored).unwrap());
+    restored.write_byte(0xA010, 0x43);
+    assert!(save_file.flush(&restored).unwrap());
+}
+
+#[test]
+fn test_no_save_without_battery() {
+    let directory = test_directory("battery");
+    let mut save_file = SaveFile::for_rom(directory.join("game.gb"));
+    let mut cartridge = cartridge_with_ram(false);
+    cartridge.write_byte(0xA000, 0x01);
+    assert!(!save_file.flush(&cartridge).unwrap());
+    assert!(!directory.join("game.sav").exists());
+
+    fs::write(directory.join("game.sav"), [0x02]).unwrap();
+    assert!(!save_file.load(&mut cartridge).unwrap());
+    assert_eq!(cartridge.read_byte(0xA000), 0x01);
+}
+
+#[test]
+fn test_write_file_atomic_replaces_file() {
+    let directory = test_directory("atomic");
+    let path = directory.join("data.sav");
+    fs::write(&path, [0xFF; 16]).unwrap();
+    write_file_atomic(&path, &[0x01, 0x02]).unwrap();
+    assert_eq!(fs::read(&path).unwrap(), vec![0x01, 0x02]);
+    assert_eq!(fs::read_dir(&directory).unwrap().count(), 1);
+}
+
+#[test]
+fn test_save_file_rtc_footer() {
+    let directory = test_directory("rtc");
+    let mut rom = vec![0; 0x8000];
+    rom[0x0147] = 0x10;
+    rom[0x0149] = 0x02;
+
+    // Saved an hour ago at 10:00:00 on day 0
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let footer = RtcFooter { live: [0, 0, 10, 0, 0], latched: [0; 5], timestamp: now - 60 * 60 };
+    let mut data = vec![0x99; 0x2000];
+    data.extend(footer.to_bytes());
+    fs::write(directory.join("clock.sav"), &data).unwrap();
+
+    let mut cartridge = Rom::new(rom).unwrap().into_cartridge().unwrap();
+    let mut save_file = SaveFile::for_rom(directory.join("clock.gb"));
+    assert!(save_file.load(cartridge.as_mut()).unwrap());
+    assert_eq!(cartridge.save_ram(), vec![0x99; 0x2000]);
+    cartridge.write_byte(0x0000, 0x0A);
+    cartridge.write_byte(0x6000, 0x00);
+    cartridge.write_byte(0x6000, 0x01);
+    cartridge.write_byte(0x4000, RTC_HOURS);
+    assert_eq!(cartridge.read_byte(0xA000), 11);
+
+    cartridge.write_byte(0x4000, RTC_DAYS_LOW);
+    cartridge.write_byte(0xA000, 0x07);
+    assert!(save_file.flush(cartridge.as_ref()).unwrap());
+    let saved = fs::read(directory.join("clock.sav")).unwrap();
+    assert_eq!(saved.len(), 0x2000 + RTC_FOOTER_SIZE_BYTES);
+    let footer = RtcFooter::from_bytes(&saved[0x2000..]).unwrap();
+    assert_eq!(footer.live[3], 0x07);
+    assert!(footer.timestamp >= now);
+}
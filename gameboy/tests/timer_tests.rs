@@ -0,0 +1,139 @@
+extern crate gameboy;
+use gameboy::cartridge::{Cartridge, RomOnly};
+use gameboy::memory::Memory;
+use gameboy::timer::*;
+
+// Creates a timer with the divider reset and the given TAC value.
+fn new_timer(tac: u8) -> Timer {
+    let mut timer = Timer::new();
+    timer.write(DIV_ADDRESS, 0x00);
+    timer.write(TAC_ADDRESS, tac);
+    timer
+}
+
+#[test]
+fn test_initial_state() {
+    let timer = Timer::new();
+    assert_eq!(timer.read(DIV_ADDRESS), 0xAB);
+    assert_eq!(timer.read(TIMA_ADDRESS), 0x00);
+    assert_eq!(timer.read(TMA_ADDRESS), 0x00);
+    assert_eq!(timer.read(TAC_ADDRESS), 0xF8);
+}
+
+#[test]
+fn test_div() {
+    let mut timer = new_timer(0x00);
+    timer.tick(252);
+    assert_eq!(timer.read(DIV_ADDRESS), 0x00);
+    timer.tick(4);
+    assert_eq!(timer.read(DIV_ADDRESS), 0x01);
+    timer.tick(256 * 0xFF);
+    assert_eq!(timer.read(DIV_ADDRESS), 0x00);
+}
+
+#[test]
+fn test_tima_frequencies() {
+    for &(tac, period) in &[(0x04, 1024), (0x05, 16), (0x06, 64), (0x07, 256)] {
+        let mut timer = new_timer(tac);
+        timer.tick(period - 4);
+        assert_eq!(timer.read(TIMA_ADDRESS), 0x00, "TAC {:02X}", tac);
+        timer.tick(4);
+        assert_eq!(timer.read(TIMA_ADDRESS), 0x01, "TAC {:02X}", tac);
+        timer.tick(period * 9);
+        assert_eq!(timer.read(TIMA_ADDRESS), 0x0A, "TAC {:02X}", tac);
+    }
+}
+
+#[test]
+fn test_tima_disabled() {
+    let mut timer = new_timer(0x01);
+    timer.tick(1024);
+    assert_eq!(timer.read(TIMA_ADDRESS), 0x00);
+}
+
+#[test]
+fn test_overflow_delayed_reload() {
+    let mut timer = new_timer(0x05);
+    timer.write(TIMA_ADDRESS, 0xFF);
+    timer.write(TMA_ADDRESS, 0x10);
+
+    // TIMA reads zero for one M-cycle before TMA is loaded
+    assert!(!timer.tick(16));
+    assert_eq!(timer.read(TIMA_ADDRESS), 0x00);
+    assert!(timer.tick(4));
+    assert_eq!(timer.read(TIMA_ADDRESS), 0x10);
+}
+
+#[test]
+fn test_overflow_write_cancels_reload() {
+    let mut timer = new_timer(0x05);
+    timer.write(TIMA_ADDRESS, 0xFF);
+    timer.write(TMA_ADDRESS, 0x10);
+
+    assert!(!timer.tick(16));
+    timer.write(TIMA_ADDRESS, 0x33);
+    assert!(!timer.tick(4));
+    assert_eq!(timer.read(TIMA_ADDRESS), 0x33);
+}
+
+#[test]
+fn test_reload_cycle_writes() {
+    let mut timer = new_timer(0x05);
+    timer.write(TIMA_ADDRESS, 0xFF);
+    timer.write(TMA_ADDRESS, 0x10);
+    timer.tick(20);
+
+    // TIMA writes are ignored while TMA is being loaded
+    timer.write(TIMA_ADDRESS, 0x33);
+    assert_eq!(timer.read(TIMA_ADDRESS), 0x10);
+
+    // TMA writes are also copied into TIMA
+    timer.write(TMA_ADDRESS, 0x44);
+    assert_eq!(timer.read(TIMA_ADDRESS), 0x44);
+
+    timer.tick(4);
+    timer.write(TIMA_ADDRESS, 0x55);
+    assert_eq!(timer.read(TIMA_ADDRESS), 0x55);
+}
+
+#[test]
+fn test_div_write_glitch() {
+    let mut timer = new_timer(0x05);
+    timer.tick(8);
+    assert_eq!(timer.read(TIMA_ADDRESS), 0x00);
+
+    // Selected bit is high, so resetting the divider is a falling edge
+    timer.write(DIV_ADDRESS, 0x00);
+    assert_eq!(timer.read(TIMA_ADDRESS), 0x01);
+
+    // Selected bit is low, no increment
+    timer.write(DIV_ADDRESS, 0x00);
+    assert_eq!(timer.read(TIMA_ADDRESS), 0x01);
+}
+
+#[test]
+fn test_tac_write_glitch() {
+    let mut timer = new_timer(0x05);
+    timer.tick(8);
+
+    // Switching to a divider bit which is low is a falling edge
+    timer.write(TAC_ADDRESS, 0x04);
+    assert_eq!(timer.read(TIMA_ADDRESS), 0x01);
+
+    // As is disabling the timer while the selected bit is high
+    timer.write(TAC_ADDRESS, 0x05);
+    timer.write(TAC_ADDRESS, 0x01);
+    assert_eq!(timer.read(TIMA_ADDRESS), 0x02);
+}
+
+#[test]
+fn test_memory_interrupt() {
+    let cartridge: Box<dyn Cartridge> = Box::new(RomOnly::new(vec![0; 0x8000]));
+    let mut memory = Memory::new(cartridge);
+
+    memory.write_byte(DIV_ADDRESS, 0x00);
+    memory.write_byte(TIMA_ADDRESS, 0xFF);
+    memory.write_byte(TAC_ADDRESS, 0x05);
+    memory.tick(20);
+    assert_eq!(memory.read_byte(0xFF0F), 0xE4);
+}
@@ -5,6 +5,12 @@ use std::env;
 use std::collections::HashMap;
 use gameboy::*;
 
+/// Converts a prefix into the register, condition or vector it encodes.
+type ConvFn = Box<dyn Fn(u8) -> u8>;
+/// Table entry for opcodes with two register operands.
+type DualOpEntry<'a> = (u8, &'a str, &'a str, ConvFn, ConvFn);
+
+#[allow(dead_code)]
 fn expected_value(regname: &str) -> u8 {
     match regname {
         "00" => 0x00,
@@ -37,10 +43,10 @@ fn expected_value(regname: &str) -> u8 {
     }
 }
 
+#[allow(dead_code)]
 fn opcode_tests() {
-    let mut opcode_table: HashMap<&str, Vec<(u8, &str, Box<(Fn(u8) -> u8)>)>> = HashMap::new();
-    let mut dualop_opcode_table: HashMap<
-            &str, Vec<(u8, &str, &str, Box<(Fn(u8) -> u8)>, Box<(Fn(u8) -> u8)>)>> = HashMap::new();
+    let mut opcode_table: HashMap<&str, Vec<(u8, &str, ConvFn)>> = HashMap::new();
+    let mut dualop_opcode_table: HashMap<&str, Vec<DualOpEntry>> = HashMap::new();
     let mut covered_prefixes = vec![];
     let expected_gaps = vec![0xD3, 0xE3, 0xE4, 0xF4, 0xDB, 0xEB, 0xEC, 0xFC, 0xDD, 0xED, 0xFD];
 
@@ -331,11 +337,32 @@ fn main() -> io::Result<()> {
     if let Ok(rom) = gameboy::rom::Rom::new(result) {
         println!("NAME: {:?}", rom.name());
         println!("KIND: {:?}", rom.kind());
+        match rom.cartridge_header() {
+            Ok(header) => {
+                println!("MANUFACTURER: {:?}", header.manufacturer_code);
+                println!("CGB: {:?}", header.cgb_support);
+                println!("SGB: {:?}", header.sgb);
+                println!("PUBLISHER: {:?} ({:?})", header.licensee.publisher(), header.licensee);
+                println!("ROM: {} banks, {} bytes", header.rom_banks, header.rom_size_bytes);
+                println!("RAM: {} banks, {} bytes", header.ram_banks, header.ram_size_bytes);
+                println!("DESTINATION: {:?}", header.destination);
+                println!("VERSION: {}", header.mask_rom_version);
+                if let Err(error) = header.validate() {
+                    println!("INVALID HEADER: {:?}", error.kind);
+                }
+            },
+            Err(error) => println!("INVALID HEADER: {:?}", error.kind)
+        }
         println!("VALID LOGO: {:?}", rom.has_valid_logo());
         println!("VALID HEADER CHECKSUM: {:?}", rom.has_valid_header_checksum());
         println!("VALID GLOBAL CHECKSUM: {:?}", rom.has_valid_global_checksum());
         if let Some(cartridge) = rom.into_cartridge() {
-            let _memory = gameboy::memory::Memory::new(cartridge);
+            let mut memory = gameboy::memory::Memory::new(cartridge);
+            let mut save_file = gameboy::save::SaveFile::for_rom(&args[1]);
+            if save_file.load(memory.cartridge.as_mut())? {
+                println!("LOADED SAVE: {}", save_file.path().display());
+            }
+            save_file.flush(memory.cartridge.as_ref())?;
         } else {
             println!("error: unsupported ROM type.")
         }